@@ -0,0 +1,17 @@
+create table webhook
+(
+    id         serial primary key,
+    user_id    text                                not null
+        constraint webhook_user_id_fk
+            references "user",
+    url        text                                not null,
+    secret     text                                not null,
+    events     text      default ''                not null,
+    is_global  boolean   default false             not null,
+    created_at timestamp default current_timestamp not null,
+    updated_at timestamp default current_timestamp not null,
+    constraint webhook_user_id_url_unique
+        unique (user_id, url)
+);
+
+create index webhook_is_global_idx on webhook (is_global);
@@ -0,0 +1,15 @@
+create table api_token
+(
+    id           serial primary key,
+    user_id      text                                not null
+        constraint api_token_user_id_fk
+            references "user",
+    token_hash   text                                not null,
+    last_used_at timestamp,
+    created_at   timestamp default current_timestamp not null,
+    updated_at   timestamp default current_timestamp not null,
+    constraint api_token_user_id_unique
+        unique (user_id),
+    constraint api_token_token_hash_unique
+        unique (token_hash)
+);
@@ -0,0 +1,3 @@
+alter table "user" add skipped_skippage bigint default 0 not null;
+alter table "user" add skipped_ai_slop bigint default 0 not null;
+alter table "user" add ai_slop_detected bigint default 0 not null;
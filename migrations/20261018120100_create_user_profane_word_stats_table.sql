@@ -0,0 +1,13 @@
+create table user_profane_word_stats
+(
+    id          serial primary key,
+    user_id     text                                not null
+        constraint user_profane_word_stats_user_id_fk
+            references "user",
+    word        text                                not null,
+    occurrences integer   default 0                 not null,
+    created_at  timestamp default current_timestamp not null,
+    updated_at  timestamp default current_timestamp not null,
+    constraint user_profane_word_stats_user_word_unique
+        unique (user_id, word)
+);
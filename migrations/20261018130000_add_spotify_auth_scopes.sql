@@ -0,0 +1,6 @@
+alter table spotify_auth
+    add scopes text default '' not null;
+
+-- Users logged in before scopes were stored granted the full set
+update spotify_auth
+set scopes = 'app-remote-control playlist-modify-private playlist-modify-public playlist-read-collaborative playlist-read-private ugc-image-upload user-follow-modify user-follow-read user-library-modify user-library-read user-modify-playback-state user-read-currently-playing user-read-email user-read-playback-position user-read-playback-state user-read-private user-read-recently-played user-top-read';
@@ -0,0 +1,14 @@
+create table track_analysis
+(
+    id         serial
+        constraint track_analysis_pk
+            primary key,
+    track_id   text                                not null,
+    locale     text                                not null,
+    model      text                                not null,
+    analysis   text                                not null,
+    created_at timestamp default CURRENT_TIMESTAMP not null,
+    updated_at timestamp default CURRENT_TIMESTAMP not null,
+    constraint track_analysis_track_id_locale_model
+        unique (track_id, locale, model)
+);
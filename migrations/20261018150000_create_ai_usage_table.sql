@@ -0,0 +1,20 @@
+create table ai_usage
+(
+    id                serial
+        constraint ai_usage_pk
+            primary key,
+    user_id           text                                not null
+        constraint ai_usage_user_id_fk
+            references "user"
+            on delete cascade,
+    feature           text                                not null,
+    backend           text                                not null,
+    model             text                                not null,
+    prompt_tokens     bigint                              not null,
+    completion_tokens bigint                              not null,
+    total_tokens      bigint                              not null,
+    created_at        timestamp default CURRENT_TIMESTAMP not null
+);
+
+create index ai_usage_user_id_created_at_index
+    on ai_usage (user_id, created_at);
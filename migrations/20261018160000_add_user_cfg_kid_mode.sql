@@ -0,0 +1 @@
+alter table "user" add cfg_kid_mode boolean default false not null;
@@ -0,0 +1,2 @@
+alter table "user" add profile text default 'standard' not null;
+alter table "user" add settings_pin_hash text;
@@ -0,0 +1 @@
+alter table "user" add cfg_skip_explicit boolean default false not null;
@@ -0,0 +1,17 @@
+create table recommendasion_feedback
+(
+    id         serial
+        constraint recommendasion_feedback_pk
+            primary key,
+    user_id    text                                not null
+        constraint recommendasion_feedback_user_id_fk
+            references "user"
+            on delete cascade,
+    track_id   text                                not null,
+    track_name text                                not null,
+    outcome    text                                not null,
+    created_at timestamp default CURRENT_TIMESTAMP not null,
+    updated_at timestamp default CURRENT_TIMESTAMP not null,
+    constraint recommendasion_feedback_user_id_track_id
+        unique (user_id, track_id)
+);
@@ -0,0 +1,14 @@
+create table track_translation
+(
+    id          serial
+        constraint track_translation_pk
+            primary key,
+    track_id    text                                not null,
+    language    text                                not null,
+    model       text                                not null,
+    translation text                                not null,
+    created_at  timestamp default CURRENT_TIMESTAMP not null,
+    updated_at  timestamp default CURRENT_TIMESTAMP not null,
+    constraint track_translation_track_id_language
+        unique (track_id, language)
+);
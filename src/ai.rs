@@ -0,0 +1,303 @@
+//! OpenAI-compatible LLM backends with per-feature model chains.
+//!
+//! Every feature has its own list of `backend:model` targets, the next one is tried when the
+//! previous fails. Local servers like Ollama or llama.cpp work through their OpenAI-compatible API
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use async_openai::config::OpenAIConfig;
+use async_openai::types::chat::{
+    CreateChatCompletionRequest,
+    CreateChatCompletionResponse,
+    CreateChatCompletionStreamResponse,
+};
+use futures::StreamExt as _;
+use futures::stream::BoxStream;
+use strum::IntoEnumIterator as _;
+use strum_macros::{AsRefStr, EnumIter};
+
+use crate::infrastructure::error::RustifyError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, AsRefStr, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum AIFeature {
+    Analysis,
+    WordDefinition,
+    Recommendasion,
+    Chat,
+    Translation,
+}
+
+pub struct Backend {
+    name: String,
+    client: async_openai::Client<OpenAIConfig>,
+}
+
+impl Backend {
+    #[must_use]
+    pub fn new(name: &str, client: async_openai::Client<OpenAIConfig>) -> Self {
+        Self {
+            name: name.to_owned(),
+            client,
+        }
+    }
+}
+
+/// Model of the backend used for a feature
+pub struct Target {
+    backend: Arc<Backend>,
+    model: String,
+}
+
+impl Target {
+    #[must_use]
+    pub fn backend(&self) -> &str {
+        &self.backend.name
+    }
+
+    #[must_use]
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+pub struct AIConfig {
+    targets: HashMap<AIFeature, Vec<Target>>,
+    monthly_token_quota: u64,
+}
+
+/// Parses comma separated `backend:model` list, model can contain colons itself (`local:llama3.1:8b`)
+fn parse_targets(targets: &str) -> anyhow::Result<Vec<(&str, &str)>> {
+    targets
+        .split(',')
+        .map(str::trim)
+        .filter(|target| !target.is_empty())
+        .map(|target| {
+            target
+                .split_once(':')
+                .filter(|(backend, model)| !backend.is_empty() && !model.is_empty())
+                .with_context(|| format!("AI target must be backend:model, got {target}"))
+        })
+        .collect()
+}
+
+/// Value of a string field from incomplete JSON, as it is being streamed.
+/// Returns `None` until the value starts
+#[must_use]
+pub fn partial_string_field(json: &str, field: &str) -> Option<String> {
+    let key = format!("\"{field}\"");
+    let rest = &json[json.find(&key)? + key.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let mut chars = rest.strip_prefix('"')?.chars();
+
+    let mut value = String::new();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '"' => break,
+            '\\' => {
+                let escaped = match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('b' | 'f') => continue,
+                    Some('u') => {
+                        let code: String = chars.by_ref().take(4).collect();
+
+                        // Incomplete or surrogate pairs, skip them
+                        match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                            Some(char) if code.len() == 4 => char,
+                            _ => continue,
+                        }
+                    },
+                    Some(char) => char,
+                    None => break,
+                };
+
+                value.push(escaped);
+            },
+            char => value.push(char),
+        }
+    }
+
+    Some(value)
+}
+
+impl AIConfig {
+    /// `targets` are `backend:model` chains per feature, features without chain use `default_targets`
+    pub fn new(
+        backends: Vec<Backend>,
+        targets: &HashMap<AIFeature, String>,
+        default_targets: &str,
+        monthly_token_quota: u64,
+    ) -> anyhow::Result<Self> {
+        let backends: HashMap<_, _> = backends
+            .into_iter()
+            .map(|backend| (backend.name.clone(), Arc::new(backend)))
+            .collect();
+
+        let mut feature_targets = HashMap::new();
+
+        for feature in AIFeature::iter() {
+            let chain = targets
+                .get(&feature)
+                .map_or(default_targets, String::as_str);
+
+            let chain: Vec<_> = parse_targets(chain)?
+                .into_iter()
+                .map(|(backend, model)| {
+                    let backend = backends
+                        .get(backend)
+                        .with_context(|| format!("AI backend {backend} is not configured"))?;
+
+                    Ok(Target {
+                        backend: Arc::clone(backend),
+                        model: model.to_owned(),
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+
+            if chain.is_empty() {
+                anyhow::bail!("No AI models configured for {}", feature.as_ref());
+            }
+
+            feature_targets.insert(feature, chain);
+        }
+
+        Ok(Self {
+            targets: feature_targets,
+            monthly_token_quota,
+        })
+    }
+
+    /// Models in the order they are tried
+    #[must_use]
+    pub fn targets(&self, feature: AIFeature) -> &[Target] {
+        self.targets.get(&feature).map_or(&[], Vec::as_slice)
+    }
+
+    /// Tokens a user can spend per calendar month
+    #[must_use]
+    pub fn monthly_token_quota(&self) -> u64 {
+        self.monthly_token_quota
+    }
+
+    /// Sends the request to the feature models until one succeeds. Model of the request is overridden
+    #[tracing::instrument(skip_all, fields(feature = feature.as_ref()))]
+    pub async fn chat(
+        &self,
+        feature: AIFeature,
+        request: CreateChatCompletionRequest,
+    ) -> anyhow::Result<(&Target, CreateChatCompletionResponse)> {
+        let mut last_err = None;
+
+        for target in self.targets(feature) {
+            let mut request = request.clone();
+            request.model.clone_from(&target.model);
+
+            match target.backend.client.chat().create(request).await {
+                Ok(response) => return Ok((target, response)),
+                Err(err) => {
+                    tracing::warn!(
+                        err = ?err,
+                        backend = target.backend(),
+                        model = target.model(),
+                        "AI request failed, trying next model"
+                    );
+
+                    last_err = Some(err);
+                },
+            }
+        }
+
+        Err(RustifyError::from_ai(&last_err.context("No AI models configured")?).into())
+    }
+
+    /// Same as `chat`, but streaming. Falls back only until the first chunk is received
+    #[tracing::instrument(skip_all, fields(feature = feature.as_ref()))]
+    pub async fn chat_stream(
+        &self,
+        feature: AIFeature,
+        request: CreateChatCompletionRequest,
+    ) -> anyhow::Result<(
+        &Target,
+        BoxStream<'static, Result<CreateChatCompletionStreamResponse, RustifyError>>,
+    )> {
+        let mut last_err = None;
+
+        for target in self.targets(feature) {
+            let mut request = request.clone();
+            request.model.clone_from(&target.model);
+
+            let res = match target.backend.client.chat().create_stream(request).await {
+                Ok(mut stream) => match stream.next().await {
+                    Some(Err(err)) => Err(err),
+                    first => Ok(futures::stream::iter(first)
+                        .chain(stream)
+                        .map(|response| response.map_err(|err| RustifyError::from_ai(&err)))
+                        .boxed()),
+                },
+                Err(err) => Err(err),
+            };
+
+            match res {
+                Ok(stream) => return Ok((target, stream)),
+                Err(err) => {
+                    tracing::warn!(
+                        err = ?err,
+                        backend = target.backend(),
+                        model = target.model(),
+                        "AI request failed, trying next model"
+                    );
+
+                    last_err = Some(err);
+                },
+            }
+        }
+
+        Err(RustifyError::from_ai(&last_err.context("No AI models configured")?).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_targets() {
+        assert_eq!(
+            parse_targets("openai:gpt-4o, local:llama3.1:8b,").unwrap(),
+            vec![("openai", "gpt-4o"), ("local", "llama3.1:8b")]
+        );
+        assert!(parse_targets("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_partial_string_field() {
+        assert_eq!(partial_string_field(r#"{"descr"#, "description"), None);
+        assert_eq!(
+            partial_string_field(r#"{"description": "It's about"#, "description"),
+            Some("It's about".into())
+        );
+        assert_eq!(
+            partial_string_field(
+                r#"{"description":"Line\n\"quoted\" \u00e9","summary":"x"}"#,
+                "description"
+            ),
+            Some("Line\n\"quoted\" é".into())
+        );
+        assert_eq!(
+            partial_string_field(r#"{"description":"ends with \"#, "description"),
+            Some("ends with ".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_targets_invalid() {
+        assert!(parse_targets("gpt-4o").is_err());
+        assert!(parse_targets("openai:").is_err());
+        assert!(parse_targets(":gpt-4o").is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::str::FromStr as _;
 use std::sync::Arc;
 use std::time::Duration;
@@ -13,15 +14,31 @@ use teloxide::dispatching::dialogue::RedisStorage as TeloxideRedisStorage;
 use teloxide::dispatching::dialogue::serializer::Bincode;
 use teloxide::requests::RequesterExt as _;
 
+use crate::ai::{AIConfig, AIFeature, Backend};
 use crate::metrics::influx::InfluxClient;
 use crate::metrics::prometheus::PrometheusClient;
 use crate::queue::QueueManager;
-use crate::services::{AISlopDetectionService, SongLinkService, UserService};
+use crate::services::{
+    AISlopDetectionService,
+    PublicResolver,
+    SongLinkService,
+    UserService,
+    WebhookService,
+};
 use crate::user::UserState;
 use crate::{lyrics, profanity, spotify};
 
+/// Tokens a user can spend per month when `AI_MONTHLY_TOKEN_QUOTA` isn't set
+const DEFAULT_AI_MONTHLY_TOKEN_QUOTA: u64 = 200_000;
+/// API keys of extra AI backends are `AI_BACKEND_<NAME>_API_KEY`
+const AI_BACKEND_ENV_PREFIX: &str = "AI_BACKEND_";
+
+/// Variables with `AI_BACKEND_ENV_PREFIX` stripped, keys are lowercase `<name>_api_key`
+type AIBackendKeys = HashMap<String, String>;
+
 pub struct App {
     spotify_manager: spotify::Manager,
+    spotify_rate_limiter: spotify::RateLimiter,
     lyrics: lyrics::Manager,
     bot: DefaultParseMode<Bot>,
     db: DatabaseConnection,
@@ -31,37 +48,27 @@ pub struct App {
     ai: Option<AIConfig>,
     dialogue_storage: Arc<TeloxideRedisStorage<Bincode>>,
     server_http_address: String,
+    server_public_url: Option<String>,
     song_link: SongLinkService,
     ai_slop_detection: AISlopDetectionService,
     queue_manager: QueueManager,
-}
-
-pub struct AIConfig {
-    openai_client: async_openai::Client<OpenAIConfig>,
-    model: String,
-}
-
-impl AIConfig {
-    #[must_use]
-    pub fn openai_client(&self) -> &async_openai::Client<OpenAIConfig> {
-        &self.openai_client
-    }
-
-    #[must_use]
-    pub fn model(&self) -> &str {
-        &self.model
-    }
+    webhook: WebhookService,
 }
 
 #[derive(Deserialize, Debug)]
 struct EnvConfig {
     telegram_bot_token: String,
+    /// Overrides Telegram Bot API URL, e.g. to use a local Bot API server or a mock
+    telegram_api_url: Option<String>,
     redis_url: String,
     database_url: String,
 
     spotify_id: String,
     spotify_secret: String,
     spotify_redirect_uri: Option<String>,
+    /// Overrides Spotify Web API and Accounts service URLs, e.g. to use a mock server
+    spotify_api_url: Option<String>,
+    spotify_auth_url: Option<String>,
 
     musixmatch_user_tokens: Option<String>,
     genius_access_token: String,
@@ -76,6 +83,17 @@ struct EnvConfig {
     openai_api_key: Option<String>,
     openai_api_base: Option<String>,
     openai_api_model: Option<String>,
+    /// Extra OpenAI-compatible backends, e.g. `local=http://localhost:11434/v1`.
+    /// API key is read from `AI_BACKEND_<NAME>_API_KEY` if backend needs it
+    ai_backends: Option<String>,
+    /// `backend:model` chains per feature, tried in order
+    ai_analysis_models: Option<String>,
+    ai_word_definition_models: Option<String>,
+    ai_recommendasion_models: Option<String>,
+    ai_chat_models: Option<String>,
+    ai_translation_models: Option<String>,
+    /// Defaults to `DEFAULT_AI_MONTHLY_TOKEN_QUOTA`
+    ai_monthly_token_quota: Option<u64>,
 
     influx_api_url: Option<String>,
     influx_token: Option<String>,
@@ -90,6 +108,7 @@ struct EnvConfig {
     pushgateway_password: Option<String>,
 
     server_http_address: Option<String>,
+    server_public_url: Option<String>,
 }
 
 impl App {
@@ -97,6 +116,10 @@ impl App {
         &self.spotify_manager
     }
 
+    pub fn spotify_rate_limiter(&self) -> &spotify::RateLimiter {
+        &self.spotify_rate_limiter
+    }
+
     pub fn lyrics(&self) -> &lyrics::Manager {
         &self.lyrics
     }
@@ -129,14 +152,19 @@ impl App {
         self.ai.as_ref()
     }
 
-    pub fn dialogue_storage(&self) -> &TeloxideRedisStorage<Bincode> {
-        &self.dialogue_storage
+    pub fn dialogue_storage(&self) -> Arc<TeloxideRedisStorage<Bincode>> {
+        Arc::clone(&self.dialogue_storage)
     }
 
     pub fn server_http_address(&self) -> &str {
         &self.server_http_address
     }
 
+    /// Public base URL of the HTTP server, e.g. `https://rustify.example.com`
+    pub fn server_public_url(&self) -> Option<&str> {
+        self.server_public_url.as_deref()
+    }
+
     pub fn queue_manager(&self) -> &QueueManager {
         &self.queue_manager
     }
@@ -144,6 +172,10 @@ impl App {
     pub fn ai_slop_detection(&self) -> &AISlopDetectionService {
         &self.ai_slop_detection
     }
+
+    pub fn webhook(&self) -> &WebhookService {
+        &self.webhook
+    }
 }
 
 fn init_influx(env: &EnvConfig) -> anyhow::Result<Option<InfluxClient>> {
@@ -282,30 +314,84 @@ async fn init_redis(redis_url: &str) -> anyhow::Result<deadpool_redis::Pool> {
     Ok(pool)
 }
 
-fn init_ai(env: &EnvConfig) -> anyhow::Result<Option<AIConfig>> {
-    let Some(api_key) = env.openai_api_key.as_deref() else {
-        return Ok(None);
-    };
-
+fn init_ai_client(
+    api_base: &str,
+    api_key: &str,
+) -> anyhow::Result<async_openai::Client<OpenAIConfig>> {
     let openai_config = OpenAIConfig::new()
         .with_api_key(api_key)
-        .with_api_base(env.openai_api_base.as_deref().unwrap_or(OPENAI_API_BASE));
+        .with_api_base(api_base);
 
     let http_client = reqwest_compat::ClientBuilder::new()
         .timeout(Duration::from_secs(180))
         .build()?;
 
-    let openai_client =
-        async_openai::Client::with_config(openai_config).with_http_client(http_client);
+    Ok(async_openai::Client::with_config(openai_config).with_http_client(http_client))
+}
+
+fn init_ai(env: &EnvConfig, backend_keys: &AIBackendKeys) -> anyhow::Result<Option<AIConfig>> {
+    let mut backends = vec![];
+
+    let default_targets = if let Some(api_key) = env.openai_api_key.as_deref() {
+        let client = init_ai_client(
+            env.openai_api_base.as_deref().unwrap_or(OPENAI_API_BASE),
+            api_key,
+        )?;
 
-    let config = AIConfig {
-        openai_client,
-        model: env
-            .openai_api_model
-            .clone()
-            .unwrap_or_else(|| "gpt-4o".into()),
+        backends.push(Backend::new("openai", client));
+
+        format!(
+            "openai:{}",
+            env.openai_api_model.as_deref().unwrap_or("gpt-4o")
+        )
+    } else {
+        String::new()
     };
 
+    for backend in env
+        .ai_backends
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|backend| !backend.is_empty())
+    {
+        let (name, api_base) = backend
+            .split_once('=')
+            .with_context(|| format!("AI backend must be name=url, got {backend}"))?;
+
+        // Local servers don't check the key, but it must not be empty
+        let api_key = backend_keys
+            .get(&format!("{}_api_key", name.to_lowercase()))
+            .map_or("none", String::as_str);
+
+        backends.push(Backend::new(name, init_ai_client(api_base, api_key)?));
+    }
+
+    if backends.is_empty() {
+        return Ok(None);
+    }
+
+    let targets: HashMap<_, _> = [
+        (AIFeature::Analysis, &env.ai_analysis_models),
+        (AIFeature::WordDefinition, &env.ai_word_definition_models),
+        (AIFeature::Recommendasion, &env.ai_recommendasion_models),
+        (AIFeature::Chat, &env.ai_chat_models),
+        (AIFeature::Translation, &env.ai_translation_models),
+    ]
+    .into_iter()
+    .filter_map(|(feature, models)| Some((feature, models.clone()?)))
+    .collect();
+
+    let config = AIConfig::new(
+        backends,
+        &targets,
+        &default_targets,
+        env.ai_monthly_token_quota
+            .unwrap_or(DEFAULT_AI_MONTHLY_TOKEN_QUOTA),
+    )
+    .context("Cannot configure AI")?;
+
     Ok(Some(config))
 }
 
@@ -317,10 +403,45 @@ fn init_song_link() -> anyhow::Result<SongLinkService> {
     Ok(SongLinkService::new(http_client))
 }
 
+fn init_webhook() -> anyhow::Result<WebhookService> {
+    // Redirects and DNS rebinding could lead webhook to internal addresses otherwise
+    let http_client = reqwest::ClientBuilder::new()
+        .timeout(Duration::from_secs(10))
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(PublicResolver)
+        .build()?;
+
+    Ok(WebhookService::new(http_client))
+}
+
 impl App {
     pub async fn init() -> anyhow::Result<&'static Self> {
+        Self::init_with(
+            envy::from_env()?,
+            envy::prefixed(AI_BACKEND_ENV_PREFIX).from_env()?,
+        )
+        .await
+    }
+
+    /// App configured by given variables instead of process environment
+    #[cfg(test)]
+    pub async fn init_from_iter(
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> anyhow::Result<&'static Self> {
+        let vars: Vec<_> = vars.into_iter().collect();
+
+        Self::init_with(
+            envy::from_iter(vars.clone())?,
+            envy::prefixed(AI_BACKEND_ENV_PREFIX).from_iter(vars)?,
+        )
+        .await
+    }
+
+    async fn init_with(
+        env: EnvConfig,
+        ai_backend_keys: AIBackendKeys,
+    ) -> anyhow::Result<&'static Self> {
         tracing::trace!("Init application");
-        let env: EnvConfig = envy::from_env()?;
 
         let redis_url = &env.redis_url;
         let redis = init_redis(redis_url).await?;
@@ -328,13 +449,27 @@ impl App {
             &env.spotify_id,
             &env.spotify_secret,
             env.spotify_redirect_uri.clone(),
+            env.spotify_api_url.clone(),
+            env.spotify_auth_url.clone(),
         );
+        let spotify_rate_limiter =
+            spotify::RateLimiter::new(redis.clone(), spotify_manager.client_id().to_owned());
         let lyrics_manager = init_lyrics_manager(&env)?;
-        let ai = init_ai(&env)?;
+        let ai = init_ai(&env, &ai_backend_keys)?;
 
         init_rustrict(&env);
 
-        let bot = Bot::new(&env.telegram_bot_token).parse_mode(teloxide::types::ParseMode::Html);
+        let mut bot = Bot::new(&env.telegram_bot_token);
+
+        if let Some(url) = env
+            .telegram_api_url
+            .as_ref()
+            .filter(|url| !url.trim().is_empty())
+        {
+            bot = bot.set_api_url(url.parse().context("Invalid TELEGRAM_API_URL")?);
+        }
+
+        let bot = bot.parse_mode(teloxide::types::ParseMode::Html);
 
         let db = init_db(&env).await?;
 
@@ -342,6 +477,7 @@ impl App {
         let prometheus = init_prometheus(&env).context("Cannot configure Prometheus Client")?;
 
         let song_link = init_song_link()?;
+        let webhook = init_webhook()?;
 
         let queue_manager = QueueManager::new(redis_url).await?;
 
@@ -351,6 +487,7 @@ impl App {
         let app = Box::new(Self {
             bot,
             spotify_manager,
+            spotify_rate_limiter,
             lyrics: lyrics_manager,
             db,
             influx,
@@ -362,8 +499,13 @@ impl App {
             server_http_address: env
                 .server_http_address
                 .unwrap_or_else(|| "0.0.0.0:3000".into()),
+            server_public_url: env
+                .server_public_url
+                .map(|url| url.trim_end_matches('/').to_owned())
+                .filter(|url| !url.is_empty()),
             queue_manager,
             ai_slop_detection: AISlopDetectionService::new(shlabs_api_key),
+            webhook,
         });
 
         let app = &*Box::leak(app);
@@ -374,7 +516,12 @@ impl App {
     pub async fn user_state(&'static self, user_id: &str) -> anyhow::Result<UserState> {
         let spotify = self.spotify_manager.for_user(&self.db, user_id).await?;
         let (user, newly_created) = UserService::upsert_by_id(self.db(), user_id).await?;
-        let state = UserState::new(user, newly_created, spotify);
+        let state = UserState::new(
+            user,
+            newly_created,
+            spotify,
+            self.spotify_rate_limiter.clone(),
+        );
 
         Ok(state)
     }
@@ -0,0 +1,107 @@
+use clap::Subcommand;
+
+use crate::app::App;
+use crate::queue::dead_letter::{self, DeadLetter};
+use crate::queue::track_check::{self, TrackCheckQueueTask};
+
+#[derive(Subcommand)]
+pub enum DeadLettersCommands {
+    /// List track checks failed all retries, newest first
+    List {
+        /// Max amount of letters to show
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Show dead letter with its error
+    Show {
+        /// Dead letter ID
+        id: String,
+    },
+    /// Put dead letter back to the queue
+    Replay {
+        /// Dead letter ID
+        id: String,
+    },
+    /// Put all dead letters back to the queue
+    ReplayAll,
+}
+
+fn print_summary(letter: &DeadLetter<TrackCheckQueueTask>) {
+    println!(
+        "{id}  {failed_at}  user {user_id}  {track}",
+        id = letter.id,
+        failed_at = letter.failed_at.format("%Y-%m-%d %H:%M:%S"),
+        user_id = letter.task.user_id(),
+        track = letter.task.track().name_with_artists(),
+    );
+}
+
+pub async fn list(app: &App, limit: usize) -> anyhow::Result<()> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    let depth = dead_letter::depth(&mut redis_conn, track_check::DEAD_LETTER_QUEUE).await?;
+    let letters: Vec<DeadLetter<TrackCheckQueueTask>> =
+        dead_letter::list(&mut redis_conn, track_check::DEAD_LETTER_QUEUE, limit).await?;
+
+    println!("Dead letters: {depth}");
+
+    for letter in &letters {
+        print_summary(letter);
+    }
+
+    Ok(())
+}
+
+pub async fn show(app: &App, id: &str) -> anyhow::Result<()> {
+    let letter: DeadLetter<TrackCheckQueueTask> = dead_letter::get(
+        &mut app.redis_conn().await?,
+        track_check::DEAD_LETTER_QUEUE,
+        id,
+    )
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("Dead letter not found: {id}"))?;
+
+    print_summary(&letter);
+    println!("Track: {}", letter.task.track().url());
+    println!("Attempts: {}", letter.attempts);
+    println!();
+    println!("{}", letter.error);
+
+    Ok(())
+}
+
+pub async fn replay(app: &App, id: &str) -> anyhow::Result<()> {
+    if !track_check::replay(app, id).await? {
+        anyhow::bail!("Dead letter not found: {id}");
+    }
+
+    println!("Dead letter {id} replayed");
+
+    Ok(())
+}
+
+pub async fn replay_all(app: &App) -> anyhow::Result<()> {
+    let replayed = track_check::replay_all(app).await?;
+
+    println!("Replayed {replayed} dead letters");
+
+    Ok(())
+}
+
+pub async fn run(command: DeadLettersCommands) {
+    crate::infrastructure::logger::init().expect("Logger should be built");
+
+    let app = App::init().await.expect("State to be built");
+
+    let result = match command {
+        DeadLettersCommands::List { limit } => list(app, limit).await,
+        DeadLettersCommands::Show { id } => show(app, &id).await,
+        DeadLettersCommands::Replay { id } => replay(app, &id).await,
+        DeadLettersCommands::ReplayAll => replay_all(app).await,
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
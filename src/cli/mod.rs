@@ -1 +1,2 @@
+pub mod dead_letters;
 pub mod users;
@@ -0,0 +1,85 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Copy, Clone, Default, Debug, DeriveEntity)]
+pub struct Entity;
+
+impl EntityName for Entity {
+    fn table_name(&self) -> &str {
+        "ai_usage"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveModel, DeriveActiveModel, Eq)]
+pub struct Model {
+    pub id: i32,
+    pub user_id: String,
+    pub feature: String,
+    pub backend: String,
+    pub model: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub created_at: DateTime,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
+pub enum PrimaryKey {
+    Id,
+}
+
+impl PrimaryKeyTrait for PrimaryKey {
+    type ValueType = i32;
+
+    fn auto_increment() -> bool {
+        true
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+pub enum Column {
+    Id,
+    UserId,
+    Feature,
+    Backend,
+    Model,
+    PromptTokens,
+    CompletionTokens,
+    TotalTokens,
+    CreatedAt,
+}
+
+impl ColumnTrait for Column {
+    type EntityName = Entity;
+
+    fn def(&self) -> ColumnDef {
+        match self {
+            Self::Id => ColumnType::Integer.def(),
+            Self::UserId => ColumnType::Text.def(),
+            Self::Feature => ColumnType::Text.def(),
+            Self::Backend => ColumnType::Text.def(),
+            Self::Model => ColumnType::Text.def(),
+            Self::PromptTokens => ColumnType::BigInteger.def(),
+            Self::CompletionTokens => ColumnType::BigInteger.def(),
+            Self::TotalTokens => ColumnType::BigInteger.def(),
+            Self::CreatedAt => ColumnType::DateTime.def(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::prelude::UserEntity",
+        from = "Column::UserId",
+        to = "super::prelude::UserColumn::Id"
+    )]
+    User,
+}
+
+impl Related<super::prelude::UserEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
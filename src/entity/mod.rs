@@ -1,9 +1,16 @@
 pub mod prelude;
 
+mod ai_usage;
+mod api_token;
+mod recommendasion_feedback;
 mod spotify_auth;
+mod track_analysis;
 mod track_language_stats;
 mod track_status;
+mod track_translation;
 mod user;
+mod user_profane_word_stats;
 mod user_word_whitelist;
+mod webhook;
 mod word_definition;
 mod word_stats;
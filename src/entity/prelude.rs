@@ -1,9 +1,34 @@
+pub use super::ai_usage::{
+    ActiveModel as AIUsageActiveModel,
+    Column as AIUsageColumn,
+    Entity as AIUsageEntity,
+    Model as AIUsageModel,
+};
+pub use super::api_token::{
+    ActiveModel as ApiTokenActiveModel,
+    Column as ApiTokenColumn,
+    Entity as ApiTokenEntity,
+    Model as ApiTokenModel,
+};
+pub use super::recommendasion_feedback::{
+    ActiveModel as RecommendasionFeedbackActiveModel,
+    Column as RecommendasionFeedbackColumn,
+    Entity as RecommendasionFeedbackEntity,
+    Model as RecommendasionFeedbackModel,
+    Outcome as RecommendasionOutcome,
+};
 pub use super::spotify_auth::{
     ActiveModel as SpotifyAuthActiveModel,
     Column as SpotifyAuthColumn,
     Entity as SpotifyAuthEntity,
     Model as SpotifyAuthModel,
 };
+pub use super::track_analysis::{
+    ActiveModel as TrackAnalysisActiveModel,
+    Column as TrackAnalysisColumn,
+    Entity as TrackAnalysisEntity,
+    Model as TrackAnalysisModel,
+};
 pub use super::track_language_stats::{
     ActiveModel as TrackLanguageStatsActiveModel,
     Column as TrackLanguageStatsColumn,
@@ -17,6 +42,12 @@ pub use super::track_status::{
     Model as TrackStatusModel,
     Status as TrackStatus,
 };
+pub use super::track_translation::{
+    ActiveModel as TrackTranslationActiveModel,
+    Column as TrackTranslationColumn,
+    Entity as TrackTranslationEntity,
+    Model as TrackTranslationModel,
+};
 #[allow(unused_imports)]
 pub use super::user::{
     AISlopDetection as UserAISlopDetection,
@@ -25,15 +56,28 @@ pub use super::user::{
     Entity as UserEntity,
     Locale as UserLocale,
     Model as UserModel,
+    Profile as UserProfile,
     Role as UserRole,
     Status as UserStatus,
 };
+pub use super::user_profane_word_stats::{
+    ActiveModel as UserProfaneWordStatsActiveModel,
+    Column as UserProfaneWordStatsColumn,
+    Entity as UserProfaneWordStatsEntity,
+    Model as UserProfaneWordStatsModel,
+};
 pub use super::user_word_whitelist::{
     ActiveModel as UserWordWhitelistActiveModel,
     Column as UserWordWhitelistColumn,
     Entity as UserWordWhitelistEntity,
     Model as UserWordWhitelistModel,
 };
+pub use super::webhook::{
+    ActiveModel as WebhookActiveModel,
+    Column as WebhookColumn,
+    Entity as WebhookEntity,
+    Model as WebhookModel,
+};
 pub use super::word_definition::{
     ActiveModel as WordDefinitionActiveModel,
     Column as WordDefinitionColumn,
@@ -0,0 +1,120 @@
+use sea_orm::Set;
+use sea_orm::entity::prelude::*;
+use sea_orm::prelude::async_trait::async_trait;
+
+use crate::utils::Clock;
+
+#[derive(Copy, Clone, Default, Debug, DeriveEntity)]
+pub struct Entity;
+
+impl EntityName for Entity {
+    fn table_name(&self) -> &str {
+        "recommendasion_feedback"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveModel, DeriveActiveModel, Eq)]
+pub struct Model {
+    pub id: i32,
+    pub user_id: String,
+    pub track_id: String,
+    pub track_name: String,
+    pub outcome: Outcome,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, _insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        self.updated_at = Set(Clock::now());
+
+        Ok(self)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
+pub enum PrimaryKey {
+    Id,
+}
+
+impl PrimaryKeyTrait for PrimaryKey {
+    type ValueType = i32;
+
+    fn auto_increment() -> bool {
+        true
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+pub enum Column {
+    Id,
+    UserId,
+    TrackId,
+    TrackName,
+    Outcome,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl ColumnTrait for Column {
+    type EntityName = Entity;
+
+    fn def(&self) -> ColumnDef {
+        match self {
+            Self::Id => ColumnType::Integer.def(),
+            Self::UserId => ColumnType::Text.def(),
+            Self::TrackId => ColumnType::Text.def(),
+            Self::TrackName => ColumnType::Text.def(),
+            Self::Outcome => Outcome::db_type(),
+            Self::CreatedAt => ColumnType::DateTime.def(),
+            Self::UpdatedAt => ColumnType::DateTime.def(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::prelude::UserEntity",
+        from = "Column::UserId",
+        to = "super::prelude::UserColumn::Id"
+    )]
+    User,
+}
+
+impl Related<super::prelude::UserEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+/// What user did with recommended track
+#[derive(Debug, Copy, Clone, EnumIter, DeriveActiveEnum, PartialEq, Eq)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum Outcome {
+    #[sea_orm(string_value = "listened")]
+    Listened,
+    #[sea_orm(string_value = "skipped")]
+    Skipped,
+    #[sea_orm(string_value = "liked")]
+    Liked,
+    #[sea_orm(string_value = "disliked")]
+    Disliked,
+}
+
+impl Outcome {
+    /// Explicit reactions are not overwritten by listening behaviour
+    #[must_use]
+    pub fn is_explicit(&self) -> bool {
+        matches!(self, Self::Liked | Self::Disliked)
+    }
+
+    #[must_use]
+    pub fn is_hit(&self) -> bool {
+        matches!(self, Self::Listened | Self::Liked)
+    }
+}
@@ -21,6 +21,8 @@ pub struct Model {
     pub refresh_token: String,
     pub suspend_until: chrono::NaiveDateTime,
     pub expires_at: Option<chrono::NaiveDateTime>,
+    /// Granted OAuth scopes, space separated
+    pub scopes: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
 }
@@ -44,6 +46,7 @@ pub enum Column {
     RefreshToken,
     SuspendUntil,
     ExpiresAt,
+    Scopes,
     CreatedAt,
     UpdatedAt,
 }
@@ -71,6 +74,7 @@ impl ColumnTrait for Column {
             Self::RefreshToken => ColumnType::Text.def(),
             Self::SuspendUntil => ColumnType::DateTime.def(),
             Self::ExpiresAt => ColumnType::DateTime.def(),
+            Self::Scopes => ColumnType::Text.def(),
             Self::CreatedAt => ColumnType::DateTime.def(),
             Self::UpdatedAt => ColumnType::DateTime.def(),
         }
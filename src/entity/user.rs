@@ -31,6 +31,11 @@ pub struct Model {
     #[sea_orm(enum_name = "LyricsLrcLib")]
     pub lyrics_lrclib: i64,
     pub lyrics_profane: i64,
+    pub skipped_skippage: i64,
+    #[sea_orm(enum_name = "SkippedAISlop")]
+    pub skipped_ai_slop: i64,
+    #[sea_orm(enum_name = "AISlopDetected")]
+    pub ai_slop_detected: i64,
     pub status: Status,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
@@ -40,6 +45,10 @@ pub struct Model {
     pub cfg_skippage_enabled: bool,
     #[sea_orm(enum_name = "CfgAISlopDetection")]
     pub cfg_ai_slop_detection: AISlopDetection,
+    pub cfg_kid_mode: bool,
+    pub cfg_skip_explicit: bool,
+    pub profile: Profile,
+    pub settings_pin_hash: Option<String>,
     pub magic_playlist: Option<String>,
     pub spotify_state: Uuid,
     pub ref_code: Option<String>,
@@ -50,6 +59,58 @@ impl Model {
     pub fn is_admin(&self) -> bool {
         self.role.is_admin()
     }
+
+    // Effective settings, profile overrides individual `cfg_*` fields
+
+    #[must_use]
+    pub fn check_profanity(&self) -> bool {
+        self.profile.is_kid_safe() || self.cfg_check_profanity
+    }
+
+    /// Kid-safe profile always turns it on. `cfg_kid_mode` is the own switch of the standard
+    /// profile, it's kept as is under kid-safe profile and applies again once it's turned off
+    #[must_use]
+    pub fn kid_mode(&self) -> bool {
+        self.profile.is_kid_safe() || self.cfg_kid_mode
+    }
+
+    #[must_use]
+    pub fn ai_slop_detection(&self) -> AISlopDetection {
+        if self.profile.is_kid_safe() {
+            AISlopDetection::Skip
+        } else {
+            self.cfg_ai_slop_detection
+        }
+    }
+
+    /// Skip disliked tracks
+    #[must_use]
+    pub fn skip_tracks(&self) -> bool {
+        self.profile.is_kid_safe() || self.cfg_skip_tracks
+    }
+
+    /// Words whitelisted by user aren't treated as profane, kid-safe profile ignores them
+    #[must_use]
+    pub fn word_whitelist(&self) -> bool {
+        !self.profile.is_kid_safe()
+    }
+
+    /// Skip tracks with any profanity, even mild one
+    #[must_use]
+    pub fn skip_profane(&self) -> bool {
+        self.profile.is_kid_safe()
+    }
+
+    #[must_use]
+    pub fn skip_explicit(&self) -> bool {
+        self.profile.is_kid_safe() || self.cfg_skip_explicit
+    }
+
+    /// Skip tracks with non English lyrics, profanity of them can't be checked
+    #[must_use]
+    pub fn skip_unverifiable(&self) -> bool {
+        self.profile.is_kid_safe()
+    }
 }
 
 #[async_trait]
@@ -79,6 +140,11 @@ pub enum Column {
     #[sea_orm(column_name = "lyrics_lrclib")]
     LyricsLrcLib,
     LyricsProfane,
+    SkippedSkippage,
+    #[sea_orm(column_name = "skipped_ai_slop")]
+    SkippedAISlop,
+    #[sea_orm(column_name = "ai_slop_detected")]
+    AISlopDetected,
     Status,
     CreatedAt,
     UpdatedAt,
@@ -88,6 +154,10 @@ pub enum Column {
     CfgSkippageEnabled,
     #[sea_orm(column_name = "cfg_ai_slop_detection")]
     CfgAISlopDetection,
+    CfgKidMode,
+    CfgSkipExplicit,
+    Profile,
+    SettingsPinHash,
     MagicPlaylist,
     SpotifyState,
     RefCode,
@@ -123,6 +193,9 @@ impl ColumnTrait for Column {
             Self::LyricsMusixmatch => ColumnType::BigInteger.def(),
             Self::LyricsLrcLib => ColumnType::BigInteger.def(),
             Self::LyricsProfane => ColumnType::BigInteger.def(),
+            Self::SkippedSkippage => ColumnType::BigInteger.def(),
+            Self::SkippedAISlop => ColumnType::BigInteger.def(),
+            Self::AISlopDetected => ColumnType::BigInteger.def(),
             Self::Status => Status::db_type(),
             Self::CreatedAt => ColumnType::DateTime.def(),
             Self::UpdatedAt => ColumnType::DateTime.def(),
@@ -131,6 +204,10 @@ impl ColumnTrait for Column {
             Self::CfgSkippageSecs => ColumnType::BigInteger.def(),
             Self::CfgSkippageEnabled => ColumnType::Boolean.def(),
             Self::CfgAISlopDetection => AISlopDetection::db_type(),
+            Self::CfgKidMode => ColumnType::Boolean.def(),
+            Self::CfgSkipExplicit => ColumnType::Boolean.def(),
+            Self::Profile => Profile::db_type(),
+            Self::SettingsPinHash => ColumnType::Text.def().null(),
             Self::MagicPlaylist => ColumnType::Text.def().null(),
             Self::SpotifyState => ColumnType::Uuid.def(),
             Self::RefCode => ColumnType::Text.def().null(),
@@ -140,14 +217,42 @@ impl ColumnTrait for Column {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::prelude::AIUsageEntity")]
+    AIUsage,
+    #[sea_orm(has_one = "super::prelude::ApiTokenEntity")]
+    ApiToken,
+    #[sea_orm(has_many = "super::prelude::RecommendasionFeedbackEntity")]
+    RecommendasionFeedback,
     #[sea_orm(has_one = "super::prelude::SpotifyAuthEntity")]
     SpotifyAuth,
     #[sea_orm(has_many = "super::prelude::TrackLanguageStatsEntity")]
     TrackLanguageStats,
     #[sea_orm(has_many = "super::prelude::TrackStatusEntity")]
     TrackStatus,
+    #[sea_orm(has_many = "super::prelude::UserProfaneWordStatsEntity")]
+    UserProfaneWordStats,
     #[sea_orm(has_many = "super::prelude::UserWordWhitelistEntity")]
     UserWordWhitelist,
+    #[sea_orm(has_many = "super::prelude::WebhookEntity")]
+    Webhook,
+}
+
+impl Related<super::prelude::AIUsageEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AIUsage.def()
+    }
+}
+
+impl Related<super::prelude::ApiTokenEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiToken.def()
+    }
+}
+
+impl Related<super::prelude::RecommendasionFeedbackEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecommendasionFeedback.def()
+    }
 }
 
 impl Related<super::prelude::SpotifyAuthEntity> for Entity {
@@ -168,12 +273,24 @@ impl Related<super::prelude::TrackStatusEntity> for Entity {
     }
 }
 
+impl Related<super::prelude::UserProfaneWordStatsEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserProfaneWordStats.def()
+    }
+}
+
 impl Related<super::prelude::UserWordWhitelistEntity> for Entity {
     fn to() -> RelationDef {
         Relation::UserWordWhitelist.def()
     }
 }
 
+impl Related<super::prelude::WebhookEntity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Webhook.def()
+    }
+}
+
 #[derive(
     Debug, Copy, Clone, EnumIter, DeriveActiveEnum, PartialEq, Eq, Default, Serialize, Deserialize,
 )]
@@ -354,3 +471,39 @@ impl AISlopDetection {
         matches!(self, Self::Ignore)
     }
 }
+
+/// Set of settings applied on top of the individual ones
+#[derive(
+    Debug, Copy, Clone, EnumIter, DeriveActiveEnum, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum Profile {
+    #[sea_orm(string_value = "standard")]
+    #[default]
+    Standard,
+    #[sea_orm(string_value = "kid_safe")]
+    KidSafe,
+}
+
+impl FromStr for Profile {
+    type Err = sea_orm::DbErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+impl TryFrom<&str> for Profile {
+    type Error = sea_orm::DbErr;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from_value(&value.to_owned())
+    }
+}
+
+impl Profile {
+    #[must_use]
+    pub fn is_kid_safe(&self) -> bool {
+        matches!(self, Self::KidSafe)
+    }
+}
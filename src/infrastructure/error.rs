@@ -0,0 +1,429 @@
+use reqwest::StatusCode;
+use strum_macros::{AsRefStr, EnumIter};
+use teloxide::ApiError;
+
+use crate::entity::prelude::UserStatus;
+use crate::spotify;
+
+/// Used when Spotify doesn't send `Retry-After` with 429
+const DEFAULT_RETRY_AFTER: chrono::Duration = chrono::Duration::seconds(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, AsRefStr, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum ErrorClass {
+    /// Goes away by itself, operation can be repeated later
+    Retryable,
+    /// User has to do something with their account to fix it
+    UserActionable,
+    /// Bug or unexpected response, repeating won't help
+    Permanent,
+}
+
+/// What to do when error happens
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorPolicy {
+    pub class: ErrorClass,
+    /// Operation should be retried, e.g. queue task fails to be picked up again
+    pub retry: bool,
+    /// User gets a message explaining what to do
+    pub notify: bool,
+    /// User checks are stopped with this status until relogin
+    pub suspend: Option<UserStatus>,
+}
+
+/// Typed version of errors coming from Spotify, lyrics providers, AI, Telegram and storages
+#[derive(Clone, Debug)]
+pub enum RustifyError {
+    SpotifyRateLimited {
+        retry_after: chrono::Duration,
+    },
+    /// Spotify 5xx responses
+    SpotifyUnavailable {
+        status: u32,
+    },
+    SpotifyUnavailableInCountry,
+    SpotifyAuth {
+        description: String,
+    },
+    SpotifyInvalidToken,
+    SpotifyApi {
+        status: u32,
+        message: String,
+    },
+    /// Spotify responded with body we cannot parse
+    SpotifyUnparsable,
+    SpotifyClient {
+        message: String,
+    },
+    TelegramBotBlocked,
+    TelegramRetryAfter,
+    TelegramApi,
+    /// Connection issues with Telegram, Spotify or lyrics providers
+    Network,
+    /// Lyrics provider responded with an error status
+    LyricsProvider {
+        status: u16,
+    },
+    /// AI backend rejected the request or responded with something unexpected
+    AiProvider {
+        message: String,
+    },
+    /// Other HTTP service responded with an error status
+    ExternalService {
+        status: u16,
+    },
+    DbUnavailable,
+    Db,
+    RedisUnavailable,
+    Redis,
+    Unknown,
+}
+
+impl std::fmt::Display for RustifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SpotifyRateLimited { retry_after } => write!(
+                f,
+                "Spotify rate limit, retry after {}s",
+                retry_after.num_seconds()
+            ),
+            Self::SpotifyUnavailable { status } => write!(f, "Spotify is unavailable ({status})"),
+            Self::SpotifyUnavailableInCountry => f.write_str("Spotify is unavailable in country"),
+            Self::SpotifyAuth { description } => write!(f, "Spotify auth error: {description}"),
+            Self::SpotifyInvalidToken => f.write_str("Spotify token is invalid"),
+            Self::SpotifyApi { status, message } => {
+                write!(f, "Spotify API error ({status}): {message}")
+            },
+            Self::SpotifyUnparsable => f.write_str("Spotify error response cannot be parsed"),
+            Self::SpotifyClient { message } => write!(f, "Spotify client error: {message}"),
+            Self::TelegramBotBlocked => f.write_str("Bot is blocked by user"),
+            Self::TelegramRetryAfter => f.write_str("Telegram rate limit"),
+            Self::TelegramApi => f.write_str("Telegram API error"),
+            Self::Network => f.write_str("Network error"),
+            Self::LyricsProvider { status } => write!(f, "Lyrics provider error ({status})"),
+            Self::AiProvider { message } => write!(f, "AI provider error: {message}"),
+            Self::ExternalService { status } => write!(f, "External service error ({status})"),
+            Self::DbUnavailable => f.write_str("Database is unavailable"),
+            Self::Db => f.write_str("Database error"),
+            Self::RedisUnavailable => f.write_str("Redis is unavailable"),
+            Self::Redis => f.write_str("Redis error"),
+            Self::Unknown => f.write_str("Unknown error"),
+        }
+    }
+}
+
+impl std::error::Error for RustifyError {}
+
+impl RustifyError {
+    /// Finds known error in the chain.
+    ///
+    /// Spotify, lyrics and AI layers return typed errors already. Telegram, database and Redis
+    /// clients are called directly, so their errors are classified here.
+    pub async fn from_anyhow(err: &mut anyhow::Error) -> Self {
+        if let Some(err) = err.chain().find_map(|err| err.downcast_ref::<Self>()) {
+            return err.clone();
+        }
+
+        if let Some(response) = spotify::SpotifyError::extract_response(err) {
+            return Self::from_spotify_response(response).await;
+        }
+
+        if let Some(err) = err.downcast_ref::<rspotify::ClientError>() {
+            return Self::from_spotify_client(err);
+        }
+
+        if let Some(err) = err.downcast_ref::<teloxide::RequestError>() {
+            return Self::from_telegram(err);
+        }
+
+        if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+            return err
+                .status()
+                .map_or(Self::Network, |status| Self::ExternalService {
+                    status: status.as_u16(),
+                });
+        }
+
+        if let Some(err) = err.downcast_ref::<sea_orm::DbErr>() {
+            return match err {
+                sea_orm::DbErr::ConnectionAcquire(_) | sea_orm::DbErr::Conn(_) => {
+                    Self::DbUnavailable
+                },
+                _ => Self::Db,
+            };
+        }
+
+        if err.downcast_ref::<deadpool_redis::PoolError>().is_some() {
+            return Self::RedisUnavailable;
+        }
+
+        if let Some(err) = err.downcast_ref::<deadpool_redis::redis::RedisError>() {
+            return if err.is_timeout() || err.is_connection_dropped() || err.is_io_error() {
+                Self::RedisUnavailable
+            } else {
+                Self::Redis
+            };
+        }
+
+        Self::Unknown
+    }
+
+    /// Reads error response body, so Spotify client errors are converted once
+    pub async fn from_spotify(mut err: rspotify::ClientError) -> Self {
+        if let rspotify::ClientError::Http(box rspotify::http::HttpError::StatusCode(response)) =
+            &mut err
+        {
+            return Self::from_spotify_response(response).await;
+        }
+
+        Self::from_spotify_client(&err)
+    }
+
+    #[must_use]
+    pub fn from_lyrics(err: &reqwest::Error) -> Self {
+        err.status()
+            .map_or(Self::Network, |status| Self::LyricsProvider {
+                status: status.as_u16(),
+            })
+    }
+
+    #[must_use]
+    pub fn from_ai(err: &async_openai::error::OpenAIError) -> Self {
+        match err {
+            async_openai::error::OpenAIError::Reqwest(err) if err.status().is_none() => {
+                Self::Network
+            },
+            _ => Self::AiProvider {
+                message: err.to_string(),
+            },
+        }
+    }
+
+    async fn from_spotify_response(response: &mut reqwest_compat::Response) -> Self {
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest_compat::header::RETRY_AFTER)
+                .and_then(|header| header.to_str().ok())
+                .and_then(|header| header.trim().parse().ok())
+                .map_or(DEFAULT_RETRY_AFTER, chrono::Duration::seconds);
+
+            return Self::SpotifyRateLimited { retry_after };
+        }
+
+        match spotify::SpotifyError::from_response(response).await {
+            Ok(spotify::SpotifyError::Regular(err)) => {
+                Self::from_spotify_regular(err.error.status, err.error.message)
+            },
+            Ok(spotify::SpotifyError::Auth(err)) => Self::SpotifyAuth {
+                description: err.error_description,
+            },
+            Err(err) => {
+                tracing::error!(err = ?err, "Had an issue parsing responce");
+
+                Self::SpotifyUnparsable
+            },
+        }
+    }
+
+    fn from_spotify_regular(status: u32, message: String) -> Self {
+        if (500..=599).contains(&status) {
+            return Self::SpotifyUnavailable { status };
+        }
+
+        if status == 403 && message == "Spotify is unavailable in this country" {
+            return Self::SpotifyUnavailableInCountry;
+        }
+
+        Self::SpotifyApi { status, message }
+    }
+
+    fn from_spotify_client(err: &rspotify::ClientError) -> Self {
+        match err {
+            rspotify::ClientError::InvalidToken => Self::SpotifyInvalidToken,
+            rspotify::ClientError::Http(box rspotify::http::HttpError::Client(_))
+            | rspotify::ClientError::Io(_) => Self::Network,
+            _ => Self::SpotifyClient {
+                message: err.to_string(),
+            },
+        }
+    }
+
+    fn from_telegram(err: &teloxide::RequestError) -> Self {
+        match err {
+            teloxide::RequestError::Api(ApiError::BotBlocked | ApiError::UserDeactivated) => {
+                Self::TelegramBotBlocked
+            },
+            teloxide::RequestError::RetryAfter(_) => Self::TelegramRetryAfter,
+            teloxide::RequestError::Network(_) | teloxide::RequestError::Io(_) => Self::Network,
+            _ => Self::TelegramApi,
+        }
+    }
+
+    /// Central table of error handling decisions
+    #[must_use]
+    pub fn policy(&self) -> ErrorPolicy {
+        use ErrorClass::{Permanent, Retryable, UserActionable};
+
+        let (class, retry, notify, suspend) = match self {
+            // Requests are postponed by rate limiter, retrying right away makes it worse
+            Self::SpotifyRateLimited { .. } | Self::TelegramRetryAfter => {
+                (Retryable, false, false, None)
+            },
+            Self::SpotifyUnavailable { .. }
+            | Self::Network
+            | Self::DbUnavailable
+            | Self::RedisUnavailable => (Retryable, true, false, None),
+            Self::SpotifyUnavailableInCountry => (
+                UserActionable,
+                false,
+                true,
+                Some(UserStatus::SpotifyForbidden),
+            ),
+            Self::SpotifyAuth { .. } | Self::SpotifyInvalidToken => (
+                UserActionable,
+                false,
+                true,
+                Some(UserStatus::SpotifyTokenInvalid),
+            ),
+            // User cannot be notified, there is nobody to talk to
+            Self::TelegramBotBlocked => {
+                (UserActionable, false, false, Some(UserStatus::BotBlocked))
+            },
+            Self::SpotifyApi { .. }
+            | Self::SpotifyUnparsable
+            | Self::SpotifyClient { .. }
+            | Self::TelegramApi
+            | Self::LyricsProvider { .. }
+            | Self::AiProvider { .. }
+            | Self::ExternalService { .. }
+            | Self::Db
+            | Self::Redis
+            | Self::Unknown => (Permanent, false, false, None),
+        };
+
+        ErrorPolicy {
+            class,
+            retry,
+            notify,
+            suspend,
+        }
+    }
+
+    /// Error is recognized and handled by policy, not just logged
+    #[must_use]
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Self::Unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spotify_regular_classification() {
+        assert!(matches!(
+            RustifyError::from_spotify_regular(503, "Service unavailable".into()),
+            RustifyError::SpotifyUnavailable { status: 503 }
+        ));
+        assert!(matches!(
+            RustifyError::from_spotify_regular(
+                403,
+                "Spotify is unavailable in this country".into()
+            ),
+            RustifyError::SpotifyUnavailableInCountry
+        ));
+        assert!(matches!(
+            RustifyError::from_spotify_regular(404, "Not found".into()),
+            RustifyError::SpotifyApi { status: 404, .. }
+        ));
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = RustifyError::SpotifyInvalidToken.policy();
+
+        assert_eq!(policy.class, ErrorClass::UserActionable);
+        assert!(policy.notify);
+        assert_eq!(policy.suspend, Some(UserStatus::SpotifyTokenInvalid));
+
+        assert!(
+            RustifyError::SpotifyUnavailable { status: 502 }
+                .policy()
+                .retry
+        );
+        assert!(
+            !RustifyError::SpotifyRateLimited {
+                retry_after: DEFAULT_RETRY_AFTER
+            }
+            .policy()
+            .retry,
+            "Rate limiter postpones requests instead"
+        );
+
+        let policy = RustifyError::TelegramBotBlocked.policy();
+
+        assert!(!policy.notify, "Blocked bot cannot send messages");
+        assert_eq!(policy.suspend, Some(UserStatus::BotBlocked));
+    }
+
+    #[tokio::test]
+    async fn test_from_anyhow() {
+        let mut err = anyhow::Error::from(rspotify::ClientError::InvalidToken).context("Get track");
+
+        assert!(matches!(
+            RustifyError::from_anyhow(&mut err).await,
+            RustifyError::SpotifyInvalidToken
+        ));
+
+        let mut err =
+            anyhow::Error::from(teloxide::RequestError::Api(ApiError::BotBlocked)).context("Send");
+
+        assert!(matches!(
+            RustifyError::from_anyhow(&mut err).await,
+            RustifyError::TelegramBotBlocked
+        ));
+
+        let mut err = anyhow::anyhow!("Something else");
+
+        assert!(!RustifyError::from_anyhow(&mut err).await.is_known());
+    }
+
+    fn status_error(status: u16) -> reqwest::Error {
+        let response = axum::http::Response::builder()
+            .status(status)
+            .body("")
+            .unwrap();
+
+        reqwest::Response::from(response)
+            .error_for_status()
+            .unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn test_from_anyhow_typed() {
+        let mut err = anyhow::Error::from(RustifyError::from_lyrics(&status_error(502)))
+            .context("Search lyrics");
+
+        assert!(matches!(
+            RustifyError::from_anyhow(&mut err).await,
+            RustifyError::LyricsProvider { status: 502 }
+        ));
+
+        // Origin is unknown, so it's not blamed on lyrics providers
+        let mut err = anyhow::Error::from(status_error(502)).context("Deliver webhook");
+
+        assert!(matches!(
+            RustifyError::from_anyhow(&mut err).await,
+            RustifyError::ExternalService { status: 502 }
+        ));
+
+        let err = async_openai::error::OpenAIError::InvalidArgument("No model".into());
+
+        assert!(matches!(
+            RustifyError::from_ai(&err),
+            RustifyError::AiProvider { .. }
+        ));
+    }
+}
@@ -1,15 +1,12 @@
-// use anyhow::Context;
-use reqwest::StatusCode;
-use teloxide::ApiError;
 use teloxide::payloads::SendMessageSetters as _;
 use teloxide::prelude::Requester as _;
 use teloxide::types::ChatId;
 
+use super::error::{ErrorClass, ErrorPolicy, RustifyError};
 use crate::app::App;
 use crate::entity::prelude::UserStatus;
-use crate::services::{MetricsService, UserService};
-use crate::spotify;
-// use crate::spotify::auth::SpotifyAuthService;
+use crate::queue::webhook;
+use crate::services::{MetricsService, SpotifyRateLimitService, UserService, WebhookEvent};
 use crate::telegram::commands::UserCommandDisplay;
 use crate::telegram::keyboards::StartKeyboard;
 
@@ -17,6 +14,8 @@ use crate::telegram::keyboards::StartKeyboard;
 pub struct ErrorHandlingResult {
     pub handled: bool,
     pub user_notified: bool,
+    /// Class of the error, when handler managed to get that far
+    pub class: Option<ErrorClass>,
 }
 
 impl ErrorHandlingResult {
@@ -25,6 +24,7 @@ impl ErrorHandlingResult {
         Self {
             handled: false,
             user_notified: false,
+            class: None,
         }
     }
 
@@ -33,6 +33,7 @@ impl ErrorHandlingResult {
         Self {
             handled: true,
             user_notified: false,
+            class: None,
         }
     }
 
@@ -41,165 +42,151 @@ impl ErrorHandlingResult {
         Self {
             handled: true,
             user_notified: true,
+            class: None,
         }
     }
 }
 
-#[tracing::instrument(skip_all, fields(%user_id))]
-pub async fn handle_blocked_bot(
-    err: &mut anyhow::Error,
-    app: &App,
-    user_id: &str,
-) -> anyhow::Result<ErrorHandlingResult> {
-    let Some(err) = err.downcast_mut::<teloxide::RequestError>() else {
-        return Ok(ErrorHandlingResult::unhandled());
-    };
-
-    if matches!(err, teloxide::RequestError::Api(ApiError::BotBlocked)) {
-        UserService::set_status(app.db(), user_id, UserStatus::BotBlocked).await?;
+#[tracing::instrument(skip_all, fields(%user_id, ?status))]
+async fn suspend(app: &App, user_id: &str, status: UserStatus) -> anyhow::Result<()> {
+    UserService::set_status(app.db(), user_id, status).await?;
 
-        return Ok(ErrorHandlingResult::handled_notified());
-    }
+    webhook::emit(
+        app,
+        user_id,
+        WebhookEvent::UserStatusChanged,
+        webhook::user_status_data(status),
+    )
+    .await;
 
-    return Ok(ErrorHandlingResult::unhandled());
+    Ok(())
 }
 
 #[tracing::instrument(skip_all, fields(%user_id))]
-pub async fn spotify_resp_error(
-    err: &mut anyhow::Error,
+async fn notify(
     app: &App,
     user_id: &str,
     locale: &str,
-) -> anyhow::Result<ErrorHandlingResult> {
-    let Some(response) = spotify::SpotifyError::extract_response(err) else {
-        return Ok(ErrorHandlingResult::unhandled());
-    };
-
-    if response.status() == StatusCode::TOO_MANY_REQUESTS {
-        tracing::info!("User got a 429 error (too many requests)");
-
-        MetricsService::spotify_429_inc(&mut app.redis_conn().await?).await?;
-
-        /*
-        let header = response
-            .headers()
-            .get("Retry-After")
-            .context("Need Retry-After header to proceed")?;
-
-        let retry_after: i64 = header.to_str()?.parse()?;
-
-        SpotifyAuthService::suspend_for(
-            app.db(),
-            &[user_id],
-            chrono::Duration::seconds(retry_after),
-        )
-        .await?;
-        */
+    error: &RustifyError,
+) -> anyhow::Result<()> {
+    let chat_id = ChatId(user_id.parse()?);
+
+    match error {
+        RustifyError::SpotifyUnavailableInCountry => {
+            app.bot()
+                .send_message(
+                    chat_id,
+                    t!(
+                        "error.unavailable-in-country",
+                        locale = locale,
+                        command = UserCommandDisplay::Login,
+                    ),
+                )
+                .reply_markup(StartKeyboard::markup(locale))
+                .await?;
+        },
+        RustifyError::SpotifyAuth { description } => {
+            app.bot()
+                .send_message(
+                    chat_id,
+                    t!(
+                        "error.spotify-auth-failed",
+                        locale = locale,
+                        command = UserCommandDisplay::Login,
+                        error = description,
+                    ),
+                )
+                .await?;
+        },
+        RustifyError::SpotifyInvalidToken => {
+            app.bot()
+                .send_message(
+                    chat_id,
+                    t!(
+                        "error.spotify-invalid-token",
+                        locale = locale,
+                        command = UserCommandDisplay::Login
+                    ),
+                )
+                .reply_markup(StartKeyboard::markup(locale))
+                .await?;
+        },
+        _ => {
+            tracing::warn!(
+                ?error,
+                "Policy requires notification, but there is no message"
+            );
+        },
     }
 
-    match spotify::SpotifyError::from_anyhow(err).await {
-        Ok(Some(err)) => {
-            match err {
-                spotify::SpotifyError::Regular(serr) => {
-                    if serr.error.status >= 500 && serr.error.status <= 599 {
-                        // NOTE: Ignore these errors
-                        // They are just spam
-
-                        return Ok(ErrorHandlingResult::handled());
-                    }
-
-                    if serr.error.status == 403
-                        && serr.error.message == "Spotify is unavailable in this country"
-                    {
-                        tracing::error!(err = ?serr, "Spotify is unavailable in this country. Stopping user checks");
-
-                        UserService::set_status(app.db(), user_id, UserStatus::SpotifyForbidden)
-                            .await?;
-
-                        app.bot()
-                            .send_message(
-                                ChatId(user_id.parse()?),
-                                t!(
-                                    "error.unavailable-in-country",
-                                    locale = locale,
-                                    command = UserCommandDisplay::Login,
-                                ),
-                            )
-                            .reply_markup(StartKeyboard::markup(locale))
-                            .await?;
-
-                        return Ok(ErrorHandlingResult::handled_notified());
-                    }
-
-                    // TODO: I need to inspect what errors to notify
-                    tracing::error!(err = ?serr, "Regular Spotify Error Happened");
-
-                    return Ok(ErrorHandlingResult::handled());
-                },
-                spotify::SpotifyError::Auth(serr) => {
-                    tracing::error!(err = ?serr, "Auth Spotify Error");
-
-                    UserService::set_status(app.db(), user_id, UserStatus::SpotifyTokenInvalid)
-                        .await?;
-
-                    app.bot()
-                        .send_message(
-                            ChatId(user_id.parse()?),
-                            t!(
-                                "error.spotify-auth-failed",
-                                locale = locale,
-                                command = UserCommandDisplay::Login,
-                                error = serr.error_description,
-                            ),
-                        )
-                        .await?;
-
-                    return Ok(ErrorHandlingResult::handled_notified());
-                },
-            };
-        },
-        Err(err) => {
-            tracing::error!(err = ?err, "Had an issue parsing responce");
+    Ok(())
+}
 
-            return Ok(ErrorHandlingResult::handled());
-        },
-        Ok(None) => return Ok(ErrorHandlingResult::unhandled()),
-    };
+/// Metrics are best-effort, failing to record them must not stop policy actions
+#[tracing::instrument(skip_all)]
+async fn record_metrics(app: &App, error: &RustifyError, class: ErrorClass) -> anyhow::Result<()> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    MetricsService::errors_inc(&mut redis_conn, class).await?;
+
+    if matches!(error, RustifyError::SpotifyRateLimited { .. }) {
+        MetricsService::spotify_429_inc(&mut redis_conn).await?;
+    }
+
+    Ok(())
 }
 
 #[tracing::instrument(skip_all, fields(%user_id))]
-pub async fn spotify_client_error(
-    err: &mut anyhow::Error,
+async fn apply_policy(
+    err: &anyhow::Error,
     app: &App,
     user_id: &str,
     locale: &str,
+    error: &RustifyError,
+    policy: ErrorPolicy,
 ) -> anyhow::Result<ErrorHandlingResult> {
-    let Some(err) = err.downcast_mut::<rspotify::ClientError>() else {
-        return Ok(ErrorHandlingResult::unhandled());
-    };
-
-    if matches!(err, rspotify::ClientError::InvalidToken) {
-        tracing::error!(err = ?err, user_id, "User has Invalid Spotify Token");
-        UserService::set_status(app.db(), user_id, UserStatus::SpotifyTokenInvalid).await?;
-
-        app.bot()
-            .send_message(
-                ChatId(user_id.parse()?),
-                t!(
-                    "error.spotify-invalid-token",
-                    locale = locale,
-                    command = UserCommandDisplay::Login
-                ),
+    match error {
+        RustifyError::Unknown => {
+            tracing::error!(err = ?err, "Unhandled Error");
+
+            return Ok(ErrorHandlingResult {
+                class: Some(policy.class),
+                ..ErrorHandlingResult::unhandled()
+            });
+        },
+        RustifyError::SpotifyRateLimited { retry_after } => {
+            tracing::info!("User got a 429 error (too many requests)");
+
+            SpotifyRateLimitService::set_retry_after(
+                &mut app.redis_conn().await?,
+                app.spotify_rate_limiter().client_id(),
+                *retry_after,
             )
-            .reply_markup(StartKeyboard::markup(locale))
             .await?;
+        },
+        // NOTE: These errors are just spam
+        RustifyError::SpotifyUnavailable { .. } => {
+            tracing::debug!(err = ?err, "Spotify is unavailable");
+        },
+        _ => {
+            tracing::error!(err = ?err, ?error, ?policy, "Handled error happened");
+        },
+    }
 
-        return Ok(ErrorHandlingResult::handled_notified());
+    if let Some(status) = policy.suspend {
+        suspend(app, user_id, status).await?;
     }
 
-    tracing::error!(err = ?err, "Spotify Client Has an Issue");
+    if policy.notify {
+        notify(app, user_id, locale, error).await?;
+    }
 
-    Ok(ErrorHandlingResult::handled())
+    Ok(ErrorHandlingResult {
+        handled: !policy.retry,
+        // Nobody to send a general error to
+        user_notified: policy.notify || policy.suspend == Some(UserStatus::BotBlocked),
+        class: Some(policy.class),
+    })
 }
 
 #[tracing::instrument(skip_all, fields(%user_id))]
@@ -209,24 +196,16 @@ async fn handle_inner(
     user_id: &str,
     locale: &str,
 ) -> anyhow::Result<ErrorHandlingResult> {
-    let res = spotify_resp_error(err, app, user_id, locale).await?;
-    if res.handled {
-        return Ok(res);
-    }
+    let error = RustifyError::from_anyhow(err).await;
+    let policy = error.policy();
 
-    let res = spotify_client_error(err, app, user_id, locale).await?;
-    if res.handled {
-        return Ok(res);
-    }
+    let res = apply_policy(err, app, user_id, locale, &error, policy).await;
 
-    let res = handle_blocked_bot(err, app, user_id).await?;
-    if res.handled {
-        return Ok(res);
+    if let Err(err) = record_metrics(app, &error, policy.class).await {
+        tracing::error!(err = ?err, "Failed to record error metrics");
     }
 
-    tracing::error!(err = ?err, "Unhandled Error");
-
-    Ok(ErrorHandlingResult::unhandled())
+    res
 }
 
 #[tracing::instrument(skip_all, fields(%user_id))]
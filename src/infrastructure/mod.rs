@@ -1,2 +1,3 @@
+pub mod error;
 pub mod error_handler;
 pub mod logger;
@@ -10,6 +10,7 @@ use rustrict::is_whitespace;
 use strsim::normalized_damerau_levenshtein;
 
 use super::utils::get_track_names;
+use crate::infrastructure::error::RustifyError;
 use crate::lyrics::BEST_FIT_THRESHOLD;
 use crate::lyrics::utils::SearchResultConfidence;
 use crate::spotify::ShortTrack;
@@ -153,8 +154,10 @@ impl GeniusLocal {
                 .header("Authorization", &self.token)
                 .query(&[("q", q)])
                 .send()
-                .await?
-                .error_for_status()?
+                .await
+                .map_err(|err| RustifyError::from_lyrics(&err))?
+                .error_for_status()
+                .map_err(|err| RustifyError::from_lyrics(&err))?
                 .json()
                 .await?;
 
@@ -215,13 +218,18 @@ impl GeniusLocal {
             .get(format!("{}/{}/lyrics", self.service_url, hit.id))
             .header("Authorization", &self.token)
             .send()
-            .await?;
+            .await
+            .map_err(|err| RustifyError::from_lyrics(&err))?;
 
         if res.status() == StatusCode::NOT_FOUND {
             return Ok(vec![]);
         }
 
-        let res: LyricsResponse = res.error_for_status()?.json().await?;
+        let res: LyricsResponse = res
+            .error_for_status()
+            .map_err(|err| RustifyError::from_lyrics(&err))?
+            .json()
+            .await?;
 
         if res.lyrics.is_empty() {
             return Err(anyhow!("Cannot get lyrics, for some reason for {}", hit.id));
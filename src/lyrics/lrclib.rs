@@ -11,6 +11,7 @@ use strsim::normalized_damerau_levenshtein;
 
 use super::BEST_FIT_THRESHOLD;
 use super::utils::get_track_names;
+use crate::infrastructure::error::RustifyError;
 use crate::lyrics::utils::SearchResultConfidence;
 use crate::spotify::ShortTrack;
 
@@ -211,7 +212,8 @@ impl LrcLib {
                 .get(url.clone())
                 .header("Lrclib-Client", "Rustify (https://github.com/vtvz/rustify)")
                 .send()
-                .await?
+                .await
+                .map_err(|err| RustifyError::from_lyrics(&err))?
                 .text()
                 .await?;
 
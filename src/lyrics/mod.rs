@@ -14,7 +14,7 @@ pub mod utils;
 
 pub const BEST_FIT_THRESHOLD: f64 = 0.6;
 
-#[derive(Display)]
+#[derive(Display, Clone, Copy, Serialize, Deserialize)]
 pub enum Provider {
     Musixmatch,
     Genius,
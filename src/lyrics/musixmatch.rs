@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Value, from_value};
 use tokio::sync::Mutex;
 
+use crate::infrastructure::error::RustifyError;
 use crate::spotify::ShortTrack;
 use crate::utils::serde::{bool_from_int, lines_from_string};
 
@@ -149,7 +150,10 @@ impl Musixmatch {
             .send()
             .await;
 
-        let text = res?.text().await?;
+        let text = res
+            .map_err(|err| RustifyError::from_lyrics(&err))?
+            .text()
+            .await?;
 
         let root: serde_json::Value = serde_json::from_str(&text)?;
 
@@ -12,6 +12,7 @@ extern crate serde;
 #[macro_use]
 extern crate rust_i18n;
 
+pub mod ai;
 pub mod app;
 pub mod cli;
 pub mod entity;
@@ -24,6 +25,8 @@ pub mod rickroll;
 pub mod services;
 pub mod spotify;
 pub mod telegram;
+#[cfg(test)]
+pub mod testing;
 pub mod tick;
 pub mod user;
 pub mod utils;
@@ -45,6 +48,9 @@ enum CliCommands {
     /// Manage users
     #[command(subcommand)]
     Users(cli::users::UsersCommands),
+    /// Inspect and replay track checks failed all retries
+    #[command(subcommand)]
+    DeadLetters(cli::dead_letters::DeadLettersCommands),
 }
 
 #[tokio::main(worker_threads = 4)]
@@ -57,5 +63,6 @@ async fn main() {
         CliCommands::Queues => workers::queues::work().await,
         CliCommands::Server => workers::server::work().await,
         CliCommands::Users(cmd) => cli::users::run(cmd).await,
+        CliCommands::DeadLetters(cmd) => cli::dead_letters::run(cmd).await,
     }
 }
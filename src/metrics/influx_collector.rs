@@ -9,6 +9,7 @@ use tracing::Instrument as _;
 use crate::app::App;
 use crate::entity::prelude::*;
 use crate::metrics::influx::InfluxClient;
+use crate::queue::{dead_letter, track_check};
 use crate::services::{
     MetricsService,
     TrackLanguageStatsService,
@@ -48,9 +49,20 @@ struct TimingsStats {
     users_process_time: u64,
     users_checked: u64,
     users_processed: u64,
+    users_scheduled: u64,
     threads_count: u64,
 }
 
+#[derive(InfluxDbWriteable, Debug)]
+struct TickShardStats {
+    time: Timestamp,
+    users_scheduled: u64,
+    #[influxdb(tag)]
+    shard: String,
+    #[influxdb(tag)]
+    worker_id: String,
+}
+
 #[derive(InfluxDbWriteable, Debug)]
 struct TickHealthStats {
     time: Timestamp,
@@ -65,6 +77,22 @@ struct ErrorsStats {
     spotify_429: u64,
 }
 
+#[derive(InfluxDbWriteable, Debug)]
+struct ErrorClassStats {
+    time: Timestamp,
+    count: u64,
+    #[influxdb(tag)]
+    class: String,
+}
+
+#[derive(InfluxDbWriteable, Debug)]
+struct DeadLetterStats {
+    time: Timestamp,
+    count: u64,
+    #[influxdb(tag)]
+    queue: String,
+}
+
 #[derive(InfluxDbWriteable, Debug)]
 struct UsersStatusStats {
     time: Timestamp,
@@ -113,6 +141,7 @@ pub async fn collect(client: &InfluxClient, app: &App) -> anyhow::Result<()> {
         lyrics_musixmatch,
         lyrics_lrclib,
         lyrics_analyzed,
+        ..
     } = UserService::get_stats(app.db(), None).await?;
 
     let tick_health_status = utils::tick_health().await;
@@ -154,6 +183,12 @@ pub async fn collect(client: &InfluxClient, app: &App) -> anyhow::Result<()> {
             spotify_429: MetricsService::spotify_429_get(&mut redis_conn).await?,
         }
         .into_query("errors"),
+        DeadLetterStats {
+            time,
+            count: dead_letter::depth(&mut redis_conn, track_check::DEAD_LETTER_QUEUE).await?,
+            queue: track_check::DEAD_LETTER_QUEUE.into(),
+        }
+        .into_query("dead_letters"),
         Uptime::new(time).into_query("uptime"),
     ];
 
@@ -169,6 +204,17 @@ pub async fn collect(client: &InfluxClient, app: &App) -> anyhow::Result<()> {
         );
     }
 
+    for (class, count) in MetricsService::errors_get(&mut redis_conn).await? {
+        metrics.push(
+            ErrorClassStats {
+                time,
+                count,
+                class: class.as_ref().into(),
+            }
+            .into_query("error_class"),
+        );
+    }
+
     for (language, count) in TrackLanguageStatsService::stats_all_users(app.db(), None).await? {
         metrics.push(
             TrackLanguageStats {
@@ -202,11 +248,29 @@ pub async fn collect_user_timings(
         check_interval: report.check_interval.as_millis() as u64,
         users_checked: report.users_checked as u64,
         users_processed: report.users_processed as u64,
+        users_scheduled: report.users_scheduled as u64,
         threads_count: report.threads_count as u64,
     }
     .into_query("process_timings");
 
-    client.write([timings_stats]).await?.error_for_status()?;
+    let mut metrics = vec![timings_stats];
+
+    for (shard, users_scheduled) in report.shards {
+        metrics.push(
+            TickShardStats {
+                time,
+                users_scheduled: users_scheduled as u64,
+                shard: shard.to_string(),
+                worker_id: report.worker_id.to_owned(),
+            }
+            .into_query("tick_shards"),
+        );
+    }
+
+    client
+        .write(metrics.into_iter())
+        .await?
+        .error_for_status()?;
 
     Ok(())
 }
@@ -38,10 +38,14 @@ pub struct PrometheusMetrics {
     pub process_users_checked: IntCounter,
     pub process_users_processed: IntCounter,
     pub process_parallel_threads: IntGauge,
+    pub process_users_scheduled: IntGauge,
+    pub process_shard_users_scheduled: IntGaugeVec,
     pub ticks: IntGauge,
     pub ticks_unhealthy: IntGauge,
     pub ticks_lagging: IntGauge,
     pub spotify_rate_limit_errors: IntGauge,
+    pub errors_by_class: IntGaugeVec,
+    pub dead_letters: IntGaugeVec,
     pub users_by_status: IntGaugeVec,
     pub tracks_by_language: IntGaugeVec,
     pub uptime: IntGauge,
@@ -147,6 +151,21 @@ impl PrometheusClient {
             )
             .context("Failed to register process_parallel_threads metric")?,
 
+            process_users_scheduled: register_int_gauge_with_registry!(
+                "process_users_scheduled",
+                "Number of users in the polling schedule",
+                registry
+            )
+            .context("Failed to register process_users_scheduled metric")?,
+
+            process_shard_users_scheduled: register_int_gauge_vec_with_registry!(
+                "process_shard_users_scheduled",
+                "Number of scheduled users in shards owned by the worker",
+                &["shard", "worker_id"],
+                registry
+            )
+            .context("Failed to register process_shard_users_scheduled metric")?,
+
             ticks: register_int_gauge_with_registry!(
                 "ticks",
                 "Total number of active ticks",
@@ -175,6 +194,22 @@ impl PrometheusClient {
             )
             .context("Failed to register spotify_rate_limit_errors metric")?,
 
+            errors_by_class: register_int_gauge_vec_with_registry!(
+                "errors_by_class_total",
+                "Handled errors by class",
+                &["class"],
+                registry
+            )
+            .context("Failed to register errors_by_class metric")?,
+
+            dead_letters: register_int_gauge_vec_with_registry!(
+                "dead_letters_total",
+                "Queue tasks failed all retries",
+                &["queue"],
+                registry
+            )
+            .context("Failed to register dead_letters metric")?,
+
             users_by_status: register_int_gauge_vec_with_registry!(
                 "users_by_status_total",
                 "Users by status",
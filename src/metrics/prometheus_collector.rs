@@ -7,6 +7,7 @@ use tracing::Instrument as _;
 use super::prometheus::PrometheusClient;
 use crate::app::App;
 use crate::entity::prelude::*;
+use crate::queue::{dead_letter, track_check};
 use crate::services::{
     MetricsService,
     TrackLanguageStatsService,
@@ -34,12 +35,16 @@ pub async fn collect(client: &PrometheusClient, app: &App) -> anyhow::Result<()>
         lyrics_musixmatch,
         lyrics_lrclib,
         lyrics_analyzed,
+        ..
     } = UserService::get_stats(app.db(), None).await?;
 
     let tick_health_status = utils::tick_health().await;
 
     let mut redis_conn = app.redis_conn().await?;
     let spotify_429_count = MetricsService::spotify_429_get(&mut redis_conn).await?;
+    let errors = MetricsService::errors_get(&mut redis_conn).await?;
+    let track_check_dead_letters =
+        dead_letter::depth(&mut redis_conn, track_check::DEAD_LETTER_QUEUE).await?;
 
     client
         .metrics()
@@ -105,6 +110,20 @@ pub async fn collect(client: &PrometheusClient, app: &App) -> anyhow::Result<()>
         .spotify_rate_limit_errors
         .set(spotify_429_count.cast_signed());
 
+    for (class, count) in errors {
+        client
+            .metrics()
+            .errors_by_class
+            .with_label_values(&[class.as_ref()])
+            .set(count.cast_signed());
+    }
+
+    client
+        .metrics()
+        .dead_letters
+        .with_label_values(&[track_check::DEAD_LETTER_QUEUE])
+        .set(track_check_dead_letters.cast_signed());
+
     client
         .metrics()
         .uptime
@@ -155,6 +174,21 @@ pub fn collect_user_timings(client: &PrometheusClient, report: &CheckReport) {
         .metrics()
         .process_parallel_threads
         .set(i64::try_from(report.threads_count).unwrap_or(0));
+    client
+        .metrics()
+        .process_users_scheduled
+        .set(i64::try_from(report.users_scheduled).unwrap_or(0));
+
+    // Shards may move to another worker
+    client.metrics().process_shard_users_scheduled.reset();
+
+    for (shard, users_scheduled) in &report.shards {
+        client
+            .metrics()
+            .process_shard_users_scheduled
+            .with_label_values(&[shard.to_string().as_str(), report.worker_id])
+            .set(i64::try_from(*users_scheduled).unwrap_or(0));
+    }
 }
 
 pub async fn collect_daemon(app: &'static App) {
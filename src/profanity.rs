@@ -1,7 +1,9 @@
 use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
+use std::hash::{DefaultHasher, Hash as _, Hasher as _};
 use std::slice::Iter;
 use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use regex::Regex;
 use rustrict::{Trie, Type, is_whitespace};
@@ -11,19 +13,38 @@ static TYPE_THRESHOLD: LazyLock<Type> = LazyLock::new(|| Type::ANY & !Type::SPAM
 static TYPE_CUSTOM: LazyLock<Type> = LazyLock::new(|| Type::MODERATE & Type::EVASIVE);
 static TYPE_TRIGGER: LazyLock<Type> = LazyLock::new(|| Type::INAPPROPRIATE | Type::EVASIVE);
 
+/// Hash of custom words changes, same words give the same version on every instance
+static CUSTOM_WORDS_VERSION: AtomicU64 = AtomicU64::new(0);
+
 pub struct Manager;
 
 impl Manager {
     pub fn add_word(word: &str) {
         unsafe { Trie::customize_default().set(word, *TYPE_CUSTOM) }
+        Self::update_custom_words_version(word, true);
         tracing::trace!(word, "Added custom word");
     }
 
     pub fn remove_word(word: &str) {
         unsafe { Trie::customize_default().set(word, Type::NONE) }
+        Self::update_custom_words_version(word, false);
         tracing::trace!(word, "Removed custom word");
     }
 
+    fn update_custom_words_version(word: &str, added: bool) {
+        let mut hasher = DefaultHasher::new();
+
+        (CUSTOM_WORDS_VERSION.load(Ordering::Relaxed), word, added).hash(&mut hasher);
+
+        CUSTOM_WORDS_VERSION.store(hasher.finish(), Ordering::Relaxed);
+    }
+
+    /// Changes with custom words, so check results stored with other words can be told apart
+    #[must_use]
+    pub fn custom_words_version() -> u64 {
+        CUSTOM_WORDS_VERSION.load(Ordering::Relaxed)
+    }
+
     #[must_use]
     pub fn check(text: &[&str]) -> CheckResult {
         CheckResult::perform(text)
@@ -54,6 +75,12 @@ impl CheckResult {
         self.typ.is(*TYPE_TRIGGER)
     }
 
+    /// Triggers on any not safe line, even mild ones
+    #[must_use]
+    pub fn should_trigger_strict(&self) -> bool {
+        self.typ.is(*TYPE_THRESHOLD)
+    }
+
     fn extract_bad_chars(line: &str, censored: &str) -> Vec<usize> {
         let bad_chars: Vec<_> = line
             .chars()
@@ -0,0 +1,250 @@
+//! Tasks that failed all retries, kept with the error for inspection and replay
+
+use std::sync::LazyLock;
+
+use chrono::NaiveDateTime;
+use deadpool_redis::redis::{AsyncCommands as _, Script};
+use rand::RngExt as _;
+use rand::distr::Alphanumeric;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::utils::Clock;
+
+/// Oldest letters are dropped when queue grows over this
+const MAX_LETTERS: isize = 10_000;
+
+/// Removes letter and returns it, so only one caller can replay it
+static TAKE_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            local letter = redis.call('HGET', KEYS[2], ARGV[1])
+
+            if not letter then
+                return false
+            end
+
+            redis.call('HDEL', KEYS[2], ARGV[1])
+            redis.call('ZREM', KEYS[1], ARGV[1])
+
+            return letter
+        ",
+    )
+});
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeadLetter<T> {
+    pub id: String,
+    pub task: T,
+    pub error: String,
+    pub attempts: usize,
+    pub failed_at: NaiveDateTime,
+}
+
+fn index_key(queue: &str) -> String {
+    format!("rustify:dead_letter:{queue}:index")
+}
+
+fn letters_key(queue: &str) -> String {
+    format!("rustify:dead_letter:{queue}:letters")
+}
+
+#[tracing::instrument(skip_all, fields(%queue, attempts))]
+pub async fn push<T: Serialize>(
+    redis_conn: &mut deadpool_redis::Connection,
+    queue: &str,
+    task: T,
+    err: &anyhow::Error,
+    attempts: usize,
+) -> anyhow::Result<String> {
+    let id: String = rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(10)
+        .map(char::from)
+        .collect();
+
+    let letter = DeadLetter {
+        id: id.clone(),
+        task,
+        error: format!("{err:?}"),
+        attempts,
+        failed_at: Clock::now(),
+    };
+
+    save(redis_conn, queue, &letter).await?;
+
+    Ok(id)
+}
+
+/// Puts taken letter back, e.g. when it cannot be replayed
+pub async fn restore<T: Serialize>(
+    redis_conn: &mut deadpool_redis::Connection,
+    queue: &str,
+    letter: &DeadLetter<T>,
+) -> anyhow::Result<()> {
+    save(redis_conn, queue, letter).await
+}
+
+async fn save<T: Serialize>(
+    redis_conn: &mut deadpool_redis::Connection,
+    queue: &str,
+    letter: &DeadLetter<T>,
+) -> anyhow::Result<()> {
+    let _: () = redis_conn
+        .hset(
+            letters_key(queue),
+            &letter.id,
+            serde_json::to_string(letter)?,
+        )
+        .await?;
+
+    let _: () = redis_conn
+        .zadd(
+            index_key(queue),
+            &letter.id,
+            letter.failed_at.and_utc().timestamp_millis(),
+        )
+        .await?;
+
+    let dropped: Vec<String> = redis_conn
+        .zrange(index_key(queue), 0, -MAX_LETTERS - 1)
+        .await?;
+
+    if !dropped.is_empty() {
+        let _: () = redis_conn.zrem(index_key(queue), &dropped).await?;
+        let _: () = redis_conn.hdel(letters_key(queue), &dropped).await?;
+    }
+
+    Ok(())
+}
+
+/// Newest letters first
+pub async fn list<T: DeserializeOwned>(
+    redis_conn: &mut deadpool_redis::Connection,
+    queue: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<DeadLetter<T>>> {
+    let Some(stop) = limit.checked_sub(1) else {
+        return Ok(vec![]);
+    };
+
+    let ids: Vec<String> = redis_conn
+        .zrevrange(index_key(queue), 0, stop.try_into()?)
+        .await?;
+
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let letters: Vec<Option<String>> = deadpool_redis::redis::cmd("HMGET")
+        .arg(letters_key(queue))
+        .arg(&ids)
+        .query_async(redis_conn)
+        .await?;
+
+    letters
+        .into_iter()
+        .flatten()
+        .map(|letter| Ok(serde_json::from_str(&letter)?))
+        .collect()
+}
+
+pub async fn get<T: DeserializeOwned>(
+    redis_conn: &mut deadpool_redis::Connection,
+    queue: &str,
+    id: &str,
+) -> anyhow::Result<Option<DeadLetter<T>>> {
+    let letter: Option<String> = redis_conn.hget(letters_key(queue), id).await?;
+
+    letter
+        .map(|letter| serde_json::from_str(&letter))
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Removes letter atomically and returns it. `None` if it's already taken or removed
+pub async fn take<T: DeserializeOwned>(
+    redis_conn: &mut deadpool_redis::Connection,
+    queue: &str,
+    id: &str,
+) -> anyhow::Result<Option<DeadLetter<T>>> {
+    let letter: Option<String> = TAKE_SCRIPT
+        .key(index_key(queue))
+        .key(letters_key(queue))
+        .arg(id)
+        .invoke_async(redis_conn)
+        .await?;
+
+    letter
+        .map(|letter| serde_json::from_str(&letter))
+        .transpose()
+        .map_err(Into::into)
+}
+
+pub async fn depth(
+    redis_conn: &mut deadpool_redis::Connection,
+    queue: &str,
+) -> anyhow::Result<u64> {
+    let depth: u64 = redis_conn.zcard(index_key(queue)).await?;
+
+    Ok(depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn test_push_list_take() {
+        let mut redis_conn = TestApp::redis_conn().await.unwrap();
+        // Each run gets its own queue, tests share Redis
+        let queue = format!("test_{}", TestApp::user_id());
+        let err = anyhow::anyhow!("Spotify is down");
+
+        let first = push(&mut redis_conn, &queue, "first", &err, 3)
+            .await
+            .unwrap();
+        let second = push(&mut redis_conn, &queue, "second", &err, 3)
+            .await
+            .unwrap();
+
+        assert_eq!(depth(&mut redis_conn, &queue).await.unwrap(), 2);
+
+        let letters: Vec<DeadLetter<String>> = list(&mut redis_conn, &queue, 10).await.unwrap();
+        let ids: Vec<_> = letters.iter().map(|letter| letter.id.as_str()).collect();
+
+        // Same millisecond is possible, so order is not checked
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&first.as_str()) && ids.contains(&second.as_str()));
+        assert!(letters[0].error.contains("Spotify is down"));
+
+        let letter: DeadLetter<String> = take(&mut redis_conn, &queue, &first)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(letter.task, "first");
+        assert_eq!(letter.attempts, 3);
+
+        // Taken letter is removed, so it cannot be replayed twice
+        assert!(
+            take::<String>(&mut redis_conn, &queue, &first)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            get::<String>(&mut redis_conn, &queue, &first)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(depth(&mut redis_conn, &queue).await.unwrap(), 1);
+
+        restore(&mut redis_conn, &queue, &letter).await.unwrap();
+
+        assert_eq!(depth(&mut redis_conn, &queue).await.unwrap(), 2);
+    }
+}
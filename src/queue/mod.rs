@@ -5,13 +5,16 @@ use apalis_redis::shared::SharedRedisStorage;
 use apalis_redis::{RedisConfig, RedisStorage};
 use redis::aio::MultiplexedConnection;
 
+pub mod dead_letter;
 pub mod track_check;
+pub mod webhook;
 
 pub struct QueueManager {
     #[allow(dead_code)]
     storage: SharedRedisStorage,
 
     track_check_queue: RedisStorage<track_check::TrackCheckQueueTask, MultiplexedConnection>,
+    webhook_queue: RedisStorage<webhook::WebhookQueueTask, MultiplexedConnection>,
 }
 
 impl QueueManager {
@@ -22,6 +25,11 @@ impl QueueManager {
         self.track_check_queue.clone()
     }
 
+    #[must_use]
+    pub fn webhook_queue(&self) -> RedisStorage<webhook::WebhookQueueTask, MultiplexedConnection> {
+        self.webhook_queue.clone()
+    }
+
     pub async fn new(redis_url: &str) -> anyhow::Result<Self> {
         let mut conn_info = redis::ConnectionInfo::from_str(redis_url)?;
         conn_info.redis.protocol = redis::ProtocolVersion::RESP3;
@@ -32,9 +40,13 @@ impl QueueManager {
         let track_check_queue = storage
             .make_shared_with_config(RedisConfig::default().set_namespace("rustify:track_check"))?;
 
+        let webhook_queue = storage
+            .make_shared_with_config(RedisConfig::default().set_namespace("rustify:webhook"))?;
+
         Ok(Self {
             storage,
             track_check_queue,
+            webhook_queue,
         })
     }
 }
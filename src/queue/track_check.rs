@@ -1,24 +1,45 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
 use anyhow::Context as _;
-use apalis::prelude::{Data, TaskSink as _};
+use apalis::prelude::{Attempt, Data, TaskSink as _};
 use isolang::Language;
 use itertools::Itertools as _;
 use rspotify::prelude::OAuthClient as _;
 use rustrict::Type;
+use serde::Serialize;
+use serde_json::json;
+use strum_macros::AsRefStr;
 use teloxide::prelude::*;
 use teloxide::types::{InlineKeyboardMarkup, ReplyMarkup};
 
+use crate::ai::AIFeature;
 use crate::app::App;
 use crate::infrastructure::error_handler;
 use crate::lyrics::SearchResult as _;
+use crate::queue::{dead_letter, webhook};
 use crate::services::{
+    AIQuotaOutput,
     AISlopDetectionPrediction,
+    AISlopDetectionProvider,
+    AIUsageService,
+    AgeRating,
+    AnalysisReport,
+    TrackAnalysisService,
+    TrackCheckCacheService,
+    TrackCheckKind,
+    TrackCheckResult,
     TrackLanguageStatsService,
     TrackStatusService,
+    TrackTranslationService,
+    UserProfaneWordStatsService,
     UserService,
     UserWordWhitelistService,
+    WebhookEvent,
     WordStatsService,
 };
-use crate::spotify::ShortTrack;
+use crate::spotify::{ClientResultExt as _, ShortTrack};
+use crate::telegram::actions;
 use crate::telegram::commands::UserCommandDisplay;
 use crate::telegram::inline_buttons::InlineButtons;
 use crate::telegram::inline_buttons_actions::InlineButtonsActions;
@@ -27,12 +48,41 @@ use crate::user::UserState;
 use crate::utils::StringUtils as _;
 use crate::{lyrics, profanity, telegram};
 
-#[derive(Clone, Serialize, Deserialize)]
+/// Retries before task is moved to dead letters
+pub const RETRIES: usize = 2;
+/// Dead letters namespace of the queue
+pub const DEAD_LETTER_QUEUE: &str = "track_check";
+/// Time for one attempt of the check
+pub const TIMEOUT: Duration = Duration::from_secs(90);
+const _: () = assert!(
+    TIMEOUT.as_secs().cast_signed() < TrackCheckCacheService::LOCK_TTL.num_seconds(),
+    "shared result lock must outlive the check"
+);
+/// How often workers waiting for a shared result check if it's ready
+const SHARED_RESULT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Translation to this locale is checked when lyrics aren't English
+pub const ENGLISH_TRANSLATION: &str = "en";
+/// Kid mode skips tracks rated higher than this
+const KID_MODE_MAX_AGE_RATING: AgeRating = AgeRating::Age12;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct TrackCheckQueueTask {
     track: ShortTrack,
     user_id: String,
 }
 
+impl TrackCheckQueueTask {
+    #[must_use]
+    pub fn track(&self) -> &ShortTrack {
+        &self.track
+    }
+
+    #[must_use]
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+}
+
 #[tracing::instrument(
     skip_all,
     fields(
@@ -53,10 +103,106 @@ pub async fn queue(app: &App, user_id: &str, track: &ShortTrack) -> anyhow::Resu
     Ok(())
 }
 
+/// Puts dead letter back to the queue. Returns `false` if there is no such letter
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn replay(app: &App, id: &str) -> anyhow::Result<bool> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    // Taken first, so concurrent replays don't queue the task twice
+    let Some(letter) =
+        dead_letter::take::<TrackCheckQueueTask>(&mut redis_conn, DEAD_LETTER_QUEUE, id).await?
+    else {
+        return Ok(false);
+    };
+
+    let res = app
+        .queue_manager()
+        .track_check_queue()
+        .push(letter.task.clone())
+        .await;
+
+    if let Err(err) = res {
+        dead_letter::restore(&mut redis_conn, DEAD_LETTER_QUEUE, &letter).await?;
+
+        return Err(err.into());
+    }
+
+    Ok(true)
+}
+
+/// Returns amount of replayed letters
+#[tracing::instrument(skip_all)]
+pub async fn replay_all(app: &App) -> anyhow::Result<usize> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    let depth = dead_letter::depth(&mut redis_conn, DEAD_LETTER_QUEUE).await?;
+    let letters: Vec<dead_letter::DeadLetter<TrackCheckQueueTask>> =
+        dead_letter::list(&mut redis_conn, DEAD_LETTER_QUEUE, depth.try_into()?).await?;
+
+    let mut replayed = 0;
+
+    for letter in letters {
+        if replay(app, &letter.id).await? {
+            replayed += 1;
+        }
+    }
+
+    Ok(replayed)
+}
+
 #[tracing::instrument(skip_all, fields(user_id = %data.user_id, track_id = %data.track.id()))]
-pub async fn consume(data: TrackCheckQueueTask, app: Data<&'static App>) -> anyhow::Result<()> {
+pub async fn consume(
+    data: TrackCheckQueueTask,
+    app: Data<&'static App>,
+    attempt: Attempt,
+) -> anyhow::Result<()> {
     let app = *app;
 
+    let check = Box::pin(check_task(app, data.clone()));
+
+    attempt_check(app, data, &attempt, TIMEOUT, check).await
+}
+
+/// Final failed attempt, including timed out one, moves task to dead letters
+async fn attempt_check(
+    app: &'static App,
+    data: TrackCheckQueueTask,
+    attempt: &Attempt,
+    timeout: Duration,
+    check: impl Future<Output = anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let res = tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("Track check timed out")));
+
+    let Err(err) = res else {
+        return Ok(());
+    };
+
+    // Attempts are counted from 1
+    if attempt.current() > RETRIES {
+        let res = async {
+            dead_letter::push(
+                &mut app.redis_conn().await?,
+                DEAD_LETTER_QUEUE,
+                data,
+                &err,
+                attempt.current(),
+            )
+            .await
+        }
+        .await;
+
+        match res {
+            Ok(id) => tracing::warn!(err = ?err, %id, "Track check moved to dead letters"),
+            Err(err) => tracing::error!(err = ?err, "Failed to save dead letter"),
+        }
+    }
+
+    Err(err)
+}
+
+async fn check_task(app: &'static App, data: TrackCheckQueueTask) -> anyhow::Result<()> {
     let user_state = app.user_state(&data.user_id).await;
 
     let user_state = match user_state {
@@ -68,18 +214,44 @@ pub async fn consume(data: TrackCheckQueueTask, app: Data<&'static App>) -> anyh
         },
     };
 
+    let increase_skips = || async {
+        if let Err(err) =
+            TrackStatusService::increase_skips(app.db(), user_state.user_id(), data.track.id())
+                .await
+        {
+            tracing::error!(err = ?err, "Error occurred on increasing skipping stats");
+        }
+    };
+
     let err_wrap = || async {
+        let res = check_explicit(app, &user_state, &data.track)
+            .await
+            .context("Check explicit")?;
+
+        if res.blocked {
+            if res.skipped {
+                increase_skips().await;
+            }
+
+            return Ok(());
+        }
+
         let res = check_ai_slop(app, &user_state, &data.track)
             .await
             .context("Check AI Slop")?;
 
         if res.skipped {
-            if let Err(err) =
-                TrackStatusService::increase_skips(app.db(), user_state.user_id(), data.track.id())
-                    .await
-            {
-                tracing::error!(err = ?err, "Error occurred on increasing skipping stats");
-            }
+            increase_skips().await;
+
+            return Ok(());
+        }
+
+        let skipped = check_age_rating(app, &user_state, &data.track)
+            .await
+            .context("Check age rating")?;
+
+        if skipped {
+            increase_skips().await;
 
             return Ok(());
         }
@@ -88,6 +260,10 @@ pub async fn consume(data: TrackCheckQueueTask, app: Data<&'static App>) -> anyh
             .await
             .context("Check lyrics failed")?;
 
+        if res.track_skipped {
+            increase_skips().await;
+        }
+
         UserService::increase_stats_query(user_state.user_id())
             .checked_lyrics(res.profane, res.provider)
             .exec(app.db())
@@ -115,6 +291,159 @@ pub struct CheckBadWordsResult {
     pub found: bool,
     pub profane: bool,
     pub provider: Option<lyrics::Provider>,
+    /// Track is skipped on Spotify by kid-safe profile
+    pub track_skipped: bool,
+}
+
+/// Outcome of a check which can stop the track
+#[derive(Default)]
+pub struct BlockCheckResult {
+    /// Track is not allowed, user is notified and other checks are not needed
+    pub blocked: bool,
+    /// Track is skipped on Spotify
+    pub skipped: bool,
+}
+
+/// Why kid-safe profile doesn't allow the track
+#[derive(Clone, Copy, Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+enum KidSafeReason {
+    Explicit,
+    Profanity,
+    UnverifiedLanguage,
+}
+
+/// Returns result computed by any worker for the track, or computes it while others wait
+#[tracing::instrument(skip_all, fields(?kind, %track_id))]
+async fn shared_result<T, Fut>(
+    app: &App,
+    kind: TrackCheckKind,
+    track_id: &str,
+    compute: impl FnOnce() -> Fut,
+) -> anyhow::Result<T>
+where
+    T: TrackCheckResult,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let deadline = tokio::time::Instant::now() + TrackCheckCacheService::LOCK_TTL.to_std()?;
+
+    let token = loop {
+        let mut redis_conn = app.redis_conn().await?;
+
+        if let Some(value) = TrackCheckCacheService::get(&mut redis_conn, kind, track_id).await? {
+            return Ok(value);
+        }
+
+        if let Some(token) = TrackCheckCacheService::lock(&mut redis_conn, kind, track_id).await? {
+            break Some(token);
+        }
+
+        // Worker holding the lock is stuck, don't wait for it anymore
+        if tokio::time::Instant::now() >= deadline {
+            break None;
+        }
+
+        drop(redis_conn);
+
+        tokio::time::sleep(SHARED_RESULT_POLL_INTERVAL).await;
+    };
+
+    let res = compute().await;
+
+    let mut redis_conn = app.redis_conn().await?;
+
+    match (&res, token.as_deref()) {
+        (Ok(value), token) => {
+            TrackCheckCacheService::set(&mut redis_conn, kind, track_id, value, token).await?;
+        },
+        (Err(_), Some(token)) => {
+            TrackCheckCacheService::unlock(&mut redis_conn, kind, track_id, token).await?;
+        },
+        (Err(_), None) => {},
+    }
+
+    res
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProfaneLine {
+    index_name: String,
+    highlighted: String,
+    words: HashSet<String>,
+}
+
+/// Part of lyrics check which doesn't depend on user
+#[derive(Serialize, Deserialize)]
+struct LyricsAnalysis {
+    provider: lyrics::Provider,
+    language: Language,
+    link: String,
+    link_text: String,
+    link_text_full: String,
+    /// Not safe lines, empty when lyrics are not English or even strict profanity check wasn't triggered
+    lines: Vec<ProfaneLine>,
+    /// Lines are found only by strict check, they are ignored unless user skips any profanity
+    #[serde(default)]
+    strict_only: bool,
+    /// Lyrics aren't English, lines are from their English translation requested by any user
+    #[serde(default)]
+    translated: bool,
+}
+
+async fn analyze_lyrics(app: &App, track: &ShortTrack) -> anyhow::Result<Option<LyricsAnalysis>> {
+    let Some(hit) = app
+        .lyrics()
+        .search_for_track(&mut app.redis_conn().await?, track)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let mut lines = vec![];
+    let mut strict_only = false;
+    let mut translated = false;
+
+    let check = if hit.language() == Language::Eng {
+        Some(profanity::Manager::check(&hit.lyrics()))
+    } else {
+        let translation =
+            TrackTranslationService::find(app.db(), track.id(), ENGLISH_TRANSLATION).await?;
+
+        translated = translation.is_some();
+
+        translation.map(|translation| {
+            let lines: Vec<_> = translation.lines.iter().map(String::as_str).collect();
+
+            profanity::Manager::check(&lines)
+        })
+    };
+
+    if let Some(check) = check
+        && check.should_trigger_strict()
+    {
+        strict_only = !check.should_trigger();
+
+        lines = check
+            .iter()
+            .filter(|profanity::LineResult { typ, .. }| !typ.is(Type::SAFE))
+            .map(|line| ProfaneLine {
+                index_name: hit.line_index_name(line.no),
+                highlighted: line.highlighted(),
+                words: line.get_profine_words(),
+            })
+            .collect();
+    }
+
+    Ok(Some(LyricsAnalysis {
+        provider: hit.provider(),
+        language: hit.language(),
+        link: hit.link(),
+        link_text: hit.link_text(false),
+        link_text_full: hit.link_text(true),
+        lines,
+        strict_only,
+        translated,
+    }))
 }
 
 #[tracing::instrument(
@@ -131,79 +460,161 @@ pub async fn check_profanity(
 ) -> anyhow::Result<CheckBadWordsResult> {
     let mut ret = CheckBadWordsResult::default();
 
-    let Some(hit) = app
-        .lyrics()
-        .search_for_track(&mut app.redis_conn().await?, track)
-        .await?
-    else {
+    let analysis = shared_result(app, TrackCheckKind::Lyrics, track.id(), || {
+        analyze_lyrics(app, track)
+    })
+    .await?;
+
+    let Some(analysis) = analysis else {
         if let Err(err) =
             TrackLanguageStatsService::increase_count(app.db(), None, state.user_id()).await
         {
             tracing::error!(err = ?err, "Error occurred on increasing language stats");
         }
+
+        notify_explicit(
+            app,
+            state,
+            track,
+            &t!(
+                "profanity-check.explicit-no-lyrics",
+                locale = state.locale()
+            ),
+        )
+        .await?;
+
         return Ok(ret);
     };
 
-    ret.provider = Some(hit.provider());
+    ret.provider = Some(analysis.provider);
     ret.found = true;
 
-    if let Err(err) =
-        TrackLanguageStatsService::increase_count(app.db(), Some(hit.language()), state.user_id())
-            .await
+    if let Err(err) = TrackLanguageStatsService::increase_count(
+        app.db(),
+        Some(analysis.language),
+        state.user_id(),
+    )
+    .await
     {
         tracing::error!(err = ?err, "Error occurred on increasing language stats");
     }
 
-    if hit.language() != Language::Eng {
-        tracing::trace!(language = %hit.language(), provider = %hit.provider(), "Track has non English lyrics");
+    if analysis.language != Language::Eng && !analysis.translated {
+        tracing::trace!(language = %analysis.language, provider = %analysis.provider, "Track has non English lyrics");
 
         ret.skipped = true;
+
+        if state.user().skip_unverifiable() {
+            ret.track_skipped =
+                block_for_kids(app, state, track, KidSafeReason::UnverifiedLanguage).await?;
+        } else {
+            notify_explicit(
+                app,
+                state,
+                track,
+                &t!(
+                    "profanity-check.explicit-unsupported-language",
+                    locale = state.locale(),
+                    language = analysis.language.to_name(),
+                ),
+            )
+            .await?;
+        }
+
         return Ok(ret);
     }
 
-    let check = profanity::Manager::check(&hit.lyrics());
+    // Mild words count too when Spotify confirms the track is explicit
+    let strict = state.user().skip_profane() || track.is_explicit();
 
-    if !check.should_trigger() {
+    if analysis.lines.is_empty() || (analysis.strict_only && !strict) {
         return Ok(ret);
     }
 
-    WordStatsService::increase_check_occurence(app.db(), &check.get_profine_words()).await?;
+    let words: HashSet<_> = analysis
+        .lines
+        .iter()
+        .flat_map(|line| line.words.iter().cloned())
+        .collect();
+
+    WordStatsService::increase_check_occurence(app.db(), &words).await?;
 
-    let ok_words =
-        UserWordWhitelistService::get_ok_words_for_user(app.db(), state.user_id()).await?;
+    let ok_words = if state.user().word_whitelist() {
+        UserWordWhitelistService::get_ok_words_for_user(app.db(), state.user_id()).await?
+    } else {
+        HashSet::new()
+    };
 
-    let bad_lines: Vec<_> = check
+    let bad_lines: Vec<_> = analysis
+        .lines
         .iter()
-        .filter(|profanity::LineResult { typ, .. }| !typ.is(Type::SAFE))
-        .filter(|line| {
-            let words = line.get_profine_words();
+        .filter(|line| line.words.difference(&ok_words).next().is_some())
+        .collect();
 
-            words.difference(&ok_words).next().is_some()
-        })
-        .map(|line: &profanity::LineResult| {
-            format!(
-                "<code>{}:</code> {}",
-                hit.line_index_name(line.no),
-                line.highlighted(),
-            )
-        })
+    let profane_words: HashSet<_> = bad_lines
+        .iter()
+        .flat_map(|line| line.words.iter().cloned())
+        .filter(|word| !ok_words.contains(word))
         .collect();
 
+    let bad_lines: Vec<_> = bad_lines
+        .into_iter()
+        .map(|line| format!("<code>{}:</code> {}", line.index_name, line.highlighted))
+        .collect();
+
+    let translated_note = if analysis.translated {
+        t!(
+            "profanity-check.translated",
+            locale = state.locale(),
+            language = analysis.language.to_name(),
+        )
+        .to_string()
+            + "\n\n"
+    } else {
+        String::new()
+    };
+
     if bad_lines.is_empty() {
         return Ok(ret);
     }
 
     ret.profane = true;
 
+    UserProfaneWordStatsService::increase_occurrences(app.db(), state.user_id(), profane_words)
+        .await?;
+
+    webhook::emit(
+        app,
+        state.user_id(),
+        WebhookEvent::ProfaneTrackDetected,
+        json!({
+            "track": webhook::track_data(track),
+            "lyrics_provider": analysis.provider.to_string(),
+            "profane_lines": bad_lines.len(),
+        }),
+    )
+    .await;
+
+    // Kids shouldn't see the lines themselves
+    if state.user().skip_profane() {
+        ret.track_skipped = block_for_kids(app, state, track, KidSafeReason::Profanity).await?;
+
+        return Ok(ret);
+    }
+
     let mut lines = bad_lines.len();
     let text = loop {
         let message = t!(
             "profanity-check.message",
             locale = state.locale(),
             track_name = track.track_tg_link(),
-            bad_lines = bad_lines.iter().take(lines).join("\n"),
-            lyrics_link = hit.link().trim(),
-            lyrics_link_text = hit.link_text(lines == bad_lines.len()),
+            bad_lines = translated_note.clone() + &bad_lines.iter().take(lines).join("\n"),
+            lyrics_link = analysis.link.trim(),
+            lyrics_link_text = if lines == bad_lines.len() {
+                &analysis.link_text_full
+            } else {
+                &analysis.link_text
+            },
             ignore_button_label = t!("inline-buttons.ignore", locale = state.locale()),
         );
 
@@ -264,17 +675,19 @@ pub async fn check_ai_slop(
     state: &UserState,
     track: &ShortTrack,
 ) -> anyhow::Result<AISlopCheckResult> {
-    if state.user().cfg_ai_slop_detection.is_ignore() {
+    if state.user().ai_slop_detection().is_ignore() {
         return Ok(AISlopCheckResult {
             is_ai_slop: false,
             skipped: false,
         });
     }
 
-    let ai_detection_result = app
-        .ai_slop_detection()
-        .is_track_ai(&mut app.redis_conn().await?, track)
-        .await?;
+    let ai_detection_result = shared_result(app, TrackCheckKind::AISlop, track.id(), || async {
+        app.ai_slop_detection()
+            .is_track_ai(&mut app.redis_conn().await?, track)
+            .await
+    })
+    .await?;
 
     if !ai_detection_result.prediction.is_track_ai() {
         return Ok(AISlopCheckResult {
@@ -283,14 +696,45 @@ pub async fn check_ai_slop(
         });
     }
 
-    if state.user().cfg_ai_slop_detection.is_skip() && state.is_spotify_premium().await? {
+    webhook::emit(
+        app,
+        state.user_id(),
+        WebhookEvent::AISlopDetected,
+        json!({
+            "track": webhook::track_data(track),
+            "prediction": match ai_detection_result.prediction {
+                AISlopDetectionPrediction::HumanMade => "human_made",
+                AISlopDetectionPrediction::PureAI => "pure_ai",
+                AISlopDetectionPrediction::ProcessedAI => "processed_ai",
+            },
+            "provider": ai_detection_result.provider.as_ref().map(AISlopDetectionProvider::name),
+        }),
+    )
+    .await;
+
+    if state.user().ai_slop_detection().is_skip() && state.is_spotify_premium().await? {
         state
             .spotify()
             .await
-            .next_track(None)
+            .request(|spotify| spotify.next_track(None))
+            .await
+            .typed()
             .await
             .context("Skip current track")?;
 
+        webhook::emit(
+            app,
+            state.user_id(),
+            WebhookEvent::TrackAutoSkipped,
+            webhook::track_skipped_data(track, "ai_slop"),
+        )
+        .await;
+
+        UserService::increase_stats_query(state.user_id())
+            .ai_slop_detected(true)
+            .exec(app.db())
+            .await?;
+
         return Ok(AISlopCheckResult {
             is_ai_slop: true,
             skipped: true,
@@ -340,8 +784,428 @@ pub async fn check_ai_slop(
         .message_effect_id("5046589136895476101".into()) // 💩 Poop effect
         .await?;
 
+    UserService::increase_stats_query(state.user_id())
+        .ai_slop_detected(false)
+        .exec(app.db())
+        .await?;
+
     Ok(AISlopCheckResult {
         is_ai_slop: true,
         skipped: false,
     })
 }
+
+/// Saved analysis of the track, or a new one paid from the user quota.
+/// `None` isn't shared with other users, their quota may allow the analysis
+async fn age_rating_report(
+    app: &'static App,
+    state: &UserState,
+    track: &ShortTrack,
+) -> anyhow::Result<Option<AnalysisReport>> {
+    if let Some(report) = TrackAnalysisService::find_any(app.db(), track.id()).await? {
+        return Ok(Some(report));
+    }
+
+    let Some(config) = app.ai() else {
+        return Ok(None);
+    };
+
+    let mut redis_conn = app.redis_conn().await?;
+
+    let Some(hit) = app
+        .lyrics()
+        .search_for_track(&mut redis_conn, track)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let AIQuotaOutput::Allowed(reservation) = AIUsageService::reserve(
+        app.db(),
+        &mut redis_conn,
+        config,
+        state.user_id(),
+        AIFeature::Analysis,
+    )
+    .await?
+    else {
+        tracing::debug!("AI quota is exceeded, track can't be rated");
+
+        return Ok(None);
+    };
+
+    drop(redis_conn);
+
+    let res = actions::analyze::generate(app, state, config, None, track, &hit.lyrics()).await;
+
+    AIUsageService::release(&mut app.redis_conn().await?, reservation).await?;
+
+    res
+}
+
+/// Skips tracks not suitable for kids when kid mode is on. Returns `true` if track is skipped
+#[tracing::instrument(
+    skip_all,
+    fields(
+        track_id = %track.id(),
+        track_name = %track.name_with_artists(),
+    )
+)]
+pub async fn check_age_rating(
+    app: &'static App,
+    state: &UserState,
+    track: &ShortTrack,
+) -> anyhow::Result<bool> {
+    if !state.user().kid_mode() || app.ai().is_none() {
+        return Ok(false);
+    }
+
+    let report = shared_result(app, TrackCheckKind::AgeRating, track.id(), || {
+        age_rating_report(app, state, track)
+    })
+    .await?;
+
+    let Some(report) = report else {
+        return Ok(false);
+    };
+
+    if report.age_rating <= KID_MODE_MAX_AGE_RATING {
+        return Ok(false);
+    }
+
+    let skipped = state.is_spotify_premium().await?;
+
+    if skipped {
+        state
+            .spotify()
+            .await
+            .request(|spotify| spotify.next_track(None))
+            .await
+            .typed()
+            .await
+            .context("Skip current track")?;
+
+        webhook::emit(
+            app,
+            state.user_id(),
+            WebhookEvent::TrackAutoSkipped,
+            webhook::track_skipped_data(track, "age_rating"),
+        )
+        .await;
+    }
+
+    let key = if skipped {
+        "kid-mode.skipped"
+    } else {
+        "kid-mode.not-suitable"
+    };
+
+    app.bot()
+        .send_message(
+            state.chat_id()?,
+            t!(
+                key,
+                locale = state.locale(),
+                track_name = track.track_tg_link(),
+                age_rating = report.age_rating.as_ref(),
+                command = if state.user().profile.is_kid_safe() {
+                    UserCommandDisplay::KidSafe
+                } else {
+                    UserCommandDisplay::ToggleKidMode
+                },
+            ),
+        )
+        .link_preview_options(link_preview_small_top(track.url()))
+        .await?;
+
+    Ok(skipped)
+}
+
+/// Skips the track for kid-safe profile and tells why without showing the content.
+/// Returns `true` if track is skipped
+async fn block_for_kids(
+    app: &'static App,
+    state: &UserState,
+    track: &ShortTrack,
+    reason: KidSafeReason,
+) -> anyhow::Result<bool> {
+    let skipped = state.is_spotify_premium().await?;
+
+    if skipped {
+        state
+            .spotify()
+            .await
+            .request(|spotify| spotify.next_track(None))
+            .await
+            .typed()
+            .await
+            .context("Skip current track")?;
+
+        webhook::emit(
+            app,
+            state.user_id(),
+            WebhookEvent::TrackAutoSkipped,
+            webhook::track_skipped_data(track, reason.as_ref()),
+        )
+        .await;
+    }
+
+    let key = if skipped {
+        "kid-safe.skipped"
+    } else {
+        "kid-safe.blocked"
+    };
+
+    app.bot()
+        .send_message(
+            state.chat_id()?,
+            t!(
+                key,
+                locale = state.locale(),
+                track_name = track.track_tg_link(),
+                reason = t!(
+                    format!("kid-safe.reason.{}", reason.as_ref()),
+                    locale = state.locale()
+                ),
+            ),
+        )
+        .link_preview_options(link_preview_small_top(track.url()))
+        .await?;
+
+    Ok(skipped)
+}
+
+/// Skips tracks Spotify marks as explicit, before any lyrics are fetched
+#[tracing::instrument(
+    skip_all,
+    fields(
+        track_id = %track.id(),
+        track_name = %track.name_with_artists(),
+    )
+)]
+pub async fn check_explicit(
+    app: &'static App,
+    state: &UserState,
+    track: &ShortTrack,
+) -> anyhow::Result<BlockCheckResult> {
+    if !state.user().skip_explicit() || !track.is_explicit() {
+        return Ok(BlockCheckResult::default());
+    }
+
+    if state.user().profile.is_kid_safe() {
+        let skipped = block_for_kids(app, state, track, KidSafeReason::Explicit).await?;
+
+        return Ok(BlockCheckResult {
+            blocked: true,
+            skipped,
+        });
+    }
+
+    // Profanity check still notifies about the track
+    if !state.is_spotify_premium().await? {
+        return Ok(BlockCheckResult::default());
+    }
+
+    state
+        .spotify()
+        .await
+        .request(|spotify| spotify.next_track(None))
+        .await
+        .typed()
+        .await
+        .context("Skip current track")?;
+
+    webhook::emit(
+        app,
+        state.user_id(),
+        WebhookEvent::TrackAutoSkipped,
+        webhook::track_skipped_data(track, "explicit"),
+    )
+    .await;
+
+    Ok(BlockCheckResult {
+        blocked: true,
+        skipped: true,
+    })
+}
+
+/// Spotify explicit flag is the only profanity signal when lyrics can't be checked
+async fn notify_explicit(
+    app: &'static App,
+    state: &UserState,
+    track: &ShortTrack,
+    reason: &str,
+) -> anyhow::Result<()> {
+    if !state.user().check_profanity() || !track.is_explicit() {
+        return Ok(());
+    }
+
+    let keyboard = vec![
+        vec![InlineButtons::Dislike(track.id().into()).into_inline_keyboard_button(state.locale())],
+        vec![InlineButtons::Ignore(track.id().into()).into_inline_keyboard_button(state.locale())],
+    ];
+
+    app.bot()
+        .send_message(
+            state.chat_id()?,
+            t!(
+                "profanity-check.explicit",
+                locale = state.locale(),
+                track_name = track.track_tg_link(),
+                reason = reason,
+                ignore_button_label = t!("inline-buttons.ignore", locale = state.locale()),
+            ),
+        )
+        .link_preview_options(link_preview_small_top(track.url()))
+        .reply_markup(ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(
+            keyboard,
+        )))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spotify::mock::track;
+    use crate::testing::TestApp;
+
+    fn task(user_id: &str) -> TrackCheckQueueTask {
+        TrackCheckQueueTask {
+            track: ShortTrack::new(track("4uLU6hMCjMI75M1A2tKUQC", 200)),
+            user_id: user_id.to_owned(),
+        }
+    }
+
+    async fn user_letters(test: &TestApp, user_id: &str) -> Vec<String> {
+        let mut redis_conn = test.app.redis_conn().await.unwrap();
+
+        let letters: Vec<dead_letter::DeadLetter<TrackCheckQueueTask>> =
+            dead_letter::list(&mut redis_conn, DEAD_LETTER_QUEUE, 10_000)
+                .await
+                .unwrap();
+
+        letters
+            .into_iter()
+            .filter(|letter| letter.task.user_id == user_id)
+            .map(|letter| letter.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_timed_out_attempt_is_dead_lettered() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+
+        // Not the last attempt, it will be retried
+        let res = attempt_check(
+            test.app,
+            task(&user_id),
+            &Attempt::new_with_value(RETRIES),
+            Duration::from_millis(10),
+            std::future::pending(),
+        )
+        .await;
+
+        assert!(res.is_err());
+        assert!(user_letters(&test, &user_id).await.is_empty());
+
+        let res = attempt_check(
+            test.app,
+            task(&user_id),
+            &Attempt::new_with_value(RETRIES + 1),
+            Duration::from_millis(10),
+            std::future::pending(),
+        )
+        .await;
+
+        assert!(res.is_err());
+        assert_eq!(user_letters(&test, &user_id).await.len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_replay() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+
+        let id = dead_letter::push(
+            &mut test.app.redis_conn().await.unwrap(),
+            DEAD_LETTER_QUEUE,
+            task(&user_id),
+            &anyhow::anyhow!("Failed"),
+            RETRIES + 1,
+        )
+        .await
+        .unwrap();
+
+        let (first, second) = tokio::join!(replay(test.app, &id), replay(test.app, &id));
+
+        // Concurrent replays queue the task once
+        assert!(first.unwrap() ^ second.unwrap());
+        assert!(user_letters(&test, &user_id).await.is_empty());
+        assert!(!replay(test.app, &id).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_shared_result_computed_once() {
+        let test = TestApp::start().await.unwrap();
+        let track_id = TestApp::user_id();
+        let computed = std::sync::atomic::AtomicUsize::new(0);
+
+        let compute = || async {
+            computed.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(300)).await;
+
+            Ok(Some(42))
+        };
+
+        let (first, second) = tokio::join!(
+            shared_result(test.app, TrackCheckKind::Lyrics, &track_id, compute),
+            shared_result(test.app, TrackCheckKind::Lyrics, &track_id, compute),
+        );
+
+        assert_eq!(first.unwrap(), Some(42));
+        assert_eq!(second.unwrap(), Some(42));
+        assert_eq!(computed.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let cached: Option<i32> =
+            shared_result(test.app, TrackCheckKind::Lyrics, &track_id, || async {
+                anyhow::bail!("Cached result is expected")
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(cached, Some(42));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_shared_result_error_releases_lock() {
+        let test = TestApp::start().await.unwrap();
+        let track_id = TestApp::user_id();
+
+        let res: anyhow::Result<Option<i32>> =
+            shared_result(test.app, TrackCheckKind::Lyrics, &track_id, || async {
+                anyhow::bail!("Failed")
+            })
+            .await;
+
+        assert!(res.is_err());
+
+        // Next worker doesn't wait for the lock to expire
+        let res = tokio::time::timeout(
+            Duration::from_secs(1),
+            shared_result(test.app, TrackCheckKind::Lyrics, &track_id, || async {
+                Ok(Some(1))
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(res.unwrap(), Some(1));
+    }
+}
@@ -0,0 +1,88 @@
+use apalis::prelude::{Data, TaskSink as _};
+use sea_orm::ActiveEnum as _;
+use serde_json::json;
+
+use crate::app::App;
+use crate::entity::prelude::UserStatus;
+use crate::services::{WebhookEvent, WebhookPayload, WebhookService};
+use crate::spotify::ShortTrack;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WebhookQueueTask {
+    webhook_id: i32,
+    payload: WebhookPayload,
+}
+
+#[tracing::instrument(skip_all, fields(%user_id, %event))]
+pub async fn queue(
+    app: &App,
+    user_id: &str,
+    event: WebhookEvent,
+    data: serde_json::Value,
+) -> anyhow::Result<()> {
+    let webhooks = WebhookService::get_subscribed(app.db(), user_id, event).await?;
+
+    if webhooks.is_empty() {
+        return Ok(());
+    }
+
+    let payload = WebhookPayload::new(event, user_id, data);
+
+    let mut queue = app.queue_manager().webhook_queue();
+
+    for webhook in webhooks {
+        queue
+            .push(WebhookQueueTask {
+                webhook_id: webhook.id,
+                payload: payload.clone(),
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[must_use]
+pub fn track_data(track: &ShortTrack) -> serde_json::Value {
+    json!({
+        "id": track.id(),
+        "name": track.name(),
+        "artists": track.artist_names(),
+        "album": track.album_name(),
+        "url": track.url(),
+        "explicit": track.is_explicit(),
+    })
+}
+
+#[must_use]
+pub fn track_skipped_data(track: &ShortTrack, reason: &str) -> serde_json::Value {
+    json!({
+        "track": track_data(track),
+        "reason": reason,
+    })
+}
+
+#[must_use]
+pub fn user_status_data(status: UserStatus) -> serde_json::Value {
+    json!({ "status": status.to_value() })
+}
+
+/// Same as [`queue`], but errors are only logged. Webhooks must never break the main flow
+pub async fn emit(app: &App, user_id: &str, event: WebhookEvent, data: serde_json::Value) {
+    if let Err(err) = queue(app, user_id, event, data).await {
+        tracing::error!(err = ?err, %user_id, %event, "Failed to queue webhook event");
+    }
+}
+
+#[tracing::instrument(skip_all, fields(webhook_id = data.webhook_id, event = %data.payload.event))]
+pub async fn consume(data: WebhookQueueTask, app: Data<&'static App>) -> anyhow::Result<()> {
+    let app = *app;
+
+    let Some(webhook) = WebhookService::get_by_id(app.db(), data.webhook_id).await? else {
+        tracing::debug!("Webhook was removed. Skip delivery");
+
+        return Ok(());
+    };
+
+    app.webhook().deliver(&webhook, &data.payload).await
+}
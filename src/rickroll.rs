@@ -4,6 +4,7 @@ use rspotify::model::TrackId;
 use rspotify::prelude::OAuthClient as _;
 
 use crate::app::App;
+use crate::spotify::ClientResultExt as _;
 use crate::user::UserState;
 
 #[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
@@ -18,10 +19,14 @@ pub async fn queue(app: &'static App, state: &UserState) -> anyhow::Result<()> {
         return Ok(());
     }
 
+    let track_id = TrackId::from_id("4PTG3Z6ehGkBFwjybzWkR8")?;
+
     state
         .spotify()
         .await
-        .add_item_to_queue(TrackId::from_id("4PTG3Z6ehGkBFwjybzWkR8")?.into(), None)
+        .request(|spotify| spotify.add_item_to_queue(track_id.into(), None))
+        .await
+        .typed()
         .await?;
 
     tracing::info!(user_id = state.user_id(), "The victim of Rickroll");
@@ -15,19 +15,20 @@ pub struct AISlopDetectionService {
     shlabs: Option<shlabs::SHLabsProvider>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum Provider {
     SpotifyAIBlocker,
     SoulOverAI,
     SHLabs,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct AISlopDetectionResult {
     pub provider: Option<Provider>,
     pub prediction: AISlopDetectionPrediction,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub enum AISlopDetectionPrediction {
     #[default]
     HumanMade,
@@ -52,10 +53,12 @@ pub trait AISlopDetector {
 }
 
 impl Provider {
+    #[must_use]
     pub fn tg_link(&self) -> String {
         teloxide::utils::html::link(self.link(), self.name())
     }
 
+    #[must_use]
     pub fn link(&self) -> &str {
         match self {
             Self::SpotifyAIBlocker => "https://github.com/CennoxX/spotify-ai-blocker",
@@ -64,6 +67,7 @@ impl Provider {
         }
     }
 
+    #[must_use]
     pub fn name(&self) -> &str {
         match self {
             Self::SpotifyAIBlocker => "Spotify AI Music Blocker",
@@ -0,0 +1,351 @@
+use std::sync::LazyLock;
+
+use async_openai::types::chat::CompletionUsage;
+use chrono::{Datelike as _, Duration, Months, NaiveDate, NaiveDateTime};
+use deadpool_redis::redis::{AsyncCommands as _, Script};
+use rand::RngExt as _;
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::Expr;
+use sea_orm::sea_query::Alias;
+use sea_orm::{
+    ColumnTrait as _,
+    ConnectionTrait,
+    EntityTrait as _,
+    QueryFilter as _,
+    QuerySelect as _,
+};
+
+use crate::ai::{AIConfig, AIFeature, Target};
+use crate::entity::prelude::{AIUsageActiveModel, AIUsageColumn, AIUsageEntity};
+use crate::utils::Clock;
+
+/// Drops expired reservations and reserves tokens if the quota isn't used up
+/// by spent and already reserved tokens. Returns 1 if tokens are left.
+/// Nothing is reserved when 0 tokens are asked
+static RESERVE_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            local now = tonumber(ARGV[1])
+
+            for _, id in ipairs(redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', now)) do
+                redis.call('HDEL', KEYS[2], id)
+            end
+
+            redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now)
+
+            local used = tonumber(ARGV[2])
+
+            for _, tokens in ipairs(redis.call('HVALS', KEYS[2])) do
+                used = used + tonumber(tokens)
+            end
+
+            if used >= tonumber(ARGV[3]) then
+                return 0
+            end
+
+            if tonumber(ARGV[5]) == 0 then
+                return 1
+            end
+
+            redis.call('ZADD', KEYS[1], now + tonumber(ARGV[6]), ARGV[4])
+            redis.call('HSET', KEYS[2], ARGV[4], ARGV[5])
+            redis.call('PEXPIRE', KEYS[1], ARGV[6])
+            redis.call('PEXPIRE', KEYS[2], ARGV[6])
+
+            return 1
+        ",
+    )
+});
+
+pub enum AIQuotaOutput {
+    Allowed(AIReservation),
+    Exceeded { renews_on: NaiveDate },
+}
+
+/// Tokens held for a request in flight, so concurrent requests can't spend more than the quota.
+/// Released with `AIUsageService::release` once real usage is recorded, expires if it isn't
+#[must_use]
+pub struct AIReservation {
+    user_id: String,
+    id: String,
+}
+
+/// Rough size of a request, reserved until its real usage is recorded
+fn reserved_tokens(feature: AIFeature) -> u64 {
+    match feature {
+        AIFeature::Analysis | AIFeature::Translation => 3000,
+        AIFeature::WordDefinition => 500,
+        AIFeature::Recommendasion => 4000,
+        AIFeature::Chat => 2000,
+    }
+}
+
+fn month_start(now: NaiveDateTime) -> NaiveDate {
+    now.date().with_day(1).unwrap_or_else(|| now.date())
+}
+
+fn next_month_start(now: NaiveDateTime) -> NaiveDate {
+    let start = month_start(now);
+
+    start.checked_add_months(Months::new(1)).unwrap_or(start)
+}
+
+/// Tokens spent by users on AI features
+pub struct AIUsageService;
+
+impl AIUsageService {
+    const RESERVATION_TTL: Duration = Duration::minutes(10);
+
+    fn reservations_key(user_id: &str) -> String {
+        format!("rustify:ai_reservations:{user_id}")
+    }
+
+    fn reserved_tokens_key(user_id: &str) -> String {
+        format!("rustify:ai_reservations:{user_id}:tokens")
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id, feature = feature.as_ref(), backend = target.backend(), model = target.model()))]
+    pub async fn record(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        feature: AIFeature,
+        target: &Target,
+        usage: Option<&CompletionUsage>,
+    ) -> anyhow::Result<()> {
+        // Some OpenAI-compatible servers don't report usage
+        let Some(usage) = usage else {
+            tracing::warn!("AI backend didn't report token usage");
+
+            return Ok(());
+        };
+
+        let model = AIUsageActiveModel {
+            user_id: Set(user_id.into()),
+            feature: Set(feature.as_ref().into()),
+            backend: Set(target.backend().into()),
+            model: Set(target.model().into()),
+            prompt_tokens: Set(usage.prompt_tokens.into()),
+            completion_tokens: Set(usage.completion_tokens.into()),
+            total_tokens: Set(usage.total_tokens.into()),
+            ..Default::default()
+        };
+
+        AIUsageEntity::insert(model).exec(db).await?;
+
+        Ok(())
+    }
+
+    /// Tokens spent since the beginning of the calendar month
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn used_this_month(db: &impl ConnectionTrait, user_id: &str) -> anyhow::Result<i64> {
+        let since = month_start(Clock::now()).and_time(chrono::NaiveTime::MIN);
+
+        let res: Option<Option<i64>> = AIUsageEntity::find()
+            .filter(AIUsageColumn::UserId.eq(user_id))
+            .filter(AIUsageColumn::CreatedAt.gte(since))
+            .select_only()
+            // SUM of bigint is numeric in Postgres
+            .expr_as(
+                Expr::expr(AIUsageColumn::TotalTokens.sum()).cast_as(Alias::new("bigint")),
+                "sum",
+            )
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(res.flatten().unwrap_or_default())
+    }
+
+    /// Returns `false` if the quota is used up, otherwise reserves `tokens` with given id
+    async fn try_reserve(
+        db: &impl ConnectionTrait,
+        redis_conn: &mut deadpool_redis::Connection,
+        config: &AIConfig,
+        user_id: &str,
+        id: &str,
+        tokens: u64,
+    ) -> anyhow::Result<bool> {
+        let used = Self::used_this_month(db, user_id).await?;
+
+        let reserved: u8 = RESERVE_SCRIPT
+            .key(Self::reservations_key(user_id))
+            .key(Self::reserved_tokens_key(user_id))
+            .arg(Clock::now().and_utc().timestamp_millis())
+            .arg(used)
+            .arg(config.monthly_token_quota())
+            .arg(id)
+            .arg(tokens)
+            .arg(Self::RESERVATION_TTL.num_milliseconds())
+            .invoke_async(redis_conn)
+            .await?;
+
+        Ok(reserved == 1)
+    }
+
+    /// Reserves tokens for a request of the feature, unless the monthly quota is used up
+    #[tracing::instrument(skip_all, fields(%user_id, feature = feature.as_ref()))]
+    pub async fn reserve(
+        db: &impl ConnectionTrait,
+        redis_conn: &mut deadpool_redis::Connection,
+        config: &AIConfig,
+        user_id: &str,
+        feature: AIFeature,
+    ) -> anyhow::Result<AIQuotaOutput> {
+        let id = format!("{:032x}", rand::rng().random::<u128>());
+
+        if !Self::try_reserve(
+            db,
+            redis_conn,
+            config,
+            user_id,
+            &id,
+            reserved_tokens(feature),
+        )
+        .await?
+        {
+            return Ok(AIQuotaOutput::Exceeded {
+                renews_on: next_month_start(Clock::now()),
+            });
+        }
+
+        Ok(AIQuotaOutput::Allowed(AIReservation {
+            user_id: user_id.to_owned(),
+            id,
+        }))
+    }
+
+    /// Returns the date quota renews on if it's used up. Nothing is reserved,
+    /// it's for skipping preparations of requests which won't be allowed
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn exceeded_until(
+        db: &impl ConnectionTrait,
+        redis_conn: &mut deadpool_redis::Connection,
+        config: &AIConfig,
+        user_id: &str,
+    ) -> anyhow::Result<Option<NaiveDate>> {
+        if Self::try_reserve(db, redis_conn, config, user_id, "", 0).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(next_month_start(Clock::now())))
+    }
+
+    /// Returns reserved tokens, spent ones are already recorded
+    #[tracing::instrument(skip_all, fields(user_id = %reservation.user_id))]
+    pub async fn release(
+        redis_conn: &mut deadpool_redis::Connection,
+        reservation: AIReservation,
+    ) -> anyhow::Result<()> {
+        let _: () = redis_conn
+            .hdel(
+                Self::reserved_tokens_key(&reservation.user_id),
+                &reservation.id,
+            )
+            .await?;
+        let _: () = redis_conn
+            .zrem(
+                Self::reservations_key(&reservation.user_id),
+                &reservation.id,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::ai::Backend;
+    use crate::testing::TestApp;
+
+    fn datetime(date: &str) -> NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(13, 45, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        assert_eq!(
+            month_start(datetime("2026-10-18")),
+            datetime("2026-10-01").date()
+        );
+        assert_eq!(
+            next_month_start(datetime("2026-10-18")),
+            datetime("2026-11-01").date()
+        );
+        assert_eq!(
+            next_month_start(datetime("2026-12-31")),
+            datetime("2027-01-01").date()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_reserve() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        let mut redis_conn = test.app.redis_conn().await.unwrap();
+
+        let config = AIConfig::new(
+            vec![Backend::new("test", async_openai::Client::new())],
+            &HashMap::new(),
+            "test:model",
+            reserved_tokens(AIFeature::Analysis) + 1,
+        )
+        .unwrap();
+
+        let reserve = || async {
+            AIUsageService::reserve(
+                test.app.db(),
+                &mut test.app.redis_conn().await.unwrap(),
+                &config,
+                &user_id,
+                AIFeature::Analysis,
+            )
+            .await
+            .unwrap()
+        };
+
+        // Concurrent requests see each other's reservations
+        let mut allowed: Vec<_> = futures::future::join_all((0..3).map(|_| reserve()))
+            .await
+            .into_iter()
+            .filter_map(|output| match output {
+                AIQuotaOutput::Allowed(reservation) => Some(reservation),
+                AIQuotaOutput::Exceeded { .. } => None,
+            })
+            .collect();
+
+        assert_eq!(allowed.len(), 2);
+
+        AIUsageService::release(&mut redis_conn, allowed.pop().unwrap())
+            .await
+            .unwrap();
+
+        assert!(matches!(reserve().await, AIQuotaOutput::Allowed(_)));
+        assert!(matches!(reserve().await, AIQuotaOutput::Exceeded { .. }));
+
+        let exceeded_until =
+            AIUsageService::exceeded_until(test.app.db(), &mut redis_conn, &config, &user_id)
+                .await
+                .unwrap();
+
+        assert_eq!(exceeded_until, Some(next_month_start(Clock::now())));
+        assert_eq!(
+            AIUsageService::exceeded_until(
+                test.app.db(),
+                &mut redis_conn,
+                &config,
+                &TestApp::user_id()
+            )
+            .await
+            .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use rand::RngExt as _;
+use rand::distr::Alphanumeric;
+use sea_orm::prelude::*;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ConnectionTrait, Set};
+use sha2::{Digest as _, Sha256};
+
+use crate::entity::prelude::*;
+use crate::utils::Clock;
+
+pub struct ApiTokenService;
+
+impl ApiTokenService {
+    pub const PREFIX: &str = "rfy_";
+
+    #[must_use]
+    pub fn hash(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    #[must_use]
+    pub fn generate_token() -> String {
+        let random: String = rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(40)
+            .map(char::from)
+            .collect();
+
+        format!("{}{random}", Self::PREFIX)
+    }
+
+    /// Issues new token for user, previous one stops working. Returns plain token
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn issue(db: &impl ConnectionTrait, user_id: &str) -> anyhow::Result<String> {
+        let token = Self::generate_token();
+
+        let model = ApiTokenActiveModel {
+            user_id: Set(user_id.to_owned()),
+            token_hash: Set(Self::hash(&token)),
+            ..Default::default()
+        };
+
+        ApiTokenEntity::insert(model)
+            .on_conflict(
+                OnConflict::column(ApiTokenColumn::UserId)
+                    .update_column(ApiTokenColumn::TokenHash)
+                    .value(ApiTokenColumn::LastUsedAt, Option::<DateTime>::None)
+                    .value(ApiTokenColumn::CreatedAt, Clock::now())
+                    .value(ApiTokenColumn::UpdatedAt, Clock::now())
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(token)
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn revoke(db: &impl ConnectionTrait, user_id: &str) -> anyhow::Result<bool> {
+        let res = ApiTokenEntity::delete_many()
+            .filter(ApiTokenColumn::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Finds owner of the token and marks token as used
+    #[tracing::instrument(skip_all)]
+    pub async fn authenticate(
+        db: &impl ConnectionTrait,
+        token: &str,
+    ) -> anyhow::Result<Option<UserModel>> {
+        if !token.starts_with(Self::PREFIX) {
+            return Ok(None);
+        }
+
+        let Some((api_token, Some(user))) = ApiTokenEntity::find()
+            .filter(ApiTokenColumn::TokenHash.eq(Self::hash(token)))
+            .find_also_related(UserEntity)
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        ApiTokenEntity::update_many()
+            .filter(ApiTokenColumn::Id.eq(api_token.id))
+            .col_expr(ApiTokenColumn::LastUsedAt, Expr::value(Clock::now()))
+            .exec(db)
+            .await?;
+
+        Ok(Some(user))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_token() {
+        let token = ApiTokenService::generate_token();
+
+        assert!(token.starts_with(ApiTokenService::PREFIX));
+        assert_eq!(token.len(), ApiTokenService::PREFIX.len() + 40);
+        assert_ne!(token, ApiTokenService::generate_token());
+    }
+
+    #[test]
+    fn test_hash() {
+        // echo -n 'rfy_test' | sha256sum
+        assert_eq!(
+            ApiTokenService::hash("rfy_test"),
+            "d1c299c72cb3ed065620cf46cdb9c2bca913267d123e7aaa575eca90977ac464"
+        );
+    }
+}
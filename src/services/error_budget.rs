@@ -0,0 +1,242 @@
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Duration, Utc};
+use deadpool_redis::redis::{AsyncCommands as _, Script};
+use rand::RngExt as _;
+
+use crate::infrastructure::error::ErrorClass;
+use crate::utils::Clock;
+
+/// Records failure and decides whether user should be told that checks don't work.
+/// Returns 1 once when budget is exhausted, unless user was notified recently
+static FAILURE_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            local now = tonumber(ARGV[1])
+            local window = tonumber(ARGV[2])
+
+            redis.call('ZADD', KEYS[1], now, ARGV[1] .. ':' .. ARGV[3] .. ':' .. ARGV[7])
+            redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+            redis.call('PEXPIRE', KEYS[1], window)
+
+            if redis.call('ZCARD', KEYS[1]) < tonumber(ARGV[4]) then
+                return 0
+            end
+
+            if redis.call('EXISTS', KEYS[3]) == 1 then
+                return 0
+            end
+
+            if not redis.call('SET', KEYS[2], ARGV[3], 'NX', 'PX', ARGV[5]) then
+                return 0
+            end
+
+            redis.call('SET', KEYS[3], 1, 'PX', ARGV[6])
+
+            return 1
+        ",
+    )
+});
+
+/// Per-user budget of failed checks.
+///
+/// When checks keep failing, user gets one message about it and another one when checks work again
+pub struct ErrorBudgetService;
+
+impl ErrorBudgetService {
+    /// Minimal time between two notifications, so flapping checks don't spam
+    const COOLDOWN: Duration = Duration::hours(6);
+    /// Users who never recover, e.g. stopped listening, don't keep the mark forever
+    const NOTIFIED_TTL: Duration = Duration::days(7);
+    /// Failures within the window to notify user
+    const THRESHOLD: u32 = 5;
+    /// Failures older than this are forgotten
+    const WINDOW: Duration = Duration::minutes(15);
+
+    fn failures_key(user_id: &str) -> String {
+        format!("rustify:error_budget:{user_id}:failures")
+    }
+
+    fn notified_key(user_id: &str) -> String {
+        format!("rustify:error_budget:{user_id}:notified")
+    }
+
+    fn cooldown_key(user_id: &str) -> String {
+        format!("rustify:error_budget:{user_id}:cooldown")
+    }
+
+    /// Returns `true` when user should be notified about not working checks
+    #[tracing::instrument(skip_all, fields(%user_id, ?class))]
+    pub async fn failure(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+        class: ErrorClass,
+    ) -> anyhow::Result<bool> {
+        Self::failure_at(redis_conn, user_id, class, Clock::now().and_utc()).await
+    }
+
+    async fn failure_at(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+        class: ErrorClass,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        // Failures within the same millisecond are different members
+        let nonce: u64 = rand::rng().random();
+
+        let notify: u8 = FAILURE_SCRIPT
+            .key(Self::failures_key(user_id))
+            .key(Self::notified_key(user_id))
+            .key(Self::cooldown_key(user_id))
+            .arg(now.timestamp_millis())
+            .arg(Self::WINDOW.num_milliseconds())
+            .arg(class.as_ref())
+            .arg(Self::THRESHOLD)
+            .arg(Self::NOTIFIED_TTL.num_milliseconds())
+            .arg(Self::COOLDOWN.num_milliseconds())
+            .arg(nonce)
+            .invoke_async(redis_conn)
+            .await?;
+
+        Ok(notify == 1)
+    }
+
+    /// Returns `true` when user was notified about failures and should know checks work again
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn success(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+    ) -> anyhow::Result<bool> {
+        // Called after every successful check, so it's read-only unless user was notified
+        let notified: bool = redis_conn.exists(Self::notified_key(user_id)).await?;
+
+        if !notified {
+            return Ok(false);
+        }
+
+        let removed: u32 = redis_conn.del(Self::notified_key(user_id)).await?;
+
+        if removed == 0 {
+            return Ok(false);
+        }
+
+        let _: () = redis_conn.del(Self::failures_key(user_id)).await?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn test_threshold() {
+        let mut redis_conn = TestApp::redis_conn().await.unwrap();
+        let user_id = TestApp::user_id();
+        let now = Utc::now();
+
+        for _ in 1..ErrorBudgetService::THRESHOLD {
+            assert!(
+                !ErrorBudgetService::failure_at(
+                    &mut redis_conn,
+                    &user_id,
+                    ErrorClass::Retryable,
+                    now
+                )
+                .await
+                .unwrap()
+            );
+        }
+
+        // Failures of the same millisecond are all counted
+        assert!(
+            ErrorBudgetService::failure_at(&mut redis_conn, &user_id, ErrorClass::Retryable, now)
+                .await
+                .unwrap()
+        );
+
+        // Notified once per exhaustion
+        assert!(
+            !ErrorBudgetService::failure_at(&mut redis_conn, &user_id, ErrorClass::Retryable, now)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn test_window() {
+        let mut redis_conn = TestApp::redis_conn().await.unwrap();
+        let user_id = TestApp::user_id();
+        let now = Utc::now();
+
+        for i in 1..ErrorBudgetService::THRESHOLD {
+            ErrorBudgetService::failure_at(
+                &mut redis_conn,
+                &user_id,
+                ErrorClass::Permanent,
+                now + Duration::seconds(i.into()),
+            )
+            .await
+            .unwrap();
+        }
+
+        // Previous failures are out of the window
+        let later = now + ErrorBudgetService::WINDOW + Duration::minutes(1);
+
+        assert!(
+            !ErrorBudgetService::failure_at(
+                &mut redis_conn,
+                &user_id,
+                ErrorClass::Permanent,
+                later
+            )
+            .await
+            .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn test_cooldown() {
+        let mut redis_conn = TestApp::redis_conn().await.unwrap();
+        let user_id = TestApp::user_id();
+        let now = Utc::now();
+
+        let exhaust = async |redis_conn: &mut deadpool_redis::Connection| {
+            let mut notified = false;
+
+            for _ in 0..ErrorBudgetService::THRESHOLD {
+                notified |= ErrorBudgetService::failure_at(
+                    redis_conn,
+                    &user_id,
+                    ErrorClass::Retryable,
+                    now,
+                )
+                .await
+                .unwrap();
+            }
+
+            notified
+        };
+
+        assert!(exhaust(&mut redis_conn).await);
+        assert!(
+            ErrorBudgetService::success(&mut redis_conn, &user_id)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !ErrorBudgetService::success(&mut redis_conn, &user_id)
+                .await
+                .unwrap(),
+            "Recovery is reported once"
+        );
+
+        // Checks are flapping, user was notified recently
+        assert!(!exhaust(&mut redis_conn).await);
+    }
+}
@@ -1,4 +1,7 @@
 use deadpool_redis::redis::AsyncCommands as _;
+use strum::IntoEnumIterator as _;
+
+use crate::infrastructure::error::ErrorClass;
 
 pub struct MetricsService {}
 
@@ -24,4 +27,34 @@ impl MetricsService {
     fn spotify_429_key() -> &'static str {
         "rustify:metrics:spotify-429"
     }
+
+    pub async fn errors_inc(
+        redis_conn: &mut deadpool_redis::Connection,
+        class: ErrorClass,
+    ) -> anyhow::Result<()> {
+        let _: () = redis_conn
+            .hincr(Self::errors_key(), class.as_ref(), 1)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Handled errors count by class
+    pub async fn errors_get(
+        redis_conn: &mut deadpool_redis::Connection,
+    ) -> anyhow::Result<Vec<(ErrorClass, u64)>> {
+        let mut res = vec![];
+
+        for class in ErrorClass::iter() {
+            let count: Option<u64> = redis_conn.hget(Self::errors_key(), class.as_ref()).await?;
+
+            res.push((class, count.unwrap_or_default()));
+        }
+
+        Ok(res)
+    }
+
+    fn errors_key() -> &'static str {
+        "rustify:metrics:errors"
+    }
 }
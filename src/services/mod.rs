@@ -1,31 +1,74 @@
 mod ai_slop_detection;
+mod ai_usage;
+mod api_token;
+mod error_budget;
 mod magic;
 mod metrics;
 mod notification;
 mod rate_limit;
 mod recommendasion;
+mod recommendasion_feedback;
 mod skippage;
 mod song_link;
 mod spotify_polling_backoff;
+mod spotify_rate_limit;
+mod telegram_web_app;
+mod tick_scheduler;
+mod tick_shard;
+mod track_analysis;
+mod track_check_cache;
 mod track_language_stats;
 mod track_status;
+mod track_translation;
 mod user;
+mod user_profane_word_stats;
 mod user_word_whitelist;
+mod web_session;
+mod webhook;
 mod word_definition;
 mod word_stats;
 
-pub use ai_slop_detection::{AISlopDetectionPrediction, AISlopDetectionService};
+pub use ai_slop_detection::{
+    AISlopDetectionPrediction,
+    AISlopDetectionService,
+    Provider as AISlopDetectionProvider,
+};
+pub use ai_usage::{AIQuotaOutput, AIReservation, AIUsageService};
+pub use api_token::ApiTokenService;
+pub use error_budget::ErrorBudgetService;
 pub use magic::MagicService;
 pub use metrics::MetricsService;
 pub use notification::NotificationService;
 pub use rate_limit::{RateLimitAction, RateLimitOutput, RateLimitService};
-pub use recommendasion::RecommendasionService;
+pub use recommendasion::{
+    RecommendasionMode,
+    RecommendasionOptions,
+    RecommendasionPlaying,
+    RecommendasionService,
+};
+pub use recommendasion_feedback::RecommendasionFeedbackService;
 pub use skippage::SkippageService;
 pub use song_link::{SongLinkPlatform, SongLinkPlatformLink, SongLinkResponse, SongLinkService};
 pub use spotify_polling_backoff::SpotifyPollingBackoffService;
+pub use spotify_rate_limit::{SpotifyRateBudget, SpotifyRateLimitService};
+pub use telegram_web_app::{TelegramWebAppService, TelegramWebAppUser};
+pub use tick_scheduler::TickSchedulerService;
+pub use tick_shard::TickShardService;
+pub use track_analysis::{
+    AgeRating,
+    AnalysisReport,
+    ContentLevel,
+    ContentRating,
+    TrackAnalysisService,
+};
+pub use track_check_cache::{TrackCheckCacheService, TrackCheckKind, TrackCheckResult};
 pub use track_language_stats::TrackLanguageStatsService;
 pub use track_status::TrackStatusService;
+pub use track_translation::{TrackTranslation, TrackTranslationService};
 pub use user::{UserService, UserStats};
+pub use user_profane_word_stats::UserProfaneWordStatsService;
 pub use user_word_whitelist::UserWordWhitelistService;
+pub use web_session::WebSessionService;
+pub use webhook::{PublicResolver, WebhookEvent, WebhookPayload, WebhookService};
 pub use word_definition::WordDefinitionService;
 pub use word_stats::WordStatsService;
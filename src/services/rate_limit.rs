@@ -7,23 +7,23 @@ pub enum RateLimitOutput {
 }
 
 pub enum RateLimitAction {
-    Analyze,
+    Api,
     Details,
     Dislike,
     Like,
-    Recommendasion,
     Magic,
+    SettingsPin,
 }
 
 impl RateLimitAction {
     fn config(&self) -> (&str, u32, Duration) {
         match self {
-            Self::Analyze => ("analyze", 1, Duration::minutes(5)),
+            Self::Api => ("api", 60, Duration::minutes(1)),
             Self::Details => ("details", 1, Duration::seconds(15)),
             Self::Dislike => ("dislike", 2, Duration::seconds(20)),
             Self::Like => ("like", 1, Duration::seconds(10)),
-            Self::Recommendasion => ("recommendasion", 1, Duration::hours(1)),
             Self::Magic => ("magic", 1, Duration::hours(6)),
+            Self::SettingsPin => ("settings_pin", 5, Duration::hours(1)),
         }
     }
 }
@@ -1,12 +1,83 @@
 use chrono::Duration;
 use deadpool_redis::redis::AsyncCommands as _;
 use itertools::Itertools as _;
+use strum_macros::EnumIter;
 
 use crate::spotify::ShortTrack;
 
+/// What kind of tracks AI is asked for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
+pub enum RecommendasionMode {
+    /// Similar to liked tracks
+    #[default]
+    Favorites,
+    /// Lesser-known tracks instead of hits
+    DeepCuts,
+    SimilarToCurrent,
+    /// Released within the last year
+    NewReleases,
+}
+
+/// User steering of recommendations, kept between generations
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecommendasionOptions {
+    pub mode: RecommendasionMode,
+    /// Free-text mood or genre hint
+    pub hint: Option<String>,
+    /// Save batch to a new playlist instead of the queue
+    pub save_to_playlist: bool,
+}
+
+/// Recommended track user is listening to now
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecommendasionPlaying {
+    pub track: ShortTrack,
+    /// Last seen progress of the track
+    pub progress_secs: i64,
+}
+
 pub struct RecommendasionService {}
 
 impl RecommendasionService {
+    pub const HINT_MAX_LEN: usize = 200;
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn get_options(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+    ) -> anyhow::Result<RecommendasionOptions> {
+        let options_key = format!("rustify:recommendasion:{user_id}:options");
+
+        let options: Option<String> = redis_conn.get(&options_key).await?;
+
+        let options = options
+            .and_then(|options| serde_json::from_str(&options).ok())
+            .unwrap_or_default();
+
+        Ok(options)
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn save_options(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+        options: &RecommendasionOptions,
+    ) -> anyhow::Result<()> {
+        let options_key = format!("rustify:recommendasion:{user_id}:options");
+
+        let ttl = Duration::days(30);
+
+        let _: () = redis_conn
+            .set_ex(
+                options_key,
+                serde_json::to_string(options)?,
+                ttl.num_seconds() as _,
+            )
+            .await?;
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip_all, fields(%user_id))]
     pub async fn get_already_recommended(
         redis_conn: &mut deadpool_redis::Connection,
@@ -43,6 +114,76 @@ impl RecommendasionService {
             )
             .await?;
 
+        // Set of IDs to look up played tracks quickly
+        let ids_key = format!("rustify:recommendasion:{user_id}:recommended_ids");
+        let ids = recommended.iter().map(|track| track.id()).collect_vec();
+
+        let _: () = redis_conn.del(&ids_key).await?;
+
+        if !ids.is_empty() {
+            let _: () = redis_conn.sadd(&ids_key, ids).await?;
+            let _: () = redis_conn.expire(&ids_key, ttl.num_seconds()).await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id, %track_id))]
+    pub async fn is_recommended(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+        track_id: &str,
+    ) -> anyhow::Result<bool> {
+        let ids_key = format!("rustify:recommendasion:{user_id}:recommended_ids");
+
+        let res: bool = redis_conn.sismember(ids_key, track_id).await?;
+
+        Ok(res)
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn get_playing(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+    ) -> anyhow::Result<Option<RecommendasionPlaying>> {
+        let playing_key = format!("rustify:recommendasion:{user_id}:playing");
+
+        let playing: Option<String> = redis_conn.get(&playing_key).await?;
+
+        Ok(playing.and_then(|playing| serde_json::from_str(&playing).ok()))
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn save_playing(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+        playing: &RecommendasionPlaying,
+    ) -> anyhow::Result<()> {
+        let playing_key = format!("rustify:recommendasion:{user_id}:playing");
+
+        // Forget the track if user stopped listening at all
+        let ttl = Duration::hours(6);
+
+        let _: () = redis_conn
+            .set_ex(
+                playing_key,
+                serde_json::to_string(playing)?,
+                ttl.num_seconds() as _,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn remove_playing(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+    ) -> anyhow::Result<()> {
+        let playing_key = format!("rustify:recommendasion:{user_id}:playing");
+
+        let _: () = redis_conn.del(playing_key).await?;
+
         Ok(())
     }
 }
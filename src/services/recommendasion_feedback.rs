@@ -0,0 +1,177 @@
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::Expr;
+use sea_orm::sea_query::{Alias, OnConflict};
+use sea_orm::{
+    ColumnTrait as _,
+    ConnectionTrait,
+    EntityTrait as _,
+    Iterable as _,
+    PaginatorTrait as _,
+    QueryFilter as _,
+    QueryOrder as _,
+    QuerySelect as _,
+};
+
+use crate::entity::prelude::*;
+use crate::services::RecommendasionService;
+use crate::spotify::ShortTrack;
+use crate::utils::Clock;
+
+/// How recommended tracks worked out for users
+pub struct RecommendasionFeedbackService;
+
+impl RecommendasionFeedbackService {
+    /// Liked or disliked outcome is not overwritten by listening behaviour
+    #[tracing::instrument(skip_all, fields(%user_id, track_id = %track.id(), ?outcome))]
+    pub async fn record(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        track: &ShortTrack,
+        outcome: RecommendasionOutcome,
+    ) -> anyhow::Result<()> {
+        let model = RecommendasionFeedbackActiveModel {
+            user_id: Set(user_id.into()),
+            track_id: Set(track.id().into()),
+            track_name: Set(track.name_with_artists()),
+            outcome: Set(outcome),
+            ..Default::default()
+        };
+
+        let explicit = || RecommendasionOutcome::iter().filter(RecommendasionOutcome::is_explicit);
+        let existing = || {
+            Expr::col((
+                RecommendasionFeedbackEntity,
+                RecommendasionFeedbackColumn::Outcome,
+            ))
+        };
+        let excluded = || {
+            Expr::col((
+                Alias::new("excluded"),
+                RecommendasionFeedbackColumn::Outcome,
+            ))
+        };
+
+        // Row is left as is when nothing changes, so it's not an error
+        RecommendasionFeedbackEntity::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    RecommendasionFeedbackColumn::UserId,
+                    RecommendasionFeedbackColumn::TrackId,
+                ])
+                .update_column(RecommendasionFeedbackColumn::Outcome)
+                .value(RecommendasionFeedbackColumn::UpdatedAt, Clock::now())
+                .action_and_where(
+                    excluded()
+                        .is_in(explicit())
+                        .or(existing().is_not_in(explicit()))
+                        .and(existing().ne(excluded())),
+                )
+                .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records like or dislike if the track was recommended to the user
+    #[tracing::instrument(skip_all, fields(%user_id, track_id = %track.id(), ?outcome))]
+    pub async fn record_reaction(
+        db: &impl ConnectionTrait,
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+        track: &ShortTrack,
+        outcome: RecommendasionOutcome,
+    ) -> anyhow::Result<()> {
+        if !RecommendasionService::is_recommended(redis_conn, user_id, track.id()).await? {
+            return Ok(());
+        }
+
+        Self::record(db, user_id, track, outcome).await
+    }
+
+    /// Latest outcomes first
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn recent(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        limit: u64,
+    ) -> anyhow::Result<Vec<RecommendasionFeedbackModel>> {
+        let feedback = RecommendasionFeedbackEntity::find()
+            .filter(RecommendasionFeedbackColumn::UserId.eq(user_id))
+            .order_by_desc(RecommendasionFeedbackColumn::UpdatedAt)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(feedback)
+    }
+
+    /// Returns `(hits, total)`, hit is a track listened fully or liked
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn hit_rate(db: &impl ConnectionTrait, user_id: &str) -> anyhow::Result<(u64, u64)> {
+        let total = RecommendasionFeedbackEntity::find()
+            .filter(RecommendasionFeedbackColumn::UserId.eq(user_id))
+            .count(db)
+            .await?;
+
+        let hits = RecommendasionFeedbackEntity::find()
+            .filter(RecommendasionFeedbackColumn::UserId.eq(user_id))
+            .filter(RecommendasionFeedbackColumn::Outcome.is_in([
+                RecommendasionOutcome::Listened,
+                RecommendasionOutcome::Liked,
+            ]))
+            .count(db)
+            .await?;
+
+        Ok((hits, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::UserService;
+    use crate::spotify::mock::track;
+    use crate::testing::TestApp;
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_record_precedence() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        UserService::upsert_by_id(test.app.db(), &user_id)
+            .await
+            .unwrap();
+
+        let track = ShortTrack::new(track("4uLU6hMCjMI75M1A2tKUQC", 200));
+
+        for (outcome, expected) in [
+            (
+                RecommendasionOutcome::Skipped,
+                RecommendasionOutcome::Skipped,
+            ),
+            (RecommendasionOutcome::Liked, RecommendasionOutcome::Liked),
+            (
+                RecommendasionOutcome::Listened,
+                RecommendasionOutcome::Liked,
+            ),
+            (RecommendasionOutcome::Liked, RecommendasionOutcome::Liked),
+            (
+                RecommendasionOutcome::Disliked,
+                RecommendasionOutcome::Disliked,
+            ),
+        ] {
+            RecommendasionFeedbackService::record(test.app.db(), &user_id, &track, outcome)
+                .await
+                .unwrap();
+
+            let feedback = RecommendasionFeedbackService::recent(test.app.db(), &user_id, 10)
+                .await
+                .unwrap();
+
+            assert_eq!(feedback.len(), 1);
+            assert_eq!(feedback[0].outcome, expected);
+        }
+    }
+}
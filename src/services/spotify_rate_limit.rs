@@ -0,0 +1,165 @@
+use std::sync::LazyLock;
+
+use chrono::Duration;
+use deadpool_redis::redis::{AsyncCommands as _, Script};
+
+use crate::services::RateLimitOutput;
+use crate::utils::Clock;
+
+/// Refills bucket, takes `cost` tokens if possible.
+/// Returns milliseconds to wait for tokens and amount of tokens left
+static TOKEN_BUCKET_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            local capacity = tonumber(ARGV[1])
+            local rate = tonumber(ARGV[2])
+            local now = tonumber(ARGV[3])
+            local cost = tonumber(ARGV[4])
+
+            local data = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+            local tokens = tonumber(data[1]) or capacity
+            local ts = tonumber(data[2]) or now
+
+            tokens = math.min(capacity, tokens + math.max(0, now - ts) * rate / 1000)
+
+            local wait = 0
+            if tokens >= cost then
+                tokens = tokens - cost
+            else
+                wait = math.ceil((cost - tokens) * 1000 / rate)
+            end
+
+            redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+            redis.call('PEXPIRE', KEYS[1], math.ceil(capacity * 1000 / rate) + 1000)
+
+            return {wait, tostring(tokens)}
+        ",
+    )
+});
+
+#[derive(Clone, Debug)]
+pub struct SpotifyRateBudget {
+    pub tokens: f64,
+    pub capacity: f64,
+    pub retry_after: Option<Duration>,
+}
+
+impl SpotifyRateBudget {
+    /// Share of budget, below which background polling slows down
+    const LOW_RATIO: f64 = 0.2;
+
+    #[must_use]
+    pub fn is_low(&self) -> bool {
+        self.retry_after.is_some() || self.tokens < self.capacity * Self::LOW_RATIO
+    }
+}
+
+/// App-wide token bucket for Spotify Web API calls, shared by all instances via Redis.
+/// Spotify limits requests per client id in rolling 30 seconds window
+pub struct SpotifyRateLimitService;
+
+impl SpotifyRateLimitService {
+    pub const CAPACITY: u32 = 150;
+    pub const REFILL_PER_SECOND: u32 = 5;
+
+    fn bucket_key(client_id: &str) -> String {
+        format!("rustify:spotify_rate_limit:{client_id}:bucket")
+    }
+
+    fn retry_after_key(client_id: &str) -> String {
+        format!("rustify:spotify_rate_limit:{client_id}:retry_after")
+    }
+
+    async fn take(
+        redis_conn: &mut deadpool_redis::Connection,
+        client_id: &str,
+        cost: u32,
+    ) -> anyhow::Result<(i64, f64)> {
+        let (wait_ms, tokens): (i64, String) = TOKEN_BUCKET_SCRIPT
+            .key(Self::bucket_key(client_id))
+            .arg(Self::CAPACITY)
+            .arg(Self::REFILL_PER_SECOND)
+            .arg(Clock::now().and_utc().timestamp_millis())
+            .arg(cost)
+            .invoke_async(redis_conn)
+            .await?;
+
+        Ok((wait_ms, tokens.parse()?))
+    }
+
+    async fn retry_after(
+        redis_conn: &mut deadpool_redis::Connection,
+        client_id: &str,
+    ) -> anyhow::Result<Option<Duration>> {
+        let ttl: i64 = redis_conn.pttl(Self::retry_after_key(client_id)).await?;
+
+        Ok((ttl > 0).then(|| Duration::milliseconds(ttl)))
+    }
+
+    /// Takes one request from the budget
+    #[tracing::instrument(skip_all, fields(%client_id))]
+    pub async fn acquire(
+        redis_conn: &mut deadpool_redis::Connection,
+        client_id: &str,
+    ) -> anyhow::Result<RateLimitOutput> {
+        if let Some(retry_after) = Self::retry_after(redis_conn, client_id).await? {
+            return Ok(RateLimitOutput::NeedToWait(retry_after));
+        }
+
+        let (wait_ms, _) = Self::take(redis_conn, client_id, 1).await?;
+
+        if wait_ms > 0 {
+            return Ok(RateLimitOutput::NeedToWait(Duration::milliseconds(wait_ms)));
+        }
+
+        Ok(RateLimitOutput::Allowed)
+    }
+
+    /// Stops all requests until `Retry-After` from Spotify 429 response passes
+    #[tracing::instrument(skip_all, fields(%client_id, %retry_after))]
+    pub async fn set_retry_after(
+        redis_conn: &mut deadpool_redis::Connection,
+        client_id: &str,
+        retry_after: Duration,
+    ) -> anyhow::Result<()> {
+        let ms = retry_after.num_milliseconds().max(1);
+
+        let _: () = redis_conn
+            .pset_ex(Self::retry_after_key(client_id), ms, ms as u64)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(%client_id))]
+    pub async fn budget(
+        redis_conn: &mut deadpool_redis::Connection,
+        client_id: &str,
+    ) -> anyhow::Result<SpotifyRateBudget> {
+        let (_, tokens) = Self::take(redis_conn, client_id, 0).await?;
+
+        Ok(SpotifyRateBudget {
+            tokens,
+            capacity: f64::from(Self::CAPACITY),
+            retry_after: Self::retry_after(redis_conn, client_id).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_is_low() {
+        let budget = |tokens, retry_after| SpotifyRateBudget {
+            tokens,
+            capacity: 100.0,
+            retry_after,
+        };
+
+        assert!(!budget(50.0, None).is_low());
+        assert!(budget(10.0, None).is_low());
+        assert!(budget(100.0, Some(Duration::seconds(5))).is_low());
+    }
+}
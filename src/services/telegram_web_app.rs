@@ -0,0 +1,122 @@
+use chrono::Duration;
+use hmac::{Hmac, Mac as _};
+use itertools::Itertools as _;
+use sha2::Sha256;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct TelegramWebAppUser {
+    pub id: i64,
+}
+
+pub struct TelegramWebAppService;
+
+impl TelegramWebAppService {
+    /// Authorization scheme for `Authorization: tma <initData>` header
+    pub const AUTH_SCHEME: &str = "tma";
+
+    #[must_use]
+    pub fn init_data_ttl() -> Duration {
+        Duration::days(1)
+    }
+
+    /// Validates Mini App `initData` as described in
+    /// <https://core.telegram.org/bots/webapps#validating-data-received-via-the-mini-app>.
+    /// Returns user only for valid and not expired data
+    pub fn validate_init_data(
+        bot_token: &str,
+        init_data: &str,
+        now: i64,
+    ) -> anyhow::Result<Option<TelegramWebAppUser>> {
+        let mut hash = None;
+        let mut pairs = vec![];
+
+        for (key, value) in url::form_urlencoded::parse(init_data.as_bytes()) {
+            if key == "hash" {
+                hash = Some(value.into_owned());
+            } else {
+                pairs.push((key.into_owned(), value.into_owned()));
+            }
+        }
+
+        let Some(hash) = hash.and_then(|hash| hex::decode(hash).ok()) else {
+            return Ok(None);
+        };
+
+        pairs.sort();
+
+        let data_check_string = pairs
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .join("\n");
+
+        let mut secret = Hmac::<Sha256>::new_from_slice(b"WebAppData")?;
+        secret.update(bot_token.as_bytes());
+        let secret = secret.finalize().into_bytes();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&secret)?;
+        mac.update(data_check_string.as_bytes());
+
+        if mac.verify_slice(&hash).is_err() {
+            return Ok(None);
+        }
+
+        let auth_date = pairs
+            .iter()
+            .find(|(key, _)| key == "auth_date")
+            .and_then(|(_, value)| value.parse::<i64>().ok());
+
+        if auth_date.is_none_or(|auth_date| now - auth_date > Self::init_data_ttl().num_seconds()) {
+            return Ok(None);
+        }
+
+        let user = pairs
+            .iter()
+            .find(|(key, _)| key == "user")
+            .and_then(|(_, value)| serde_json::from_str(value).ok());
+
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "123456:TEST";
+    const INIT_DATA: &str = "query_id=AAH&user=%7B%22id%22%3A42%2C%22first_name%22%3A%22Test%22%7D&auth_date=1700000000&hash=98bf2679383811ad2055adb82c6c06b58e24ba010fcb4ad351fd3de10f879360";
+
+    #[test]
+    fn test_validate_init_data() {
+        assert_eq!(
+            TelegramWebAppService::validate_init_data(TOKEN, INIT_DATA, 1_700_000_100).unwrap(),
+            Some(TelegramWebAppUser { id: 42 })
+        );
+    }
+
+    #[test]
+    fn test_validate_init_data_wrong_token() {
+        assert_eq!(
+            TelegramWebAppService::validate_init_data("654321:TEST", INIT_DATA, 1_700_000_100)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_init_data_tampered() {
+        let init_data = INIT_DATA.replace("%3A42", "%3A43");
+
+        assert_eq!(
+            TelegramWebAppService::validate_init_data(TOKEN, &init_data, 1_700_000_100).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_init_data_expired() {
+        assert_eq!(
+            TelegramWebAppService::validate_init_data(TOKEN, INIT_DATA, 1_700_200_000).unwrap(),
+            None
+        );
+    }
+}
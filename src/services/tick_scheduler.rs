@@ -0,0 +1,219 @@
+use std::sync::LazyLock;
+
+use chrono::Duration;
+use deadpool_redis::redis::{AsyncCommands as _, Script};
+
+use crate::services::TickShardService;
+use crate::utils::Clock;
+
+/// Takes users with next poll time in the past and pushes them `ARGV[3]` into the future,
+/// so crashed worker doesn't lose them and nobody else takes them while they are processed
+static CLAIM_DUE_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            local ids = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1], 'LIMIT', 0, ARGV[2])
+
+            for _, id in ipairs(ids) do
+                redis.call('ZADD', KEYS[1], 'XX', ARGV[3], id)
+            end
+
+            return ids
+        ",
+    )
+});
+
+/// Adds new active users to be polled right away, removes users that are not active anymore.
+/// Returns amount of removed users
+static SYNC_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            local active = {}
+
+            for i = 2, #ARGV do
+                active[ARGV[i]] = true
+                redis.call('ZADD', KEYS[1], 'NX', ARGV[1], ARGV[i])
+            end
+
+            local removed = 0
+
+            for _, id in ipairs(redis.call('ZRANGE', KEYS[1], 0, -1)) do
+                if not active[id] then
+                    redis.call('ZREM', KEYS[1], id)
+                    removed = removed + 1
+                end
+            end
+
+            return removed
+        ",
+    )
+});
+
+/// Schedule of Spotify polling: sorted set of users by the time they should be polled next
+pub struct TickSchedulerService;
+
+impl TickSchedulerService {
+    /// Claimed users return to schedule after this time if worker died while processing them
+    const CLAIM_TIMEOUT: Duration = Duration::minutes(1);
+    /// Manual skips are noticed not later than this
+    const MAX_PLAYING_INTERVAL: Duration = Duration::seconds(10);
+    const MAX_THREADS: usize = 32;
+    /// Polling interval right after track change, when something may happen soon
+    const MIN_INTERVAL: Duration = Duration::seconds(3);
+    const MIN_THREADS: usize = 2;
+    /// Spotify switches tracks a bit after the end of previous one
+    const TRACK_END_MARGIN: Duration = Duration::seconds(1);
+    const USERS_PER_THREAD: usize = 50;
+
+    fn key(shard: u32) -> String {
+        format!("rustify:tick_scheduler:{shard}:queue")
+    }
+
+    fn user_key(user_id: &str) -> String {
+        Self::key(TickShardService::shard_of(user_id))
+    }
+
+    /// Delay before next poll of user listening to the track with `remaining` time left.
+    /// `None` means the track was just changed by us
+    #[must_use]
+    pub fn playing_interval(remaining: Option<Duration>) -> Duration {
+        remaining.map_or(Self::MIN_INTERVAL, |remaining| {
+            (remaining + Self::TRACK_END_MARGIN)
+                .clamp(Self::MIN_INTERVAL, Self::MAX_PLAYING_INTERVAL)
+        })
+    }
+
+    /// Parallel polling threads for amount of scheduled users
+    #[must_use]
+    pub fn threads_count(users: usize) -> usize {
+        users
+            .div_ceil(Self::USERS_PER_THREAD)
+            .clamp(Self::MIN_THREADS, Self::MAX_THREADS)
+    }
+
+    /// Syncs shard schedule with active users of this shard
+    #[tracing::instrument(skip_all, fields(shard, users = user_ids.len()))]
+    pub async fn sync(
+        redis_conn: &mut deadpool_redis::Connection,
+        shard: u32,
+        user_ids: &[&str],
+    ) -> anyhow::Result<u64> {
+        let removed: u64 = SYNC_SCRIPT
+            .key(Self::key(shard))
+            .arg(Clock::now().and_utc().timestamp_millis())
+            .arg(user_ids)
+            .invoke_async(redis_conn)
+            .await?;
+
+        Ok(removed)
+    }
+
+    /// Takes up to `limit` users of the shards which should be polled now
+    #[tracing::instrument(skip_all, fields(?shards, limit))]
+    pub async fn claim_due(
+        redis_conn: &mut deadpool_redis::Connection,
+        shards: &[u32],
+        limit: usize,
+    ) -> anyhow::Result<Vec<String>> {
+        let now = Clock::now().and_utc().timestamp_millis();
+        let mut user_ids = vec![];
+
+        for shard in shards {
+            let limit = limit.saturating_sub(user_ids.len());
+
+            if limit == 0 {
+                break;
+            }
+
+            let claimed: Vec<String> = CLAIM_DUE_SCRIPT
+                .key(Self::key(*shard))
+                .arg(now)
+                .arg(limit)
+                .arg(now + Self::CLAIM_TIMEOUT.num_milliseconds())
+                .invoke_async(redis_conn)
+                .await?;
+
+            user_ids.extend(claimed);
+        }
+
+        Ok(user_ids)
+    }
+
+    /// Sets next poll time for user, if user is still in schedule
+    #[tracing::instrument(skip_all, fields(%user_id, %delay))]
+    pub async fn schedule_in(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+        delay: Duration,
+    ) -> anyhow::Result<()> {
+        let at = (Clock::now() + delay).and_utc().timestamp_millis();
+
+        let _: () = deadpool_redis::redis::cmd("ZADD")
+            .arg(Self::user_key(user_id))
+            .arg("XX")
+            .arg(at)
+            .arg(user_id)
+            .query_async(redis_conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn remove(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+    ) -> anyhow::Result<()> {
+        let _: () = redis_conn.zrem(Self::user_key(user_id), user_id).await?;
+
+        Ok(())
+    }
+
+    /// Amount of scheduled users by shard
+    pub async fn shard_sizes(
+        redis_conn: &mut deadpool_redis::Connection,
+        shards: &[u32],
+    ) -> anyhow::Result<Vec<(u32, usize)>> {
+        let mut sizes = Vec::with_capacity(shards.len());
+
+        for shard in shards {
+            let len: usize = redis_conn.zcard(Self::key(*shard)).await?;
+
+            sizes.push((*shard, len));
+        }
+
+        Ok(sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playing_interval() {
+        assert_eq!(
+            TickSchedulerService::playing_interval(None),
+            Duration::seconds(3)
+        );
+        assert_eq!(
+            TickSchedulerService::playing_interval(Some(Duration::seconds(5))),
+            Duration::seconds(6),
+            "Poll right after the track end"
+        );
+        assert_eq!(
+            TickSchedulerService::playing_interval(Some(Duration::seconds(200))),
+            Duration::seconds(10)
+        );
+        assert_eq!(
+            TickSchedulerService::playing_interval(Some(Duration::zero())),
+            Duration::seconds(3)
+        );
+    }
+
+    #[test]
+    fn test_threads_count() {
+        assert_eq!(TickSchedulerService::threads_count(0), 2);
+        assert_eq!(TickSchedulerService::threads_count(101), 3);
+        assert_eq!(TickSchedulerService::threads_count(100_000), 32);
+    }
+}
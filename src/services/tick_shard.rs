@@ -0,0 +1,169 @@
+use std::sync::LazyLock;
+
+use chrono::Duration;
+use deadpool_redis::redis::Script;
+use rand::RngExt as _;
+use rand::distr::Alphanumeric;
+use sha2::{Digest as _, Sha256};
+
+use crate::utils::Clock;
+
+/// Registers worker heartbeat, renews its leases and rebalances shards,
+/// so every alive worker owns not more than its fair share.
+/// Returns indexes of owned shards
+static HEARTBEAT_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            local worker = ARGV[1]
+            local now = tonumber(ARGV[2])
+            local ttl = tonumber(ARGV[3])
+            local shards = #KEYS - 1
+
+            redis.call('ZADD', KEYS[1], now, worker)
+            redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - ttl)
+            redis.call('PEXPIRE', KEYS[1], ttl)
+
+            local workers = redis.call('ZCARD', KEYS[1])
+            local target = math.ceil(shards / workers)
+            local owned = {}
+
+            for i = 2, #KEYS do
+                if redis.call('GET', KEYS[i]) == worker then
+                    if #owned < target then
+                        redis.call('PEXPIRE', KEYS[i], ttl)
+                        table.insert(owned, i - 2)
+                    else
+                        redis.call('DEL', KEYS[i])
+                    end
+                end
+            end
+
+            for i = 2, #KEYS do
+                if #owned >= target then
+                    break
+                end
+
+                if redis.call('SET', KEYS[i], worker, 'NX', 'PX', ttl) then
+                    table.insert(owned, i - 2)
+                end
+            end
+
+            table.sort(owned)
+
+            return owned
+        ",
+    )
+});
+
+static RELEASE_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            redis.call('ZREM', KEYS[1], ARGV[1])
+
+            for i = 2, #KEYS do
+                if redis.call('GET', KEYS[i]) == ARGV[1] then
+                    redis.call('DEL', KEYS[i])
+                end
+            end
+
+            return 0
+        ",
+    )
+});
+
+static WORKER_ID: LazyLock<String> = LazyLock::new(|| {
+    rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(12)
+        .map(char::from)
+        .collect()
+});
+
+/// Splits users between track check workers.
+///
+/// Each user belongs to one of `SHARDS`, each shard is leased to one worker
+/// and the lease is renewed with heartbeats. Leases of dead worker expire and are taken over by others
+pub struct TickShardService;
+
+impl TickShardService {
+    pub const SHARDS: u32 = 16;
+
+    /// Heartbeats should be sent few times within this period
+    #[must_use]
+    pub fn lease_ttl() -> Duration {
+        Duration::seconds(15)
+    }
+
+    /// Random id of current worker process
+    #[must_use]
+    pub fn worker_id() -> &'static str {
+        &WORKER_ID
+    }
+
+    #[must_use]
+    pub fn shard_of(user_id: &str) -> u32 {
+        let hash = Sha256::digest(user_id.as_bytes());
+
+        u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]) % Self::SHARDS
+    }
+
+    fn workers_key() -> &'static str {
+        "rustify:tick_shards:workers"
+    }
+
+    fn lease_keys() -> Vec<String> {
+        (0..Self::SHARDS)
+            .map(|shard| format!("rustify:tick_shards:{shard}:owner"))
+            .collect()
+    }
+
+    /// Renews leases of current worker and takes free shards. Returns owned shards
+    #[tracing::instrument(skip_all, fields(worker_id = Self::worker_id()))]
+    pub async fn heartbeat(
+        redis_conn: &mut deadpool_redis::Connection,
+    ) -> anyhow::Result<Vec<u32>> {
+        let shards: Vec<u32> = HEARTBEAT_SCRIPT
+            .key(Self::workers_key())
+            .key(Self::lease_keys())
+            .arg(Self::worker_id())
+            .arg(Clock::now().and_utc().timestamp_millis())
+            .arg(Self::lease_ttl().num_milliseconds())
+            .invoke_async(redis_conn)
+            .await?;
+
+        Ok(shards)
+    }
+
+    /// Gives shards away on shutdown, so other workers don't wait for leases to expire
+    #[tracing::instrument(skip_all, fields(worker_id = Self::worker_id()))]
+    pub async fn release(redis_conn: &mut deadpool_redis::Connection) -> anyhow::Result<()> {
+        let _: i64 = RELEASE_SCRIPT
+            .key(Self::workers_key())
+            .key(Self::lease_keys())
+            .arg(Self::worker_id())
+            .invoke_async(redis_conn)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shard_of() {
+        assert_eq!(
+            TickShardService::shard_of("123456"),
+            TickShardService::shard_of("123456"),
+            "User stays in the same shard"
+        );
+
+        let shards = (0..1000)
+            .map(|user_id| TickShardService::shard_of(&user_id.to_string()))
+            .collect::<std::collections::HashSet<_>>();
+
+        assert_eq!(shards.len(), TickShardService::SHARDS as usize);
+    }
+}
@@ -0,0 +1,327 @@
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ColumnTrait as _,
+    ConnectionTrait,
+    DeleteResult,
+    EntityTrait as _,
+    QueryFilter as _,
+    QueryOrder as _,
+    QuerySelect as _,
+};
+use serde_json::json;
+use strum::IntoEnumIterator as _;
+use strum_macros::{AsRefStr, EnumIter};
+
+use crate::entity::prelude::{TrackAnalysisActiveModel, TrackAnalysisColumn, TrackAnalysisEntity};
+use crate::utils::Clock;
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, AsRefStr, EnumIter,
+)]
+pub enum AgeRating {
+    #[serde(rename = "0+")]
+    #[strum(serialize = "0+")]
+    Everyone,
+    #[serde(rename = "6+")]
+    #[strum(serialize = "6+")]
+    Age6,
+    #[serde(rename = "12+")]
+    #[strum(serialize = "12+")]
+    Age12,
+    #[serde(rename = "16+")]
+    #[strum(serialize = "16+")]
+    Age16,
+    #[serde(rename = "18+")]
+    #[strum(serialize = "18+")]
+    Age18,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, AsRefStr, EnumIter)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ContentLevel {
+    None,
+    Mild,
+    Strong,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContentRating {
+    pub level: ContentLevel,
+    pub details: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContentRatings {
+    pub profanity: ContentRating,
+    pub sexual: ContentRating,
+    pub drugs: ContentRating,
+    pub violence: ContentRating,
+    pub religion: ContentRating,
+    pub occultism: ContentRating,
+}
+
+impl ContentRatings {
+    const CATEGORIES: [&str; 6] = [
+        "profanity",
+        "sexual",
+        "drugs",
+        "violence",
+        "religion",
+        "occultism",
+    ];
+
+    /// Categories with their names, in the order they are shown
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &ContentRating)> {
+        Self::CATEGORIES.into_iter().zip([
+            &self.profanity,
+            &self.sexual,
+            &self.drugs,
+            &self.violence,
+            &self.religion,
+            &self.occultism,
+        ])
+    }
+}
+
+/// Structured AI analysis of the track lyrics
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnalysisReport {
+    /// Goes first, so it can be shown while the rest is generated
+    pub description: String,
+    pub summary: String,
+    pub themes: Vec<String>,
+    pub mood: String,
+    pub content: ContentRatings,
+    pub age_rating: AgeRating,
+}
+
+impl AnalysisReport {
+    pub const TOOL_NAME: &str = "analyze_lyrics";
+
+    /// Parameters of the tool AI is forced to call with the report
+    #[must_use]
+    pub fn schema() -> serde_json::Value {
+        let levels: Vec<_> = ContentLevel::iter()
+            .map(|level| level.as_ref().to_owned())
+            .collect();
+        let ratings: Vec<_> = AgeRating::iter()
+            .map(|rating| rating.as_ref().to_owned())
+            .collect();
+
+        let rating = json!({
+            "type": "object",
+            "properties": {
+                "level": {
+                    "type": "string",
+                    "enum": levels,
+                    "description": "How much of this content the song has"
+                },
+                "details": {
+                    "type": "string",
+                    "description": "What exactly is in the song, empty if level is none"
+                }
+            },
+            "additionalProperties": false,
+            "required": ["level", "details"]
+        });
+
+        let categories: serde_json::Map<_, _> = ContentRatings::CATEGORIES
+            .iter()
+            .map(|category| ((*category).to_owned(), rating.clone()))
+            .collect();
+
+        json!({
+            "type": "object",
+            "properties": {
+                "description": {
+                    "type": "string",
+                    "description": "Detailed description, meaning and storyline of the song, up to 1500 characters, no formatting"
+                },
+                "summary": {
+                    "type": "string",
+                    "description": "One sentence summary of the song"
+                },
+                "themes": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Main themes of the song, one to three words each"
+                },
+                "mood": {
+                    "type": "string",
+                    "description": "Mood of the song in a few words"
+                },
+                "content": {
+                    "type": "object",
+                    "properties": categories,
+                    "additionalProperties": false,
+                    "required": ContentRatings::CATEGORIES
+                },
+                "age_rating": {
+                    "type": "string",
+                    "enum": ratings,
+                    "description": "Minimal listener age the song is suitable for"
+                }
+            },
+            "additionalProperties": false,
+            "required": ["description", "summary", "themes", "mood", "content", "age_rating"]
+        })
+    }
+}
+
+fn parse(analysis: &str) -> Option<AnalysisReport> {
+    // Free-form analyses saved before reports were structured are generated again
+    serde_json::from_str(analysis)
+        .inspect_err(|err| tracing::debug!(err = ?err, "Analysis is not a report"))
+        .ok()
+}
+
+/// AI lyrics analyses, generated once per track, locale and model
+pub struct TrackAnalysisService;
+
+impl TrackAnalysisService {
+    #[tracing::instrument(skip_all, fields(%track_id, %locale, %model))]
+    pub async fn find(
+        db: &impl ConnectionTrait,
+        track_id: &str,
+        locale: &str,
+        model: &str,
+    ) -> anyhow::Result<Option<AnalysisReport>> {
+        let analysis: Option<String> = TrackAnalysisEntity::find()
+            .select_only()
+            .column(TrackAnalysisColumn::Analysis)
+            .filter(TrackAnalysisColumn::TrackId.eq(track_id))
+            .filter(TrackAnalysisColumn::Locale.eq(locale))
+            .filter(TrackAnalysisColumn::Model.eq(model))
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(analysis.as_deref().and_then(parse))
+    }
+
+    /// Latest report of the track in any locale and model, e.g. to get age rating
+    #[tracing::instrument(skip_all, fields(%track_id))]
+    pub async fn find_any(
+        db: &impl ConnectionTrait,
+        track_id: &str,
+    ) -> anyhow::Result<Option<AnalysisReport>> {
+        let analyses: Vec<String> = TrackAnalysisEntity::find()
+            .select_only()
+            .column(TrackAnalysisColumn::Analysis)
+            .filter(TrackAnalysisColumn::TrackId.eq(track_id))
+            .order_by_desc(TrackAnalysisColumn::UpdatedAt)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(analyses.iter().find_map(|analysis| parse(analysis)))
+    }
+
+    #[tracing::instrument(skip_all, fields(%track_id, %locale, %model))]
+    pub async fn save(
+        db: &impl ConnectionTrait,
+        track_id: &str,
+        locale: &str,
+        model: &str,
+        report: &AnalysisReport,
+    ) -> anyhow::Result<()> {
+        let model = TrackAnalysisActiveModel {
+            track_id: Set(track_id.into()),
+            locale: Set(locale.into()),
+            model: Set(model.into()),
+            analysis: Set(serde_json::to_string(report)?),
+            ..Default::default()
+        };
+
+        TrackAnalysisEntity::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    TrackAnalysisColumn::TrackId,
+                    TrackAnalysisColumn::Locale,
+                    TrackAnalysisColumn::Model,
+                ])
+                .update_column(TrackAnalysisColumn::Analysis)
+                .value(TrackAnalysisColumn::UpdatedAt, Clock::now())
+                .to_owned(),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes analyses of the track in all locales and models
+    #[tracing::instrument(skip_all, fields(%track_id))]
+    pub async fn clear(db: &impl ConnectionTrait, track_id: &str) -> anyhow::Result<DeleteResult> {
+        let result = TrackAnalysisEntity::delete_many()
+            .filter(TrackAnalysisColumn::TrackId.eq(track_id))
+            .exec(db)
+            .await?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_matches_schema() {
+        let schema = AnalysisReport::schema();
+
+        let rating = json!({ "level": "mild", "details": "Some" });
+        let report = json!({
+            "description": "Song about summer",
+            "summary": "Summer",
+            "themes": ["summer", "love"],
+            "mood": "Happy",
+            "content": {
+                "profanity": rating,
+                "sexual": rating,
+                "drugs": { "level": "none", "details": "" },
+                "violence": rating,
+                "religion": rating,
+                "occultism": { "level": "strong", "details": "Rituals" }
+            },
+            "age_rating": "12+"
+        });
+
+        let required: Vec<_> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field.as_str().unwrap())
+            .collect();
+
+        let fields: Vec<_> = report
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+
+        assert_eq!(required.len(), fields.len());
+        assert!(fields.iter().all(|field| required.contains(field)));
+
+        let report: AnalysisReport = serde_json::from_value(report).unwrap();
+
+        assert_eq!(report.age_rating, AgeRating::Age12);
+        assert_eq!(report.content.drugs.level, ContentLevel::None);
+        assert_eq!(report.content.iter().count(), 6);
+    }
+
+    #[test]
+    fn test_age_rating_order() {
+        assert!(AgeRating::Age16 > AgeRating::Age12);
+        assert!(AgeRating::Everyone < AgeRating::Age6);
+        assert_eq!(AgeRating::Age18.as_ref(), "18+");
+    }
+
+    #[test]
+    fn test_free_form_analysis_is_not_report() {
+        assert!(parse("This song is about love").is_none());
+    }
+}
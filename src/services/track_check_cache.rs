@@ -0,0 +1,337 @@
+use std::sync::LazyLock;
+
+use chrono::Duration;
+use deadpool_redis::redis::{AsyncCommands as _, Script};
+use rand::RngExt as _;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::profanity;
+use crate::services::ai_slop_detection::AISlopDetectionResult;
+
+/// Releases the lock only if it's still held by the same worker
+static UNLOCK_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+            if redis.call('GET', KEYS[1]) == ARGV[1] then
+                return redis.call('DEL', KEYS[1])
+            end
+
+            return 0
+        ",
+    )
+});
+
+#[derive(Clone, Copy, Debug)]
+pub enum TrackCheckKind {
+    Lyrics,
+    AISlop,
+    AgeRating,
+}
+
+impl TrackCheckKind {
+    /// Lyrics results have profanity verdicts, so they are kept apart for every set of custom words
+    fn name(self) -> String {
+        match self {
+            Self::Lyrics => format!("lyrics:{:x}", profanity::Manager::custom_words_version()),
+            Self::AISlop => "ai_slop".to_owned(),
+            Self::AgeRating => "age_rating".to_owned(),
+        }
+    }
+
+    /// How long negative results are kept. Age rating isn't there when user's AI quota is
+    /// used up, so only real ratings are shared
+    fn negative_ttl(self) -> Option<Duration> {
+        match self {
+            Self::Lyrics | Self::AISlop => Some(Duration::minutes(10)),
+            Self::AgeRating => None,
+        }
+    }
+}
+
+/// Result of a track check which can be shared between users
+pub trait TrackCheckResult: Serialize + DeserializeOwned {
+    /// Nothing was found for the track yet, e.g. lyrics aren't published.
+    /// Such results are cached shortly to be checked again soon
+    fn is_negative(&self) -> bool;
+}
+
+impl<T: Serialize + DeserializeOwned> TrackCheckResult for Option<T> {
+    fn is_negative(&self) -> bool {
+        self.is_none()
+    }
+}
+
+impl TrackCheckResult for AISlopDetectionResult {
+    fn is_negative(&self) -> bool {
+        self.provider.is_none()
+    }
+}
+
+/// Results of track checks shared between users.
+///
+/// Only one worker computes result for a track at a time, others wait for it with `lock`
+pub struct TrackCheckCacheService;
+
+impl TrackCheckCacheService {
+    /// Lock expires if computing worker died.
+    /// Longer than track check timeout, so it doesn't expire while the result is computed
+    pub const LOCK_TTL: Duration = Duration::minutes(2);
+    const TTL: Duration = Duration::days(1);
+
+    fn key(kind: TrackCheckKind, track_id: &str) -> String {
+        format!("rustify:track_check_cache:{}:{track_id}", kind.name())
+    }
+
+    fn lock_key(kind: TrackCheckKind, track_id: &str) -> String {
+        format!("rustify:track_check_cache:{}:{track_id}:lock", kind.name())
+    }
+
+    #[tracing::instrument(skip_all, fields(?kind, %track_id))]
+    pub async fn get<T: DeserializeOwned>(
+        redis_conn: &mut deadpool_redis::Connection,
+        kind: TrackCheckKind,
+        track_id: &str,
+    ) -> anyhow::Result<Option<T>> {
+        let value: Option<String> = redis_conn.get(Self::key(kind, track_id)).await?;
+
+        value
+            .map(|value| serde_json::from_str(&value))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Result is computed again on the next check, e.g. when its inputs are changed
+    #[tracing::instrument(skip_all, fields(?kind, %track_id))]
+    pub async fn clear(
+        redis_conn: &mut deadpool_redis::Connection,
+        kind: TrackCheckKind,
+        track_id: &str,
+    ) -> anyhow::Result<()> {
+        let _: () = redis_conn.del(Self::key(kind, track_id)).await?;
+
+        Ok(())
+    }
+
+    /// Returns lock token if current worker should compute the result
+    #[tracing::instrument(skip_all, fields(?kind, %track_id))]
+    pub async fn lock(
+        redis_conn: &mut deadpool_redis::Connection,
+        kind: TrackCheckKind,
+        track_id: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let token = format!("{:032x}", rand::rng().random::<u128>());
+
+        let locked: Option<String> = deadpool_redis::redis::cmd("SET")
+            .arg(Self::lock_key(kind, track_id))
+            .arg(&token)
+            .arg("NX")
+            .arg("PX")
+            .arg(Self::LOCK_TTL.num_milliseconds())
+            .query_async(redis_conn)
+            .await?;
+
+        Ok(locked.map(|_| token))
+    }
+
+    /// Releases the lock, unless it expired and was taken by another worker
+    #[tracing::instrument(skip_all, fields(?kind, %track_id))]
+    pub async fn unlock(
+        redis_conn: &mut deadpool_redis::Connection,
+        kind: TrackCheckKind,
+        track_id: &str,
+        token: &str,
+    ) -> anyhow::Result<()> {
+        let _: u8 = UNLOCK_SCRIPT
+            .key(Self::lock_key(kind, track_id))
+            .arg(token)
+            .invoke_async(redis_conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Stores the result, unless it's negative and such results aren't shared,
+    /// and releases the lock if it's held
+    #[tracing::instrument(skip_all, fields(?kind, %track_id))]
+    pub async fn set<T: TrackCheckResult>(
+        redis_conn: &mut deadpool_redis::Connection,
+        kind: TrackCheckKind,
+        track_id: &str,
+        value: &T,
+        token: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let ttl = if value.is_negative() {
+            kind.negative_ttl()
+        } else {
+            Some(Self::TTL)
+        };
+
+        if let Some(ttl) = ttl {
+            let _: () = redis_conn
+                .set_ex(
+                    Self::key(kind, track_id),
+                    serde_json::to_string(value)?,
+                    ttl.num_seconds().try_into()?,
+                )
+                .await?;
+        }
+
+        match token {
+            Some(token) => Self::unlock(redis_conn, kind, track_id, token).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn test_unlock_with_foreign_token() {
+        let mut redis_conn = TestApp::redis_conn().await.unwrap();
+        let track_id = TestApp::user_id();
+
+        let token =
+            TrackCheckCacheService::lock(&mut redis_conn, TrackCheckKind::Lyrics, &track_id)
+                .await
+                .unwrap()
+                .unwrap();
+
+        assert!(
+            TrackCheckCacheService::lock(&mut redis_conn, TrackCheckKind::Lyrics, &track_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        TrackCheckCacheService::unlock(&mut redis_conn, TrackCheckKind::Lyrics, &track_id, "stale")
+            .await
+            .unwrap();
+
+        assert!(
+            TrackCheckCacheService::lock(&mut redis_conn, TrackCheckKind::Lyrics, &track_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        TrackCheckCacheService::unlock(&mut redis_conn, TrackCheckKind::Lyrics, &track_id, &token)
+            .await
+            .unwrap();
+
+        assert!(
+            TrackCheckCacheService::lock(&mut redis_conn, TrackCheckKind::Lyrics, &track_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn test_negative_ttl() {
+        let mut redis_conn = TestApp::redis_conn().await.unwrap();
+        let track_id = TestApp::user_id();
+
+        for (value, ttl) in [
+            (None, TrackCheckKind::Lyrics.negative_ttl().unwrap()),
+            (Some(1), TrackCheckCacheService::TTL),
+        ] {
+            TrackCheckCacheService::set(
+                &mut redis_conn,
+                TrackCheckKind::Lyrics,
+                &track_id,
+                &value,
+                None,
+            )
+            .await
+            .unwrap();
+
+            let actual: i64 = redis_conn
+                .ttl(TrackCheckCacheService::key(
+                    TrackCheckKind::Lyrics,
+                    &track_id,
+                ))
+                .await
+                .unwrap();
+
+            assert!(actual > ttl.num_seconds() - 5 && actual <= ttl.num_seconds());
+
+            let cached: Option<Option<i32>> =
+                TrackCheckCacheService::get(&mut redis_conn, TrackCheckKind::Lyrics, &track_id)
+                    .await
+                    .unwrap();
+
+            assert_eq!(cached, Some(value));
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn test_negative_age_rating_not_shared() {
+        let mut redis_conn = TestApp::redis_conn().await.unwrap();
+        let track_id = TestApp::user_id();
+
+        let token =
+            TrackCheckCacheService::lock(&mut redis_conn, TrackCheckKind::AgeRating, &track_id)
+                .await
+                .unwrap();
+
+        TrackCheckCacheService::set(
+            &mut redis_conn,
+            TrackCheckKind::AgeRating,
+            &track_id,
+            &None::<i32>,
+            token.as_deref(),
+        )
+        .await
+        .unwrap();
+
+        let cached: Option<Option<i32>> =
+            TrackCheckCacheService::get(&mut redis_conn, TrackCheckKind::AgeRating, &track_id)
+                .await
+                .unwrap();
+
+        assert_eq!(cached, None);
+
+        // Next worker computes it itself
+        assert!(
+            TrackCheckCacheService::lock(&mut redis_conn, TrackCheckKind::AgeRating, &track_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn test_clear() {
+        let mut redis_conn = TestApp::redis_conn().await.unwrap();
+        let track_id = TestApp::user_id();
+
+        TrackCheckCacheService::set(
+            &mut redis_conn,
+            TrackCheckKind::Lyrics,
+            &track_id,
+            &Some(1),
+            None,
+        )
+        .await
+        .unwrap();
+
+        TrackCheckCacheService::clear(&mut redis_conn, TrackCheckKind::Lyrics, &track_id)
+            .await
+            .unwrap();
+
+        let cached: Option<Option<i32>> =
+            TrackCheckCacheService::get(&mut redis_conn, TrackCheckKind::Lyrics, &track_id)
+                .await
+                .unwrap();
+
+        assert_eq!(cached, None);
+    }
+}
@@ -1,10 +1,11 @@
 use sea_orm::ActiveValue::Set;
 use sea_orm::prelude::*;
-use sea_orm::sea_query::{Alias, Expr};
+use sea_orm::sea_query::{Alias, Expr, Func, SimpleExpr};
 use sea_orm::{
     ConnectionTrait,
     FromQueryResult,
     IntoActiveModel as _,
+    QueryOrder as _,
     QuerySelect as _,
     UpdateResult,
 };
@@ -149,6 +150,61 @@ impl TrackStatusService {
         }
     }
 
+    #[tracing::instrument(skip_all, fields(%user_id, ?status, %page, %limit))]
+    pub async fn list_by_status(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        status: TrackStatus,
+        page: u64,
+        limit: u64,
+    ) -> anyhow::Result<Vec<TrackStatusModel>> {
+        let res = Self::builder()
+            .user_id(Some(user_id))
+            .status(Some(status))
+            .build()
+            .order_by_desc(TrackStatusColumn::UpdatedAt)
+            .offset(page * limit)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Number of tracks which got the status per day, by date of the first reaction on the track
+    #[tracing::instrument(skip_all, fields(%user_id, ?status))]
+    pub async fn count_status_by_day(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        status: TrackStatus,
+        since: chrono::NaiveDateTime,
+    ) -> anyhow::Result<Vec<(chrono::NaiveDate, i64)>> {
+        let day = || -> SimpleExpr {
+            Func::cust(Alias::new("date"))
+                .arg(Expr::col(TrackStatusColumn::CreatedAt))
+                .into()
+        };
+
+        let res = Self::builder()
+            .user_id(Some(user_id))
+            .status(Some(status))
+            .build()
+            .filter(TrackStatusColumn::CreatedAt.gte(since))
+            .select_only()
+            .expr_as(day(), "day")
+            .expr_as(
+                TrackStatusColumn::Id.count().cast_as(Alias::new("bigint")),
+                "count",
+            )
+            .group_by(day())
+            .order_by_asc(day())
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(res)
+    }
+
     #[tracing::instrument(skip_all, fields(%user_id, %track_id))]
     pub async fn increase_skips(
         db: &impl ConnectionTrait,
@@ -0,0 +1,107 @@
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ColumnTrait as _,
+    ConnectionTrait,
+    EntityTrait as _,
+    QueryFilter as _,
+    QuerySelect as _,
+};
+use serde_json::json;
+
+use crate::entity::prelude::{
+    TrackTranslationActiveModel,
+    TrackTranslationColumn,
+    TrackTranslationEntity,
+};
+use crate::utils::Clock;
+
+/// Line by line AI translation of the track lyrics
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrackTranslation {
+    /// Same amount and order as original lines
+    pub lines: Vec<String>,
+}
+
+impl TrackTranslation {
+    pub const TOOL_NAME: &str = "translate_lyrics";
+
+    /// Parameters of the tool AI is forced to call with the translation
+    #[must_use]
+    pub fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "lines": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Translated lines, exactly one for each original line, empty for empty ones"
+                }
+            },
+            "additionalProperties": false,
+            "required": ["lines"]
+        })
+    }
+}
+
+/// AI lyrics translations, generated once per track and target language
+pub struct TrackTranslationService;
+
+impl TrackTranslationService {
+    #[tracing::instrument(skip_all, fields(%track_id, %language))]
+    pub async fn find(
+        db: &impl ConnectionTrait,
+        track_id: &str,
+        language: &str,
+    ) -> anyhow::Result<Option<TrackTranslation>> {
+        let translation: Option<String> = TrackTranslationEntity::find()
+            .select_only()
+            .column(TrackTranslationColumn::Translation)
+            .filter(TrackTranslationColumn::TrackId.eq(track_id))
+            .filter(TrackTranslationColumn::Language.eq(language))
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(translation.and_then(|translation| {
+            serde_json::from_str(&translation)
+                .inspect_err(|err| tracing::debug!(err = ?err, "Invalid saved translation"))
+                .ok()
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(%track_id, %language, %model))]
+    pub async fn save(
+        db: &impl ConnectionTrait,
+        track_id: &str,
+        language: &str,
+        model: &str,
+        translation: &TrackTranslation,
+    ) -> anyhow::Result<()> {
+        let model = TrackTranslationActiveModel {
+            track_id: Set(track_id.into()),
+            language: Set(language.into()),
+            model: Set(model.into()),
+            translation: Set(serde_json::to_string(translation)?),
+            ..Default::default()
+        };
+
+        TrackTranslationEntity::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    TrackTranslationColumn::TrackId,
+                    TrackTranslationColumn::Language,
+                ])
+                .update_columns([
+                    TrackTranslationColumn::Model,
+                    TrackTranslationColumn::Translation,
+                ])
+                .value(TrackTranslationColumn::UpdatedAt, Clock::now())
+                .to_owned(),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
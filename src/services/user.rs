@@ -1,7 +1,8 @@
 use chrono::Duration;
 use deadpool_redis::redis::AsyncCommands as _;
 use sea_orm::prelude::*;
-use sea_orm::sea_query::{Alias, Expr, Func};
+use sea_orm::sea_query::extension::postgres::PgExpr as _;
+use sea_orm::sea_query::{Alias, Condition, Expr, Func};
 use sea_orm::{
     ConnectionTrait,
     FromQueryResult,
@@ -12,6 +13,7 @@ use sea_orm::{
     UpdateMany,
     UpdateResult,
 };
+use sha2::{Digest as _, Sha256};
 
 use crate::entity::prelude::*;
 use crate::lyrics;
@@ -80,6 +82,30 @@ impl UserStatsIncreaseQueryBuilder {
         self
     }
 
+    pub fn skipped_skippage(mut self) -> Self {
+        self.0 = self.0.col_expr(
+            UserColumn::SkippedSkippage,
+            Expr::col(UserColumn::SkippedSkippage).add(1),
+        );
+
+        self
+    }
+
+    pub fn ai_slop_detected(mut self, skipped: bool) -> Self {
+        self.0 = self
+            .0
+            .col_expr(
+                UserColumn::AISlopDetected,
+                Expr::col(UserColumn::AISlopDetected).add(1),
+            )
+            .col_expr(
+                UserColumn::SkippedAISlop,
+                Expr::col(UserColumn::SkippedAISlop).add(i32::from(skipped)),
+            );
+
+        self
+    }
+
     pub async fn exec(self, db: &impl ConnectionTrait) -> Result<UpdateResult, DbErr> {
         self.0
             .col_expr(UserColumn::UpdatedAt, Expr::value(Clock::now()))
@@ -99,6 +125,9 @@ pub struct UserStats {
     pub lyrics_musixmatch: i64,
     pub lyrics_lrclib: i64,
     pub lyrics_analyzed: i64,
+    pub skipped_skippage: i64,
+    pub skipped_ai_slop: i64,
+    pub ai_slop_detected: i64,
 }
 
 pub struct UserService;
@@ -119,6 +148,30 @@ impl UserService {
         query
     }
 
+    /// Same as [`Self::query`], but looks for user by exact ID or part of the name
+    #[must_use]
+    pub fn search_query(search: Option<&str>, status: Option<UserStatus>) -> Select<UserEntity> {
+        let mut query = Self::query(None, status);
+
+        if let Some(search) = search.map(str::trim).filter(|search| !search.is_empty()) {
+            let pattern = format!(
+                "%{}%",
+                search
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_")
+            );
+
+            query = query.filter(
+                Condition::any()
+                    .add(UserColumn::Id.eq(search))
+                    .add(Expr::col(UserColumn::Name).ilike(pattern)),
+            );
+        }
+
+        query
+    }
+
     #[tracing::instrument(skip_all, fields(user_id = %id))]
     pub async fn sync_name(
         db: &impl ConnectionTrait,
@@ -303,6 +356,102 @@ impl UserService {
         Ok(res)
     }
 
+    #[tracing::instrument(skip_all, fields(user_id = %id))]
+    pub async fn set_cfg_check_profanity(
+        db: &impl ConnectionTrait,
+        id: &str,
+        enabled: bool,
+    ) -> anyhow::Result<UpdateResult> {
+        let res = UserEntity::update_many()
+            .filter(UserColumn::Id.eq(id))
+            .col_expr(UserColumn::CfgCheckProfanity, Expr::value(enabled))
+            .col_expr(UserColumn::UpdatedAt, Expr::value(Clock::now()))
+            .exec(db)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[tracing::instrument(skip_all, fields(user_id = %id))]
+    pub async fn set_cfg_kid_mode(
+        db: &impl ConnectionTrait,
+        id: &str,
+        enabled: bool,
+    ) -> anyhow::Result<UpdateResult> {
+        let res = UserEntity::update_many()
+            .filter(UserColumn::Id.eq(id))
+            .col_expr(UserColumn::CfgKidMode, Expr::value(enabled))
+            .col_expr(UserColumn::UpdatedAt, Expr::value(Clock::now()))
+            .exec(db)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[tracing::instrument(skip_all, fields(user_id = %id))]
+    pub async fn set_cfg_skip_explicit(
+        db: &impl ConnectionTrait,
+        id: &str,
+        enabled: bool,
+    ) -> anyhow::Result<UpdateResult> {
+        let res = UserEntity::update_many()
+            .filter(UserColumn::Id.eq(id))
+            .col_expr(UserColumn::CfgSkipExplicit, Expr::value(enabled))
+            .col_expr(UserColumn::UpdatedAt, Expr::value(Clock::now()))
+            .exec(db)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[tracing::instrument(skip_all, fields(user_id = %id))]
+    pub async fn set_cfg_skip_tracks(
+        db: &impl ConnectionTrait,
+        id: &str,
+        enabled: bool,
+    ) -> anyhow::Result<UpdateResult> {
+        let res = UserEntity::update_many()
+            .filter(UserColumn::Id.eq(id))
+            .col_expr(UserColumn::CfgSkipTracks, Expr::value(enabled))
+            .col_expr(UserColumn::UpdatedAt, Expr::value(Clock::now()))
+            .exec(db)
+            .await?;
+
+        Ok(res)
+    }
+
+    /// PIN is salted with user ID, so equal PINs of different users have different hashes
+    #[must_use]
+    pub fn settings_pin_hash(id: &str, pin: &str) -> String {
+        hex::encode(Sha256::digest(format!("{id}:{pin}").as_bytes()))
+    }
+
+    #[must_use]
+    pub fn is_valid_settings_pin(pin: &str) -> bool {
+        (4..=8).contains(&pin.len()) && pin.chars().all(|char| char.is_ascii_digit())
+    }
+
+    /// Sets settings profile, PIN is required to switch from non-standard profile
+    #[tracing::instrument(skip_all, fields(user_id = %id, ?profile))]
+    pub async fn set_profile(
+        db: &impl ConnectionTrait,
+        id: &str,
+        profile: UserProfile,
+        pin: Option<&str>,
+    ) -> anyhow::Result<UpdateResult> {
+        let pin_hash = pin.map(|pin| Self::settings_pin_hash(id, pin));
+
+        let res = UserEntity::update_many()
+            .filter(UserColumn::Id.eq(id))
+            .col_expr(UserColumn::Profile, Expr::value(profile))
+            .col_expr(UserColumn::SettingsPinHash, Expr::value(pin_hash))
+            .col_expr(UserColumn::UpdatedAt, Expr::value(Clock::now()))
+            .exec(db)
+            .await?;
+
+        Ok(res)
+    }
+
     #[tracing::instrument(skip_all, fields(user_id = %id))]
     pub async fn set_locale(
         db: &impl ConnectionTrait,
@@ -479,6 +628,27 @@ impl UserService {
                 ]),
                 "lyrics_analyzed",
             )
+            .expr_as(
+                Func::coalesce([
+                    UserColumn::SkippedSkippage.sum().cast_as(bigint()),
+                    Expr::val(0).into(),
+                ]),
+                "skipped_skippage",
+            )
+            .expr_as(
+                Func::coalesce([
+                    UserColumn::SkippedAISlop.sum().cast_as(bigint()),
+                    Expr::val(0).into(),
+                ]),
+                "skipped_ai_slop",
+            )
+            .expr_as(
+                Func::coalesce([
+                    UserColumn::AISlopDetected.sum().cast_as(bigint()),
+                    Expr::val(0).into(),
+                ]),
+                "ai_slop_detected",
+            )
             .into_model::<UserStats>()
             .one(db)
             .await?
@@ -514,3 +684,30 @@ impl UserService {
         Ok(users)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_settings_pin() {
+        assert!(UserService::is_valid_settings_pin("1234"));
+        assert!(UserService::is_valid_settings_pin("12345678"));
+        assert!(!UserService::is_valid_settings_pin("123"));
+        assert!(!UserService::is_valid_settings_pin("123456789"));
+        assert!(!UserService::is_valid_settings_pin("12a4"));
+        assert!(!UserService::is_valid_settings_pin("١٢٣٤"));
+    }
+
+    #[test]
+    fn test_settings_pin_hash() {
+        assert_eq!(
+            UserService::settings_pin_hash("1", "1234"),
+            UserService::settings_pin_hash("1", "1234")
+        );
+        assert_ne!(
+            UserService::settings_pin_hash("1", "1234"),
+            UserService::settings_pin_hash("2", "1234")
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use itertools::Itertools as _;
+use sea_orm::ActiveValue::Set;
+use sea_orm::prelude::*;
+use sea_orm::sea_query::{Alias, OnConflict};
+use sea_orm::{ConnectionTrait, QueryOrder as _, QuerySelect as _};
+
+use crate::entity::prelude::*;
+use crate::utils::Clock;
+
+pub struct UserProfaneWordStatsService;
+
+impl UserProfaneWordStatsService {
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn increase_occurrences(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        words: impl IntoIterator<Item = impl Into<String>>,
+    ) -> anyhow::Result<()> {
+        let models = words
+            .into_iter()
+            .map(|word| UserProfaneWordStatsActiveModel {
+                user_id: Set(user_id.to_owned()),
+                word: Set(word.into()),
+                occurrences: Set(1),
+                updated_at: Set(Clock::now()),
+                ..Default::default()
+            })
+            .collect_vec();
+
+        if models.is_empty() {
+            return Ok(());
+        }
+
+        UserProfaneWordStatsEntity::insert_many(models)
+            .on_conflict(
+                OnConflict::columns([
+                    UserProfaneWordStatsColumn::UserId,
+                    UserProfaneWordStatsColumn::Word,
+                ])
+                .value(
+                    UserProfaneWordStatsColumn::Occurrences,
+                    Expr::col((
+                        UserProfaneWordStatsEntity,
+                        UserProfaneWordStatsColumn::Occurrences,
+                    ))
+                    .add(Expr::col((
+                        Alias::new("excluded"),
+                        UserProfaneWordStatsColumn::Occurrences,
+                    ))),
+                )
+                .update_column(UserProfaneWordStatsColumn::UpdatedAt)
+                .to_owned(),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id, %limit))]
+    pub async fn top_for_user(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        limit: u64,
+    ) -> anyhow::Result<Vec<(String, i32)>> {
+        let res = UserProfaneWordStatsEntity::find()
+            .select_only()
+            .column(UserProfaneWordStatsColumn::Word)
+            .column(UserProfaneWordStatsColumn::Occurrences)
+            .filter(UserProfaneWordStatsColumn::UserId.eq(user_id))
+            .order_by_desc(UserProfaneWordStatsColumn::Occurrences)
+            .order_by_asc(UserProfaneWordStatsColumn::Word)
+            .limit(limit)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(res)
+    }
+}
@@ -9,6 +9,9 @@ use crate::entity::prelude::*;
 pub struct UserWordWhitelistService;
 
 impl UserWordWhitelistService {
+    pub const MAX_WORDS: u64 = 20;
+    pub const MAX_WORD_LENGTH: usize = 16;
+
     #[tracing::instrument(skip_all, fields(%user_id))]
     pub async fn get_ok_words_for_user(
         db: &impl ConnectionTrait,
@@ -0,0 +1,85 @@
+use chrono::Duration;
+use deadpool_redis::redis::AsyncCommands as _;
+use rand::RngExt as _;
+use rand::distr::Alphanumeric;
+
+/// Browser sessions for pages served by HTTP server. User gets one-time login link from the bot,
+/// which is exchanged for session cookie on the first visit
+pub struct WebSessionService;
+
+impl WebSessionService {
+    pub const COOKIE_NAME: &str = "rustify_session";
+
+    #[must_use]
+    pub fn login_token_ttl() -> Duration {
+        Duration::minutes(10)
+    }
+
+    #[must_use]
+    pub fn session_ttl() -> Duration {
+        Duration::hours(1)
+    }
+
+    fn generate_token() -> String {
+        rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(48)
+            .map(char::from)
+            .collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn create_login_token(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+    ) -> anyhow::Result<String> {
+        let token = Self::generate_token();
+        let key = format!("rustify:web:login:{token}");
+
+        let _: () = redis_conn
+            .set_ex(key, user_id, Self::login_token_ttl().num_seconds() as u64)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Returns user id of the login token. Token is removed, so it works only once
+    #[tracing::instrument(skip_all)]
+    pub async fn consume_login_token(
+        redis_conn: &mut deadpool_redis::Connection,
+        token: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let key = format!("rustify:web:login:{token}");
+
+        let user_id: Option<String> = redis_conn.get_del(key).await?;
+
+        Ok(user_id)
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn create_session(
+        redis_conn: &mut deadpool_redis::Connection,
+        user_id: &str,
+    ) -> anyhow::Result<String> {
+        let session = Self::generate_token();
+        let key = format!("rustify:web:session:{session}");
+
+        let _: () = redis_conn
+            .set_ex(key, user_id, Self::session_ttl().num_seconds() as u64)
+            .await?;
+
+        Ok(session)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_session_user(
+        redis_conn: &mut deadpool_redis::Connection,
+        session: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let key = format!("rustify:web:session:{session}");
+
+        let user_id: Option<String> = redis_conn.get(key).await?;
+
+        Ok(user_id)
+    }
+}
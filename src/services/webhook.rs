@@ -0,0 +1,449 @@
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use anyhow::Context as _;
+use hmac::{Hmac, Mac as _};
+use rand::RngExt as _;
+use rand::distr::Alphanumeric;
+use sea_orm::prelude::*;
+use sea_orm::sea_query::{Condition, OnConflict};
+use sea_orm::{ConnectionTrait, QueryOrder as _, Set};
+use sha2::Sha256;
+use strum::IntoEnumIterator as _;
+use strum_macros::EnumIter;
+
+use crate::entity::prelude::*;
+use crate::utils::Clock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+pub enum WebhookEvent {
+    #[serde(rename = "track_disliked")]
+    TrackDisliked,
+    #[serde(rename = "profane_track_detected")]
+    ProfaneTrackDetected,
+    #[serde(rename = "ai_slop_detected")]
+    AISlopDetected,
+    #[serde(rename = "track_auto_skipped")]
+    TrackAutoSkipped,
+    #[serde(rename = "user_status_changed")]
+    UserStatusChanged,
+}
+
+impl WebhookEvent {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TrackDisliked => "track_disliked",
+            Self::ProfaneTrackDetected => "profane_track_detected",
+            Self::AISlopDetected => "ai_slop_detected",
+            Self::TrackAutoSkipped => "track_auto_skipped",
+            Self::UserStatusChanged => "user_status_changed",
+        }
+    }
+
+    /// Parses comma separated list of events. Empty string means all events
+    pub fn parse_list(events: &str) -> anyhow::Result<Vec<Self>> {
+        events
+            .split(',')
+            .map(str::trim)
+            .filter(|event| !event.is_empty())
+            .map(Self::from_str)
+            .collect()
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::iter()
+            .find(|event| event.as_str() == s)
+            .with_context(|| format!("Unknown webhook event {s}"))
+    }
+}
+
+impl std::fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub event: WebhookEvent,
+    pub user_id: String,
+    pub timestamp: i64,
+    pub data: serde_json::Value,
+}
+
+impl WebhookPayload {
+    #[must_use]
+    pub fn new(event: WebhookEvent, user_id: &str, data: serde_json::Value) -> Self {
+        Self {
+            event,
+            user_id: user_id.to_owned(),
+            timestamp: Clock::now().and_utc().timestamp(),
+            data,
+        }
+    }
+}
+
+pub struct WebhookService {
+    client: reqwest::Client,
+}
+
+impl WebhookService {
+    pub const EVENT_HEADER: &str = "X-Rustify-Event";
+    pub const MAX_PER_USER: u64 = 5;
+    pub const SIGNATURE_HEADER: &str = "X-Rustify-Signature";
+
+    #[must_use]
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Sends payload to webhook. Non-2xx response is an error to make queue retry delivery
+    #[tracing::instrument(skip_all, fields(webhook_id = webhook.id, event = %payload.event))]
+    pub async fn deliver(
+        &self,
+        webhook: &WebhookModel,
+        payload: &WebhookPayload,
+    ) -> anyhow::Result<()> {
+        // DNS records could have been changed since the webhook was added
+        Self::validate_url(&webhook.url).await?;
+
+        let body = serde_json::to_vec(payload)?;
+        let signature = Self::sign(&webhook.secret, &body)?;
+
+        self.client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(Self::EVENT_HEADER, payload.event.as_str())
+            .header(Self::SIGNATURE_HEADER, format!("sha256={signature}"))
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// HMAC-SHA256 signature of the request body in hex
+    pub fn sign(secret: &str, body: &[u8]) -> anyhow::Result<String> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+        mac.update(body);
+
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[must_use]
+    pub fn generate_secret() -> String {
+        rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect()
+    }
+
+    /// Allows only http(s) URLs pointing to public addresses, so webhooks can't reach internal services
+    pub async fn validate_url(url: &str) -> anyhow::Result<url::Url> {
+        let url = url::Url::parse(url)?;
+
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("Only http and https webhooks are supported");
+        }
+
+        match url.host() {
+            Some(url::Host::Ipv4(ip)) => check_public_ip(ip.into())?,
+            Some(url::Host::Ipv6(ip)) => check_public_ip(ip.into())?,
+            Some(url::Host::Domain(domain)) => {
+                resolve_public(domain, url.port_or_known_default().unwrap_or_default()).await?;
+            },
+            None => anyhow::bail!("Webhook URL has no host"),
+        }
+
+        Ok(url)
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn add(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        url: &str,
+        events: &[WebhookEvent],
+        is_global: bool,
+    ) -> anyhow::Result<WebhookModel> {
+        let events = events.iter().map(WebhookEvent::as_str).collect::<Vec<_>>();
+
+        let model = WebhookActiveModel {
+            user_id: Set(user_id.to_owned()),
+            url: Set(url.to_owned()),
+            secret: Set(Self::generate_secret()),
+            events: Set(events.join(",")),
+            is_global: Set(is_global),
+            ..Default::default()
+        };
+
+        let res = WebhookEntity::insert(model)
+            .on_conflict(
+                OnConflict::columns([WebhookColumn::UserId, WebhookColumn::Url])
+                    .update_columns([
+                        WebhookColumn::Secret,
+                        WebhookColumn::Events,
+                        WebhookColumn::IsGlobal,
+                    ])
+                    .value(WebhookColumn::UpdatedAt, Clock::now())
+                    .to_owned(),
+            )
+            .exec_with_returning(db)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id, %id))]
+    pub async fn remove(db: &impl ConnectionTrait, user_id: &str, id: i32) -> anyhow::Result<bool> {
+        let res = WebhookEntity::delete_many()
+            .filter(WebhookColumn::UserId.eq(user_id))
+            .filter(WebhookColumn::Id.eq(id))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    #[tracing::instrument(skip_all, fields(%id))]
+    pub async fn get_by_id(
+        db: &impl ConnectionTrait,
+        id: i32,
+    ) -> anyhow::Result<Option<WebhookModel>> {
+        Ok(WebhookEntity::find_by_id(id).one(db).await?)
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn list_for_user(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+    ) -> anyhow::Result<Vec<WebhookModel>> {
+        let res = WebhookEntity::find()
+            .filter(WebhookColumn::UserId.eq(user_id))
+            .order_by_asc(WebhookColumn::Id)
+            .all(db)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[tracing::instrument(skip_all, fields(%user_id))]
+    pub async fn count_for_user(db: &impl ConnectionTrait, user_id: &str) -> anyhow::Result<u64> {
+        let res = WebhookEntity::find()
+            .filter(WebhookColumn::UserId.eq(user_id))
+            .count(db)
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Returns user's own webhooks and global ones, subscribed to the event
+    #[tracing::instrument(skip_all, fields(%user_id, %event))]
+    pub async fn get_subscribed(
+        db: &impl ConnectionTrait,
+        user_id: &str,
+        event: WebhookEvent,
+    ) -> anyhow::Result<Vec<WebhookModel>> {
+        let webhooks: Vec<WebhookModel> = WebhookEntity::find()
+            .filter(
+                Condition::any()
+                    .add(WebhookColumn::UserId.eq(user_id))
+                    .add(WebhookColumn::IsGlobal.eq(true)),
+            )
+            .all(db)
+            .await?;
+
+        let webhooks = webhooks
+            .into_iter()
+            .filter(|webhook| match WebhookEvent::parse_list(&webhook.events) {
+                Ok(events) => events.is_empty() || events.contains(&event),
+                Err(err) => {
+                    tracing::warn!(err = ?err, webhook_id = webhook.id, "Webhook has broken events list");
+
+                    false
+                },
+            })
+            .collect();
+
+        Ok(webhooks)
+    }
+}
+
+/// Loopback, private, link-local and unspecified addresses belong to the internal network
+#[must_use]
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Shared address space (CGNAT) 100.64.0.0/10
+                || (a == 100 && b & 0xc0 == 64)
+                // IETF protocol assignments 192.0.0.0/24
+                || (a == 192 && b == 0 && c == 0)
+                // Benchmarking 198.18.0.0/15
+                || (a == 198 && b & 0xfe == 18))
+        },
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or_else(
+            || {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            },
+            |ip| is_public_ip(ip.into()),
+        ),
+    }
+}
+
+fn check_public_ip(ip: IpAddr) -> anyhow::Result<()> {
+    if !is_public_ip(ip) {
+        anyhow::bail!("Webhook address {ip} is not public");
+    }
+
+    Ok(())
+}
+
+/// Resolves host and fails if any of its addresses is not public
+async fn resolve_public(host: &str, port: u16) -> anyhow::Result<Vec<SocketAddr>> {
+    let addrs: Vec<_> = tokio::net::lookup_host((host, port))
+        .await
+        .with_context(|| format!("Cannot resolve webhook host {host}"))?
+        .collect();
+
+    if addrs.is_empty() {
+        anyhow::bail!("Webhook host {host} has no addresses");
+    }
+
+    for addr in &addrs {
+        check_public_ip(addr.ip())?;
+    }
+
+    Ok(addrs)
+}
+
+/// DNS resolver of webhook HTTP client, checks addresses right before connecting to them
+pub struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_owned();
+
+        Box::pin(async move {
+            let addrs = resolve_public(&host, 0).await?;
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events_list() {
+        assert_eq!(WebhookEvent::parse_list("").unwrap(), vec![]);
+        assert_eq!(
+            WebhookEvent::parse_list("track_disliked, ai_slop_detected").unwrap(),
+            vec![WebhookEvent::TrackDisliked, WebhookEvent::AISlopDetected]
+        );
+        assert!(WebhookEvent::parse_list("track_disliked,unknown").is_err());
+    }
+
+    #[test]
+    fn test_event_serde_matches_str() {
+        for event in WebhookEvent::iter() {
+            let serialized = serde_json::to_string(&event).unwrap();
+
+            assert_eq!(serialized, format!("\"{}\"", event.as_str()));
+            assert_eq!(event.as_str().parse::<WebhookEvent>().unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        // echo -n '{"a":1}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            WebhookService::sign("secret", br#"{"a":1}"#).unwrap(),
+            "aa9e2e3575f5d7098b6caccd790888c36d5fdb63342a73bada2d6a51747a8494"
+        );
+    }
+
+    #[test]
+    fn test_generate_secret() {
+        let secret = WebhookService::generate_secret();
+
+        assert_eq!(secret.len(), 32);
+        assert!(secret.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(secret, WebhookService::generate_secret());
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "100.64.0.1",
+            "100.127.255.254",
+            "192.0.0.8",
+            "198.18.0.1",
+            "198.19.255.255",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+
+        for ip in [
+            "1.1.1.1",
+            "93.184.216.34",
+            "100.128.0.1",
+            "198.20.0.1",
+            "2606:4700:4700::1111",
+        ] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_url() {
+        assert!(
+            WebhookService::validate_url("https://1.1.1.1/hook")
+                .await
+                .is_ok()
+        );
+
+        for url in [
+            "ftp://example.com/hook",
+            "http://127.0.0.1:8080/hook",
+            "http://[::1]/hook",
+            "http://10.0.0.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://0.0.0.0/hook",
+            "http://localhost/hook",
+        ] {
+            assert!(WebhookService::validate_url(url).await.is_err(), "{url}");
+        }
+    }
+}
@@ -5,6 +5,7 @@ use async_openai::types::chat::{
     CreateChatCompletionRequestArgs,
 };
 use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::OnConflict;
 use sea_orm::{
     ColumnTrait as _,
     ConnectionTrait,
@@ -14,37 +15,52 @@ use sea_orm::{
     QuerySelect as _,
 };
 
-use crate::app::AIConfig;
+use crate::ai::{AIConfig, AIFeature};
+use crate::app::App;
 use crate::entity::prelude::{
     WordDefinitionActiveModel,
     WordDefinitionColumn,
     WordDefinitionEntity,
 };
+use crate::services::{AIQuotaOutput, AIUsageService};
+use crate::utils::Clock;
 
 pub struct WordDefinitionService {}
 
 impl WordDefinitionService {
-    #[tracing::instrument(skip_all, fields(%locale, %profane_word))]
+    /// Generated definitions are shared, tokens are counted for the user who requested it first.
+    /// Returns `None` if definition isn't generated yet and user's AI quota is used up
+    #[tracing::instrument(skip_all, fields(%user_id, %locale, %profane_word))]
     pub async fn get_definition(
-        db: &impl ConnectionTrait,
+        app: &App,
+        user_id: &str,
         locale: &str,
         config: &AIConfig,
         profane_word: &str,
-    ) -> anyhow::Result<String> {
-        let definition: Option<String> = WordDefinitionEntity::find()
-            .select_only()
-            .column(WordDefinitionColumn::Definition)
-            .filter(WordDefinitionColumn::Word.eq(profane_word))
-            .filter(WordDefinitionColumn::Locale.eq(locale))
-            .into_tuple()
-            .one(db)
-            .await?;
+    ) -> anyhow::Result<Option<String>> {
+        let db = app.db();
 
-        if let Some(definition) = definition {
-            return Ok(definition);
+        if let Some(definition) = Self::find_definition(db, locale, profane_word).await? {
+            return Ok(Some(definition));
         }
 
-        let definition = Self::get_definition_internal(config, locale, profane_word).await?;
+        let AIQuotaOutput::Allowed(reservation) = AIUsageService::reserve(
+            db,
+            &mut app.redis_conn().await?,
+            config,
+            user_id,
+            AIFeature::WordDefinition,
+        )
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let res = Self::get_definition_internal(db, user_id, config, locale, profane_word).await;
+
+        AIUsageService::release(&mut app.redis_conn().await?, reservation).await?;
+
+        let definition = res?;
 
         let model = WordDefinitionActiveModel {
             word: Set(profane_word.into()),
@@ -55,11 +71,13 @@ impl WordDefinitionService {
 
         WordDefinitionEntity::insert(model).exec(db).await?;
 
-        Ok(definition)
+        Ok(Some(definition))
     }
 
-    #[tracing::instrument(skip_all, fields(%locale, %profane_word))]
-    pub async fn get_definition_internal(
+    #[tracing::instrument(skip_all, fields(%user_id, %locale, %profane_word))]
+    async fn get_definition_internal(
+        db: &impl ConnectionTrait,
+        user_id: &str,
         config: &AIConfig,
         locale: &str,
         profane_word: &str,
@@ -71,7 +89,6 @@ impl WordDefinitionService {
         );
 
         let req = CreateChatCompletionRequestArgs::default()
-            .model(config.model())
             // .temperature(0.5)
             .messages([
                 ChatCompletionRequestSystemMessage::from("You are a helpful assistant.").into(),
@@ -79,17 +96,24 @@ impl WordDefinitionService {
             ])
             .build()?;
 
-        let res = config
-            .openai_client()
-            .chat()
-            .create(req)
-            .await?
+        let (target, response) = config.chat(AIFeature::WordDefinition, req).await?;
+
+        AIUsageService::record(
+            db,
+            user_id,
+            AIFeature::WordDefinition,
+            target,
+            response.usage.as_ref(),
+        )
+        .await?;
+
+        let res = response
             .choices
             .first()
             .context("I need at least one choice")?
             .message
-            .clone()
             .content
+            .clone()
             .context("I need message content")?;
 
         Ok(res)
@@ -109,4 +133,97 @@ impl WordDefinitionService {
 
         Ok(result)
     }
+
+    #[tracing::instrument(skip_all, fields(%locale, %profane_word))]
+    pub async fn find_definition(
+        db: &impl ConnectionTrait,
+        locale: &str,
+        profane_word: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let definition = WordDefinitionEntity::find()
+            .select_only()
+            .column(WordDefinitionColumn::Definition)
+            .filter(WordDefinitionColumn::Word.eq(profane_word))
+            .filter(WordDefinitionColumn::Locale.eq(locale))
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(definition)
+    }
+
+    /// Stores manually written definition, replacing generated one
+    #[tracing::instrument(skip_all, fields(%locale, %profane_word))]
+    pub async fn set_definition(
+        db: &impl ConnectionTrait,
+        locale: &str,
+        profane_word: &str,
+        definition: &str,
+    ) -> anyhow::Result<()> {
+        let model = WordDefinitionActiveModel {
+            word: Set(profane_word.into()),
+            definition: Set(definition.into()),
+            locale: Set(locale.into()),
+            updated_at: Set(Clock::now()),
+            ..Default::default()
+        };
+
+        WordDefinitionEntity::insert(model)
+            .on_conflict(
+                OnConflict::columns([WordDefinitionColumn::Word, WordDefinitionColumn::Locale])
+                    .update_columns([
+                        WordDefinitionColumn::Definition,
+                        WordDefinitionColumn::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::ai::Backend;
+    use crate::testing::TestApp;
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_get_definition_quota_exceeded() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+
+        let config = AIConfig::new(
+            vec![Backend::new("test", async_openai::Client::new())],
+            &HashMap::new(),
+            "test:model",
+            1,
+        )
+        .unwrap();
+
+        // Another request in flight takes the whole quota
+        let reserved = AIUsageService::reserve(
+            test.app.db(),
+            &mut test.app.redis_conn().await.unwrap(),
+            &config,
+            &user_id,
+            AIFeature::Analysis,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(reserved, AIQuotaOutput::Allowed(_)));
+
+        let definition =
+            WordDefinitionService::get_definition(test.app, &user_id, "en", &config, &user_id)
+                .await
+                .unwrap();
+
+        assert_eq!(definition, None);
+    }
 }
@@ -13,6 +13,7 @@ use sea_orm::{
 
 use crate::entity::prelude::*;
 use crate::services::UserService;
+use crate::spotify::scopes;
 use crate::utils::Clock;
 
 pub struct SpotifyAuthService;
@@ -46,6 +47,11 @@ impl SpotifyAuthService {
             Set(token.refresh_token.context("Refresh token is required")?);
         spotify_auth.expires_at = Set(token.expires_at.map(|item| item.naive_utc()));
 
+        // Refresh responses may omit scopes, previously granted ones stay then
+        if !token.scopes.is_empty() {
+            spotify_auth.scopes = Set(scopes::serialize(&token.scopes));
+        }
+
         Ok(spotify_auth.save(db).await?)
     }
 
@@ -66,6 +72,7 @@ impl SpotifyAuthService {
             access_token: spotify_auth.access_token,
             refresh_token: Some(spotify_auth.refresh_token),
             expires_at: spotify_auth.expires_at.map(|item| item.and_utc()),
+            scopes: scopes::deserialize(&spotify_auth.scopes),
             ..Default::default()
         }))
     }
@@ -0,0 +1,709 @@
+//! In-process Spotify Web API for tests.
+//!
+//! Implements endpoints used by Rustify with state which tests can script and inspect.
+//! Point [`super::Manager`] at it with `SPOTIFY_API_URL` and `SPOTIFY_AUTH_URL`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Query, State};
+use axum::http::{Method, StatusCode, Uri};
+use axum::response::{IntoResponse as _, Response};
+use axum::routing::any;
+use chrono::{Duration, Utc};
+use rspotify::model::{
+    ArtistId,
+    FullTrack,
+    Id as _,
+    SimplifiedAlbum,
+    SimplifiedArtist,
+    TrackId,
+    Type as SpotifyType,
+};
+use serde_json::{Value, json};
+
+pub const ACCESS_TOKEN: &str = "mock-access-token";
+pub const REFRESH_TOKEN: &str = "mock-refresh-token";
+pub const USER_ID: &str = "mock_user";
+
+pub struct MockPlaying {
+    pub track_id: String,
+    pub progress: Duration,
+    pub is_playing: bool,
+    /// Context type and uri, e.g. playlist the track is played from
+    pub context: Option<(SpotifyType, String)>,
+}
+
+pub struct MockPlaylist {
+    pub name: String,
+    pub track_ids: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct MockState {
+    pub premium: bool,
+    pub tracks: HashMap<String, FullTrack>,
+    pub playing: Option<MockPlaying>,
+    /// Tracks started by "next" request
+    pub queue: VecDeque<String>,
+    pub saved_tracks: Vec<String>,
+    pub playlists: HashMap<String, MockPlaylist>,
+    /// Refresh requests fail with `invalid_grant` when set
+    pub revoked: bool,
+    /// `METHOD /path` of every received request
+    pub requests: Vec<String>,
+}
+
+impl MockState {
+    pub fn add_track(&mut self, track: FullTrack) {
+        let id = track
+            .id
+            .as_ref()
+            .expect("Mock tracks have ids")
+            .id()
+            .to_owned();
+
+        self.tracks.insert(id, track);
+    }
+
+    pub fn play(&mut self, track_id: &str, progress: Duration) {
+        self.playing = Some(MockPlaying {
+            track_id: track_id.to_owned(),
+            progress,
+            is_playing: true,
+            context: None,
+        });
+    }
+
+    #[must_use]
+    pub fn requested(&self, request: &str) -> usize {
+        self.requests.iter().filter(|r| *r == request).count()
+    }
+}
+
+pub struct MockSpotify {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockSpotify {
+    /// Starts server on a random local port
+    pub async fn start() -> anyhow::Result<Self> {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let router = Router::new()
+            .fallback(any(handle))
+            .with_state(state.clone());
+
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        Ok(Self { url, state })
+    }
+
+    #[must_use]
+    pub fn api_url(&self) -> String {
+        format!("{}/v1/", self.url)
+    }
+
+    #[must_use]
+    pub fn auth_url(&self) -> String {
+        format!("{}/", self.url)
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().expect("Mock state lock")
+    }
+}
+
+/// Track with one artist, id is used to make names
+#[must_use]
+pub fn track(id: &str, duration_secs: i64) -> FullTrack {
+    let artist = SimplifiedArtist {
+        external_urls: HashMap::from([(
+            "spotify".to_owned(),
+            format!("https://open.spotify.com/artist/{id}"),
+        )]),
+        href: None,
+        id: Some(ArtistId::from_id(id.to_owned()).expect("Valid artist id")),
+        name: format!("Artist {id}"),
+    };
+
+    FullTrack {
+        album: SimplifiedAlbum {
+            album_group: None,
+            album_type: Some("album".to_owned()),
+            artists: vec![artist.clone()],
+            available_markets: vec![],
+            external_urls: HashMap::from([(
+                "spotify".to_owned(),
+                format!("https://open.spotify.com/album/{id}"),
+            )]),
+            href: None,
+            id: None,
+            images: vec![],
+            name: format!("Album {id}"),
+            release_date: Some("2020-01-01".to_owned()),
+            release_date_precision: Some("day".to_owned()),
+            restrictions: None,
+        },
+        artists: vec![artist],
+        available_markets: vec![],
+        disc_number: 1,
+        duration: Duration::seconds(duration_secs),
+        explicit: false,
+        external_ids: HashMap::new(),
+        external_urls: HashMap::from([(
+            "spotify".to_owned(),
+            format!("https://open.spotify.com/track/{id}"),
+        )]),
+        href: None,
+        id: Some(TrackId::from_id(id.to_owned()).expect("Valid track id")),
+        is_local: false,
+        is_playable: Some(true),
+        linked_from: None,
+        restrictions: None,
+        name: format!("Track {id}"),
+        popularity: 50,
+        preview_url: None,
+        track_number: 1,
+        r#type: SpotifyType::Track,
+    }
+}
+
+fn json_response(status: StatusCode, value: Value) -> Response {
+    (status, axum::Json(value)).into_response()
+}
+
+fn not_found() -> Response {
+    json_response(
+        StatusCode::NOT_FOUND,
+        json!({ "error": { "status": 404, "message": "Not found" } }),
+    )
+}
+
+fn snapshot() -> Response {
+    json_response(StatusCode::OK, json!({ "snapshot_id": "mock-snapshot" }))
+}
+
+fn ids_param(params: &HashMap<String, String>) -> Vec<String> {
+    params
+        .get("ids")
+        .map(|ids| ids.split(',').map(str::to_owned).collect())
+        .unwrap_or_default()
+}
+
+/// `spotify:track:<id>` to `<id>`
+fn uri_id(uri: &str) -> String {
+    uri.rsplit(':').next().unwrap_or_default().to_owned()
+}
+
+fn page(items: Vec<Value>, params: &HashMap<String, String>) -> Value {
+    let limit: usize = params
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(20);
+    let offset: usize = params
+        .get("offset")
+        .and_then(|offset| offset.parse().ok())
+        .unwrap_or(0);
+    let total = items.len();
+
+    json!({
+        "href": "",
+        "items": items.into_iter().skip(offset).take(limit).collect::<Vec<_>>(),
+        "limit": limit,
+        "next": (offset + limit < total).then_some(""),
+        "offset": offset,
+        "previous": null,
+        "total": total,
+    })
+}
+
+/// Simplified playlist, full one when `items` are given
+fn playlist_json(id: &str, playlist: &MockPlaylist, items: Option<Vec<Value>>) -> Value {
+    let tracks = items.map_or_else(
+        || json!({ "href": "", "total": playlist.track_ids.len() }),
+        |items| page(items, &HashMap::new()),
+    );
+
+    json!({
+        "collaborative": false,
+        "description": null,
+        "external_urls": { "spotify": format!("https://open.spotify.com/playlist/{id}") },
+        "followers": { "total": 0 },
+        "href": "",
+        "id": id,
+        "images": [],
+        "name": playlist.name,
+        "owner": { "external_urls": {}, "href": "", "id": USER_ID },
+        "public": false,
+        "snapshot_id": "mock-snapshot",
+        "tracks": tracks,
+    })
+}
+
+fn currently_playing(state: &MockState) -> Response {
+    let Some(playing) = &state.playing else {
+        return StatusCode::NO_CONTENT.into_response();
+    };
+
+    let Some(track) = state.tracks.get(&playing.track_id) else {
+        return not_found();
+    };
+
+    let context = playing
+        .context
+        .as_ref()
+        .map(|(kind, uri)| json!({ "uri": uri, "href": "", "external_urls": {}, "type": kind }));
+
+    json_response(
+        StatusCode::OK,
+        json!({
+            "context": context,
+            "timestamp": Utc::now().timestamp_millis(),
+            "progress_ms": playing.progress.num_milliseconds(),
+            "is_playing": playing.is_playing,
+            "item": track,
+            "currently_playing_type": "track",
+            "actions": { "disallows": {} },
+        }),
+    )
+}
+
+fn token(state: &MockState, form: &HashMap<String, String>) -> Response {
+    if state.revoked || form.get("refresh_token").map(String::as_str) != Some(REFRESH_TOKEN) {
+        return json_response(
+            StatusCode::BAD_REQUEST,
+            json!({ "error": "invalid_grant", "error_description": "Refresh token revoked" }),
+        );
+    }
+
+    json_response(
+        StatusCode::OK,
+        json!({
+            "access_token": ACCESS_TOKEN,
+            "token_type": "Bearer",
+            "expires_in": 3600,
+            "scope": super::scopes::serialize(&super::scopes::scopes_for(&[])),
+        }),
+    )
+}
+
+async fn handle(
+    State(state): State<Arc<Mutex<MockState>>>,
+    method: Method,
+    uri: Uri,
+    Query(params): Query<HashMap<String, String>>,
+    body: Bytes,
+) -> Response {
+    respond(
+        &mut state.lock().expect("Mock state lock"),
+        method,
+        uri.path().trim_end_matches('/'),
+        &params,
+        &body,
+    )
+}
+
+#[allow(clippy::too_many_lines)]
+fn respond(
+    state: &mut MockState,
+    method: Method,
+    path: &str,
+    params: &HashMap<String, String>,
+    body: &Bytes,
+) -> Response {
+    state.requests.push(format!("{method} {path}"));
+
+    let json: Value = serde_json::from_slice(body).unwrap_or_default();
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (Method::POST, ["api", "token"]) => {
+            let form = url::form_urlencoded::parse(body).into_owned().collect();
+
+            token(state, &form)
+        },
+        (Method::GET, ["v1", "me"]) => json_response(
+            StatusCode::OK,
+            json!({
+                "display_name": "Mock",
+                "external_urls": {},
+                "href": "",
+                "id": USER_ID,
+                "product": if state.premium { "premium" } else { "free" },
+            }),
+        ),
+        (Method::GET, ["v1", "me", "player", "currently-playing"]) => currently_playing(state),
+        (Method::POST, ["v1", "me", "player", "next"]) => {
+            state.playing = state.queue.pop_front().map(|track_id| MockPlaying {
+                track_id,
+                progress: Duration::zero(),
+                is_playing: true,
+                context: None,
+            });
+
+            StatusCode::NO_CONTENT.into_response()
+        },
+        (Method::POST, ["v1", "me", "player", "queue"]) => {
+            if let Some(uri) = params.get("uri") {
+                state.queue.push_back(uri_id(uri));
+            }
+
+            StatusCode::NO_CONTENT.into_response()
+        },
+        (Method::GET, ["v1", "me", "tracks"]) => {
+            let items = state
+                .saved_tracks
+                .iter()
+                .filter_map(|id| state.tracks.get(id))
+                .map(|track| json!({ "added_at": Utc::now(), "track": track }))
+                .collect();
+
+            json_response(StatusCode::OK, page(items, params))
+        },
+        (Method::PUT, ["v1", "me", "tracks"]) => {
+            for id in ids_param(params) {
+                if !state.saved_tracks.contains(&id) {
+                    state.saved_tracks.insert(0, id);
+                }
+            }
+
+            StatusCode::OK.into_response()
+        },
+        (Method::DELETE, ["v1", "me", "tracks"]) => {
+            let ids = ids_param(params);
+
+            state.saved_tracks.retain(|id| !ids.contains(id));
+
+            StatusCode::OK.into_response()
+        },
+        (Method::GET, ["v1", "tracks", id]) => {
+            state.tracks.get(*id).map_or_else(not_found, |track| {
+                json_response(StatusCode::OK, json!(track))
+            })
+        },
+        (Method::GET, ["v1", "artists"]) => {
+            let artists: Vec<Value> = ids_param(params)
+                .into_iter()
+                .map(|id| {
+                    json!({
+                        "external_urls": {},
+                        "followers": { "total": 0 },
+                        "genres": [],
+                        "href": "",
+                        "id": id,
+                        "images": [],
+                        "name": format!("Artist {id}"),
+                        "popularity": 50,
+                    })
+                })
+                .collect();
+
+            json_response(StatusCode::OK, json!({ "artists": artists }))
+        },
+        (Method::GET, ["v1", "users", _, "playlists"]) => {
+            let items = state
+                .playlists
+                .iter()
+                .map(|(id, playlist)| playlist_json(id, playlist, None))
+                .collect();
+
+            json_response(StatusCode::OK, page(items, params))
+        },
+        (Method::POST, ["v1", "users", _, "playlists"]) => {
+            let id = format!("{:0>22}", state.playlists.len() + 1);
+            let playlist = MockPlaylist {
+                name: json["name"].as_str().unwrap_or_default().to_owned(),
+                track_ids: vec![],
+            };
+            let json = playlist_json(&id, &playlist, Some(vec![]));
+
+            state.playlists.insert(id, playlist);
+
+            json_response(StatusCode::CREATED, json)
+        },
+        (method, ["v1", "playlists", id, "tracks"]) => {
+            let Some(playlist) = state.playlists.get_mut(*id) else {
+                return not_found();
+            };
+
+            let uris = |key: &str| -> Vec<String> {
+                json[key]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|item| item.as_str().or_else(|| item["uri"].as_str()))
+                    .map(uri_id)
+                    .collect()
+            };
+
+            match method {
+                Method::POST => playlist.track_ids.extend(uris("uris")),
+                Method::PUT => playlist.track_ids = uris("uris"),
+                Method::DELETE => {
+                    let removed = uris("tracks");
+
+                    playlist.track_ids.retain(|id| !removed.contains(id));
+                },
+                _ => return not_found(),
+            }
+
+            snapshot()
+        },
+        _ => not_found(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rspotify::model::{PlaylistId, Token, UserId};
+    use rspotify::prelude::{BaseClient, OAuthClient as _};
+    use rspotify::{AuthCodeSpotify, ClientError};
+
+    use super::*;
+    use crate::spotify::{
+        CurrentlyPlaying,
+        CurrentlyPlayingNoneReason,
+        Manager,
+        PAGE_LIMIT,
+        ShortTrack,
+        SpotifyWrapper,
+    };
+
+    const TRACK_ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+    const NEXT_TRACK_ID: &str = "7GhIk7Il098yCjg4BQjzvb";
+
+    async fn client(mock: &MockSpotify) -> SpotifyWrapper<Box<AuthCodeSpotify>> {
+        let manager = Manager::new(
+            "client-id",
+            "client-secret",
+            None,
+            Some(mock.api_url()),
+            Some(mock.auth_url()),
+        );
+
+        let spotify = manager.spotify.clone();
+
+        *spotify.token.lock().await.expect("Token lock") = Some(Token {
+            access_token: ACCESS_TOKEN.to_owned(),
+            expires_in: Duration::hours(1),
+            expires_at: Some(Utc::now() + Duration::hours(1)),
+            refresh_token: Some(REFRESH_TOKEN.to_owned()),
+            ..Default::default()
+        });
+
+        SpotifyWrapper::new(Box::new(spotify))
+    }
+
+    #[test]
+    fn test_manager_ignores_empty_urls() {
+        let manager = Manager::new(
+            "client-id",
+            "client-secret",
+            None,
+            Some(String::new()),
+            Some(" ".to_owned()),
+        );
+        let default = rspotify::Config::default();
+
+        assert_eq!(manager.spotify.config.api_base_url, default.api_base_url);
+        assert_eq!(manager.spotify.config.auth_base_url, default.auth_base_url);
+    }
+
+    #[tokio::test]
+    async fn test_currently_playing() {
+        let mock = MockSpotify::start().await.unwrap();
+        let spotify = client(&mock).await;
+
+        assert!(matches!(
+            spotify.current_playing_wrapped().await,
+            CurrentlyPlaying::None(CurrentlyPlayingNoneReason::Nothing)
+        ));
+
+        mock.state().add_track(track(TRACK_ID, 200));
+        mock.state().play(TRACK_ID, Duration::seconds(150));
+
+        let CurrentlyPlaying::Ok(track, _, progress) = spotify.current_playing_wrapped().await
+        else {
+            panic!("Track should be playing");
+        };
+
+        assert_eq!(track.id(), TRACK_ID);
+        assert_eq!(track.duration_secs(), 200);
+        assert_eq!(progress, Some(Duration::seconds(150)));
+
+        mock.state().playing.as_mut().unwrap().is_playing = false;
+
+        assert!(matches!(
+            spotify.current_playing_wrapped().await,
+            CurrentlyPlaying::None(CurrentlyPlayingNoneReason::Pause)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_skip_to_queued_track() {
+        let mock = MockSpotify::start().await.unwrap();
+        let spotify = client(&mock).await;
+
+        mock.state().add_track(track(TRACK_ID, 200));
+        mock.state().add_track(track(NEXT_TRACK_ID, 100));
+        mock.state().play(TRACK_ID, Duration::zero());
+
+        spotify
+            .request(|spotify| {
+                spotify.add_item_to_queue(TrackId::from_id(NEXT_TRACK_ID).unwrap().into(), None)
+            })
+            .await
+            .unwrap();
+        spotify
+            .request(|spotify| spotify.next_track(None))
+            .await
+            .unwrap();
+
+        let CurrentlyPlaying::Ok(track, ..) = spotify.current_playing_wrapped().await else {
+            panic!("Queued track should be playing");
+        };
+
+        assert_eq!(track.id(), NEXT_TRACK_ID);
+        assert_eq!(mock.state().requested("POST /v1/me/player/next"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_saved_tracks() {
+        let mock = MockSpotify::start().await.unwrap();
+        let spotify = client(&mock).await;
+        let track_id = TrackId::from_id(TRACK_ID).unwrap();
+
+        mock.state().add_track(track(TRACK_ID, 200));
+
+        spotify
+            .request(|spotify| spotify.current_user_saved_tracks_add([track_id.clone()]))
+            .await
+            .unwrap();
+
+        let saved = spotify
+            .request(|spotify| spotify.current_user_saved_tracks_manual(None, Some(10), None))
+            .await
+            .unwrap();
+
+        assert_eq!(saved.items.len(), 1);
+        assert_eq!(
+            ShortTrack::new(saved.items[0].track.clone()).name(),
+            format!("Track {TRACK_ID}")
+        );
+
+        spotify
+            .request(|spotify| spotify.current_user_saved_tracks_delete([track_id]))
+            .await
+            .unwrap();
+
+        assert!(mock.state().saved_tracks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_paginate() {
+        let mock = MockSpotify::start().await.unwrap();
+        let spotify = client(&mock).await;
+
+        for i in 0..120 {
+            let id = format!("{i:0>22}");
+
+            mock.state().add_track(track(&id, 200));
+            mock.state().saved_tracks.push(id);
+        }
+
+        let saved = spotify
+            .paginate(Some(100), |spotify, offset| {
+                spotify.current_user_saved_tracks_manual(None, Some(PAGE_LIMIT), Some(offset))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(saved.len(), 100);
+        assert_eq!(mock.state().requested("GET /v1/me/tracks"), 2);
+
+        let saved = spotify
+            .paginate(None, |spotify, offset| {
+                spotify.current_user_saved_tracks_manual(None, Some(PAGE_LIMIT), Some(offset))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(saved.len(), 120);
+        assert_eq!(
+            saved.last().unwrap().track.id.as_ref().unwrap().id(),
+            format!("{:0>22}", 119)
+        );
+        assert_eq!(mock.state().requested("GET /v1/me/tracks"), 5);
+    }
+
+    #[tokio::test]
+    async fn test_playlist_items() {
+        let mock = MockSpotify::start().await.unwrap();
+        let spotify = client(&mock).await;
+        let user_id = UserId::from_id(USER_ID).unwrap();
+
+        let playlist = spotify
+            .request(|spotify| {
+                spotify.user_playlist_create(user_id.clone(), "Magic", Some(false), None, None)
+            })
+            .await
+            .unwrap();
+
+        let items = [TRACK_ID, NEXT_TRACK_ID].map(|id| TrackId::from_id(id).unwrap().into());
+
+        spotify
+            .request(|spotify| spotify.playlist_add_items(playlist.id.clone(), items, None))
+            .await
+            .unwrap();
+        spotify
+            .request(|spotify| {
+                spotify.playlist_remove_all_occurrences_of_items(
+                    PlaylistId::from_id(playlist.id.id()).unwrap(),
+                    [TrackId::from_id(TRACK_ID).unwrap().into()],
+                    None,
+                )
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            mock.state().playlists[playlist.id.id()].track_ids,
+            vec![NEXT_TRACK_ID]
+        );
+
+        let playlists = spotify
+            .request(|spotify| spotify.user_playlists_manual(user_id, Some(10), None))
+            .await
+            .unwrap();
+
+        assert_eq!(playlists.items[0].name, "Magic");
+    }
+
+    #[tokio::test]
+    async fn test_token_refresh() {
+        let mock = MockSpotify::start().await.unwrap();
+        let spotify = client(&mock).await;
+
+        assert!(
+            Manager::is_token_valid(spotify.request(BaseClient::refresh_token).await)
+                .await
+                .unwrap()
+        );
+
+        mock.state().revoked = true;
+
+        let res = spotify.request(BaseClient::refresh_token).await;
+
+        assert!(matches!(res, Err(ClientError::Http(_))));
+        assert!(
+            !Manager::is_token_valid(res).await.unwrap(),
+            "Revoked token is invalid"
+        );
+    }
+}
@@ -1,7 +1,12 @@
 pub mod auth;
 pub mod errors;
+#[cfg(test)]
+pub mod mock;
+pub mod rate_limit;
+pub mod scopes;
 
 use std::borrow::Cow;
+use std::future::Future;
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -10,6 +15,7 @@ use auth::SpotifyAuthService;
 use chrono::{Duration, NaiveDate};
 use deadpool_redis::redis::AsyncCommands as _;
 pub use errors::SpotifyError;
+pub use rate_limit::RateLimiter;
 use rspotify::clients::{BaseClient as _, OAuthClient as _};
 use rspotify::http::HttpError;
 use rspotify::model::{
@@ -18,19 +24,35 @@ use rspotify::model::{
     FullPlaylist,
     FullTrack,
     Id,
+    Page,
     PlayableItem,
     PlaylistId,
     SimplifiedPlaylist,
     TrackId,
 };
-use rspotify::{AuthCodeSpotify, ClientError, ClientResult, Token, scopes};
+use rspotify::{AuthCodeSpotify, ClientError, ClientResult, Token};
 use sea_orm::{DbConn, TransactionTrait as _};
 use teloxide::utils::html;
 
 use crate::entity::prelude::*;
+use crate::infrastructure::error::RustifyError;
 use crate::services::UserService;
 use crate::user::UserState;
 
+/// Spotify layer returns typed errors, error response body is read once here
+pub trait ClientResultExt<T> {
+    fn typed(self) -> impl Future<Output = Result<T, RustifyError>> + Send;
+}
+
+impl<T: Send> ClientResultExt<T> for ClientResult<T> {
+    async fn typed(self) -> Result<T, RustifyError> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => Err(RustifyError::from_spotify(err).await),
+        }
+    }
+}
+
 pub struct ShortPlaylist {
     id: PlaylistId<'static>,
     url: String,
@@ -84,6 +106,9 @@ pub struct ShortTrack {
     name: String,
     url: String,
     duration_secs: i64,
+    /// Tasks queued before the field was added don't have it
+    #[serde(default)]
+    explicit: bool,
     artist_names: Vec<String>,
     artist_ids: Vec<ArtistId<'static>>,
     artist_urls: Vec<String>,
@@ -102,6 +127,7 @@ impl ShortTrack {
             name: full_track.name,
 
             duration_secs: full_track.duration.num_seconds(),
+            explicit: full_track.explicit,
 
             artist_names: full_track
                 .artists
@@ -187,6 +213,12 @@ impl ShortTrack {
         self.duration_secs
     }
 
+    /// Spotify marks track as containing explicit content
+    #[must_use]
+    pub fn is_explicit(&self) -> bool {
+        self.explicit
+    }
+
     #[must_use]
     pub fn artist_names(&self) -> Vec<&str> {
         self.artist_names.iter().map(String::as_str).collect()
@@ -289,15 +321,10 @@ impl CurrentlyPlayingNoneReason {
 }
 
 pub enum CurrentlyPlaying {
-    Err(ClientError),
+    Err(RustifyError),
     None(CurrentlyPlayingNoneReason),
-    Ok(Box<ShortTrack>, Option<SpotifyContext>),
-}
-
-impl From<ClientError> for CurrentlyPlaying {
-    fn from(err: ClientError) -> Self {
-        Self::Err(err)
-    }
+    /// Track, its context and playback progress
+    Ok(Box<ShortTrack>, Option<SpotifyContext>, Option<Duration>),
 }
 
 pub struct Manager {
@@ -310,9 +337,18 @@ impl Manager {
         spotify_id: &str,
         spotify_secret: &str,
         spotify_redirect_uri: Option<String>,
+        api_base_url: Option<String>,
+        auth_base_url: Option<String>,
     ) -> Self {
+        let default = rspotify::Config::default();
+
+        // Empty variables in env files mean default Spotify URLs
+        let non_empty = |url: Option<String>| url.filter(|url| !url.trim().is_empty());
+
         let config = rspotify::Config {
             token_refreshing: false,
+            api_base_url: non_empty(api_base_url).unwrap_or(default.api_base_url),
+            auth_base_url: non_empty(auth_base_url).unwrap_or(default.auth_base_url),
             ..Default::default()
         };
 
@@ -321,27 +357,7 @@ impl Manager {
         let oauth = rspotify::OAuth {
             redirect_uri: spotify_redirect_uri
                 .unwrap_or_else(|| "http://localhost:8080/callback".into()),
-            // TODO Reduce to minimum
-            scopes: scopes!(
-                "ugc-image-upload",
-                "user-read-playback-state",
-                "user-modify-playback-state",
-                "user-read-currently-playing",
-                "user-read-private",
-                "user-read-email",
-                "user-follow-modify",
-                "user-follow-read",
-                "user-library-modify",
-                "user-library-read",
-                "app-remote-control",
-                "user-read-playback-position",
-                "user-top-read",
-                "user-read-recently-played",
-                "playlist-modify-private",
-                "playlist-read-collaborative",
-                "playlist-read-private",
-                "playlist-modify-public"
-            ),
+            scopes: scopes::scopes_for(&[]),
             ..Default::default()
         };
 
@@ -350,6 +366,11 @@ impl Manager {
         Self { spotify }
     }
 
+    #[must_use]
+    pub fn client_id(&self) -> &str {
+        &self.spotify.creds.id
+    }
+
     async fn token_refresh(
         db: &DbConn,
         user_id: &str,
@@ -399,7 +420,7 @@ impl Manager {
         let response = match res {
             Ok(()) => return Ok(true),
             Err(ClientError::Http(box HttpError::StatusCode(ref mut response))) => response,
-            Err(err) => return Err(err.into()),
+            Err(err) => return Err(RustifyError::from_spotify(err).await.into()),
         };
 
         let err = SpotifyError::from_response(response).await?;
@@ -426,26 +447,108 @@ impl Manager {
         Ok(instance)
     }
 
-    pub async fn get_authorize_url(&self, state: &UserState) -> anyhow::Result<String> {
-        let mut spotify = state.spotify().await.clone();
+    /// Authorization URL requesting scopes for given features
+    pub async fn get_authorize_url(
+        &self,
+        state: &UserState,
+        features: &[scopes::Feature],
+    ) -> anyhow::Result<String> {
+        let mut spotify = state.spotify_unlimited().await.clone();
 
         spotify.oauth.state = state.user().spotify_state.to_string();
+        // Keep already granted scopes, new consent replaces the old one
+        let granted = state.granted_scopes().await;
+
+        spotify.oauth.scopes = scopes::scopes_for(features)
+            .into_iter()
+            .chain(granted)
+            .collect();
 
         spotify.get_authorize_url(false).context("Get auth")
     }
 }
 
+/// Max items Spotify returns in a page of saved tracks and playlists
+pub const PAGE_LIMIT: u32 = 50;
+
 pub struct SpotifyWrapper<S> {
     spotify: S,
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl<S> SpotifyWrapper<S> {
     pub fn new(spotify: S) -> Self {
-        Self { spotify }
+        Self {
+            spotify,
+            rate_limiter: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Waits for a slot in the rate budget before one more request
+    async fn acquire(&self) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
     }
 }
 
 impl<S: Deref<Target = AuthCodeSpotify>> SpotifyWrapper<S> {
+    /// Makes a single request to Spotify, it takes a slot in the rate budget.
+    /// The client isn't reachable otherwise, so no request bypasses the budget
+    pub async fn request<'a, T, F, Fut>(&'a self, request: F) -> T
+    where
+        F: FnOnce(&'a AuthCodeSpotify) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        self.acquire().await;
+
+        request(&self.spotify).await
+    }
+
+    /// Fetches pages one by one until `max` items are collected, each page takes a slot in the rate budget
+    pub async fn paginate<'a, T, F, Fut>(
+        &'a self,
+        max: Option<usize>,
+        fetch_page: F,
+    ) -> anyhow::Result<Vec<T>>
+    where
+        T: serde::de::DeserializeOwned + Send,
+        F: Fn(&'a AuthCodeSpotify, u32) -> Fut,
+        Fut: Future<Output = ClientResult<Page<T>>>,
+    {
+        let mut items = vec![];
+
+        loop {
+            let offset = u32::try_from(items.len())?;
+
+            let page = self
+                .request(|spotify| fetch_page(spotify, offset))
+                .await
+                .typed()
+                .await?;
+            let last = page.next.is_none() || page.items.is_empty();
+
+            items.extend(page.items);
+
+            if last || max.is_some_and(|max| items.len() >= max) {
+                break;
+            }
+        }
+
+        if let Some(max) = max {
+            items.truncate(max);
+        }
+
+        Ok(items)
+    }
+
+    /// Track from cache, Spotify is requested only on cache miss
     pub async fn short_track_cached(
         &self,
         redis_conn: &mut deadpool_redis::Connection,
@@ -462,7 +565,12 @@ impl<S: Deref<Target = AuthCodeSpotify>> SpotifyWrapper<S> {
             return Ok(track);
         }
 
-        let track: ShortTrack = self.spotify.track(track_id, None).await?.into();
+        let track: ShortTrack = self
+            .request(|spotify| spotify.track(track_id, None))
+            .await
+            .typed()
+            .await?
+            .into();
 
         let _: () = redis_conn
             .set_ex(key, serde_json::to_string(&track)?, ttl.num_seconds() as _)
@@ -472,20 +580,22 @@ impl<S: Deref<Target = AuthCodeSpotify>> SpotifyWrapper<S> {
     }
 
     pub async fn current_playing_wrapped(&self) -> CurrentlyPlaying {
-        let playing = self.current_playing(None, None::<&[_]>).await;
+        let playing = self
+            .request(|spotify| spotify.current_playing(None, None::<&[_]>))
+            .await;
 
         let playing = match playing {
             Ok(playing) => playing,
-            Err(err) => return err.into(),
+            Err(err) => return CurrentlyPlaying::Err(RustifyError::from_spotify(err).await),
         };
 
-        let (item, context) = match playing {
+        let (item, context, progress) = match playing {
             Some(playing) => {
                 if !playing.is_playing {
                     return CurrentlyPlaying::None(CurrentlyPlayingNoneReason::Pause);
                 }
 
-                (playing.item, playing.context)
+                (playing.item, playing.context, playing.progress)
             },
             None => return CurrentlyPlaying::None(CurrentlyPlayingNoneReason::Nothing),
         };
@@ -499,16 +609,8 @@ impl<S: Deref<Target = AuthCodeSpotify>> SpotifyWrapper<S> {
         };
 
         match &track.id {
-            Some(_) => CurrentlyPlaying::Ok(Box::new(track.into()), context),
+            Some(_) => CurrentlyPlaying::Ok(Box::new(track.into()), context, progress),
             None => CurrentlyPlaying::None(CurrentlyPlayingNoneReason::Local),
         }
     }
 }
-
-impl<T: Deref<Target = AuthCodeSpotify>> Deref for SpotifyWrapper<T> {
-    type Target = AuthCodeSpotify;
-
-    fn deref(&self) -> &AuthCodeSpotify {
-        &self.spotify
-    }
-}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use crate::services::{RateLimitOutput, SpotifyRateLimitService};
+
+/// Handle to app-wide Spotify budget, every [`crate::spotify::SpotifyWrapper`] goes through it
+#[derive(Clone)]
+pub struct RateLimiter {
+    redis: deadpool_redis::Pool,
+    client_id: String,
+}
+
+impl RateLimiter {
+    /// Longer waits are left to Spotify, it answers with 429 and new `Retry-After`
+    const MAX_WAIT: Duration = Duration::from_secs(30);
+
+    #[must_use]
+    pub fn new(redis: deadpool_redis::Pool, client_id: String) -> Self {
+        Self { redis, client_id }
+    }
+
+    #[must_use]
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Waits for a request slot in the budget. Redis issues don't block Spotify calls
+    pub async fn acquire(&self) {
+        let mut waited = Duration::ZERO;
+
+        loop {
+            let res = async {
+                let mut redis_conn = self.redis.get().await?;
+
+                SpotifyRateLimitService::acquire(&mut redis_conn, &self.client_id).await
+            }
+            .await;
+
+            let wait = match res {
+                Ok(RateLimitOutput::Allowed) => return,
+                Ok(RateLimitOutput::NeedToWait(wait)) => wait.to_std().unwrap_or_default(),
+                Err(err) => {
+                    tracing::error!(err = ?err, "Failed to acquire Spotify rate limit");
+                    return;
+                },
+            };
+
+            if waited + wait > Self::MAX_WAIT {
+                tracing::warn!(?wait, "Spotify rate budget is exhausted, proceeding anyway");
+                return;
+            }
+
+            tokio::time::sleep(wait).await;
+            waited += wait;
+        }
+    }
+}
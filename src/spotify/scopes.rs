@@ -0,0 +1,134 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+
+use itertools::Itertools as _;
+
+use crate::entity::prelude::{UserAISlopDetection, UserModel};
+
+/// Rustify features and Spotify scopes they need.
+/// Only the base set is requested on login, the rest is requested incrementally
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    Base,
+    AutoSkip,
+    Collection,
+    MagicPlaylist,
+    Recommendations,
+    RecommendationsPlaylist,
+}
+
+impl Feature {
+    #[must_use]
+    pub fn scopes(self) -> &'static [&'static str] {
+        match self {
+            Self::Base => &[
+                "user-read-currently-playing",
+                "user-read-playback-state",
+                "user-read-private",
+            ],
+            Self::AutoSkip => &["user-modify-playback-state"],
+            Self::Collection => &[
+                "user-library-modify",
+                "playlist-modify-private",
+                "playlist-modify-public",
+            ],
+            Self::MagicPlaylist => &[
+                "user-library-read",
+                "playlist-read-private",
+                "playlist-modify-private",
+                "playlist-modify-public",
+            ],
+            Self::Recommendations => &["user-library-read", "user-modify-playback-state"],
+            Self::RecommendationsPlaylist => &["playlist-modify-private"],
+        }
+    }
+
+    #[must_use]
+    pub fn localize(self, locale: &str) -> Cow<'_, str> {
+        match self {
+            Self::Base => t!("login.feature-base", locale = locale),
+            Self::AutoSkip => t!("login.feature-auto-skip", locale = locale),
+            Self::Collection => t!("login.feature-collection", locale = locale),
+            Self::MagicPlaylist => t!("login.feature-magic-playlist", locale = locale),
+            Self::Recommendations => t!("login.feature-recommendations", locale = locale),
+            Self::RecommendationsPlaylist => {
+                t!("login.feature-recommendations-playlist", locale = locale)
+            },
+        }
+    }
+
+    /// Features used by current user settings
+    #[must_use]
+    pub fn enabled_for(user: &UserModel) -> Vec<Self> {
+        let mut features = vec![Self::Base];
+
+        if user.skip_tracks() {
+            features.extend([Self::AutoSkip, Self::Collection]);
+        }
+
+        if user.cfg_skippage_enabled
+            || user.kid_mode()
+            || user.skip_profane()
+            || user.skip_explicit()
+            || user.ai_slop_detection() == UserAISlopDetection::Skip
+        {
+            features.push(Self::AutoSkip);
+        }
+
+        features.into_iter().unique().collect()
+    }
+
+    #[must_use]
+    pub fn is_granted(self, granted: &HashSet<String>) -> bool {
+        self.scopes().iter().all(|scope| granted.contains(*scope))
+    }
+}
+
+/// Scopes for features, base scopes are always included
+#[must_use]
+pub fn scopes_for(features: &[Feature]) -> HashSet<String> {
+    features
+        .iter()
+        .chain([&Feature::Base])
+        .flat_map(|feature| feature.scopes())
+        .map(|scope| (*scope).to_owned())
+        .collect()
+}
+
+/// Space separated, as in OAuth responses
+#[must_use]
+pub fn serialize<S: BuildHasher>(scopes: &HashSet<String, S>) -> String {
+    scopes.iter().sorted().join(" ")
+}
+
+#[must_use]
+pub fn deserialize(scopes: &str) -> HashSet<String> {
+    scopes.split_whitespace().map(str::to_owned).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes_for_includes_base() {
+        let scopes = scopes_for(&[Feature::AutoSkip]);
+
+        assert!(Feature::Base.is_granted(&scopes));
+        assert!(Feature::AutoSkip.is_granted(&scopes));
+        assert!(!Feature::MagicPlaylist.is_granted(&scopes));
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let scopes = scopes_for(&[Feature::Recommendations]);
+
+        assert_eq!(deserialize(&serialize(&scopes)), scopes);
+        assert_eq!(
+            serialize(&deserialize("b a")),
+            "a b",
+            "Scopes are sorted to keep stored value stable"
+        );
+    }
+}
@@ -3,6 +3,7 @@ use itertools::Itertools as _;
 use teloxide::prelude::*;
 use teloxide::sugar::bot::BotMessagesExt as _;
 use teloxide::types::InlineKeyboardMarkup;
+use teloxide::utils::html;
 
 use crate::app::App;
 use crate::entity::prelude::{TrackStatus, UserStatus};
@@ -77,9 +78,12 @@ pub async fn handle_inline(
     Ok(())
 }
 
-async fn format_user_details(app: &'static App, user_id: &str) -> anyhow::Result<String> {
+pub async fn format_user_details(app: &'static App, user_id: &str) -> anyhow::Result<String> {
     let Some(user) = UserService::get_by_id(app.db(), user_id).await? else {
-        return Ok(format!("User with ID <code>{user_id}</code> is not found"));
+        return Ok(format!(
+            "User with ID <code>{}</code> is not found",
+            html::escape(user_id)
+        ));
     };
 
     let stats = UserService::get_stats(app.db(), Some(user_id)).await?;
@@ -133,8 +137,11 @@ async fn format_user_details(app: &'static App, user_id: &str) -> anyhow::Result
             • Updated: <code>{updated_at}</code>
 
             <b>Configuration:</b>
+            • Profile: <code>{profile:?}</code>
             • Profanity Check: <code>{check_profanity}</code>
             • Track Skip: <code>{skip_tracks}</code>
+            • Kid Mode: <code>{kid_mode}</code>
+            • Explicit Skip: <code>{skip_explicit}</code>
             • AI Slop: <code>{ai_slop:?}</code>
             • Skippage Enabled: <code>{skippage_enabled}</code>
             • Skippage Duration: <code>{skippage_secs} seconds</code>
@@ -160,7 +167,7 @@ async fn format_user_details(app: &'static App, user_id: &str) -> anyhow::Result
             <blockquote expandable><b>Languages stats:</b>
             {languages}</blockquote>
         "#,
-        name = user.name,
+        name = html::escape(&user.name),
         id = user.id,
         status = user.status,
         role = user.role,
@@ -171,8 +178,11 @@ async fn format_user_details(app: &'static App, user_id: &str) -> anyhow::Result
         ),
         created_at = user.created_at.format("%Y-%m-%d %H:%M:%S"),
         updated_at = user.updated_at.format("%Y-%m-%d %H:%M:%S"),
+        profile = user.profile,
         check_profanity = render_bool(user.cfg_check_profanity),
         skip_tracks = render_bool(user.cfg_skip_tracks),
+        kid_mode = render_bool(user.cfg_kid_mode),
+        skip_explicit = render_bool(user.cfg_skip_explicit),
         ai_slop = user.cfg_ai_slop_detection,
         skippage_enabled = render_bool(user.cfg_skippage_enabled),
         skippage_secs = user.cfg_skippage_secs,
@@ -14,6 +14,7 @@ use teloxide::types::{
 use crate::app::App;
 use crate::entity::prelude::UserAISlopDetection;
 use crate::services::UserService;
+use crate::spotify::scopes::Feature;
 use crate::telegram::handlers::HandleStatus;
 use crate::telegram::inline_buttons::InlineButtons;
 use crate::user::UserState;
@@ -28,6 +29,10 @@ pub async fn handle_inline(
 ) -> anyhow::Result<()> {
     app.bot().answer_callback_query(q.id).await?;
 
+    if super::kid_safe::check_settings_locked(app, state, m.chat.id).await? {
+        return Ok(());
+    }
+
     if status != state.user().cfg_ai_slop_detection {
         UserService::set_cfg_ai_slop_detection(app.db(), state.user_id(), status).await?;
 
@@ -38,6 +43,10 @@ pub async fn handle_inline(
                 state.locale(),
             )))
             .await?;
+
+        if status == UserAISlopDetection::Skip {
+            super::login::ensure_features(app, state, &[Feature::AutoSkip]).await?;
+        }
     }
 
     Ok(())
@@ -76,7 +85,7 @@ pub async fn handle(
             t!("ai-slop.setting-description", locale = state.locale()),
         )
         .reply_markup(ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(
-            get_keyboard(state.user().cfg_ai_slop_detection, state.locale()),
+            get_keyboard(state.user().ai_slop_detection(), state.locale()),
         )))
         .await?;
 
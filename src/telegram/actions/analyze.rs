@@ -1,10 +1,19 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
 use async_openai::types::chat::{
+    ChatCompletionNamedToolChoice,
     ChatCompletionRequestUserMessage,
+    ChatCompletionStreamOptions,
+    ChatCompletionTool,
+    ChatCompletionToolChoiceOption,
     CreateChatCompletionRequestArgs,
+    FunctionObjectArgs,
 };
 use backon::{ExponentialBuilder, Retryable as _};
+use futures::StreamExt as _;
 use itertools::Itertools as _;
-use rspotify::model::TrackId;
+use rspotify::model::{Id as _, TrackId};
 use teloxide::payloads::{
     AnswerCallbackQuerySetters as _,
     EditMessageTextSetters as _,
@@ -12,15 +21,22 @@ use teloxide::payloads::{
 };
 use teloxide::prelude::Requester as _;
 use teloxide::sugar::bot::BotMessagesExt as _;
+use teloxide::sugar::request::RequestLinkPreviewExt as _;
 use teloxide::types::{CallbackQuery, InlineKeyboardMarkup, Message};
+use teloxide::utils::html;
 
-use crate::app::{AIConfig, App};
+use crate::ai::{self, AIConfig, AIFeature};
+use crate::app::App;
 use crate::lyrics::SearchResult as _;
 use crate::profanity;
 use crate::services::{
-    RateLimitAction,
-    RateLimitOutput,
-    RateLimitService,
+    AIQuotaOutput,
+    AIUsageService,
+    AnalysisReport,
+    ContentLevel,
+    TrackAnalysisService,
+    TrackCheckCacheService,
+    TrackCheckKind,
     TrackStatusService,
     UserService,
     WordDefinitionService,
@@ -28,10 +44,47 @@ use crate::services::{
 };
 use crate::spotify::ShortTrack;
 use crate::telegram::MESSAGE_MAX_LEN;
+use crate::telegram::handlers::HandleStatus;
 use crate::telegram::inline_buttons::InlineButtons;
 use crate::telegram::utils::link_preview_small_top;
 use crate::user::UserState;
-use crate::utils::{DurationPrettyFormat as _, StringUtils as _};
+use crate::utils::StringUtils as _;
+
+/// Telegram allows about one edit per second in a chat, stay well below
+const STREAM_EDIT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Admin command to remove saved analyses and age rating of the track, so they are generated again
+#[tracing::instrument(skip_all, fields(%track_id))]
+pub async fn handle_reset(
+    app: &'static App,
+    m: &Message,
+    track_id: &str,
+) -> anyhow::Result<HandleStatus> {
+    let track_id = TrackId::from_id_or_uri(track_id)?;
+
+    let result = TrackAnalysisService::clear(app.db(), track_id.id()).await?;
+
+    // Kid mode uses age rating shared from the analysis
+    TrackCheckCacheService::clear(
+        &mut app.redis_conn().await?,
+        TrackCheckKind::AgeRating,
+        track_id.id(),
+    )
+    .await?;
+
+    app.bot()
+        .send_message(
+            m.chat.id,
+            format!(
+                "Removed {} analyses of <code>{}</code>",
+                result.rows_affected,
+                track_id.id()
+            ),
+        )
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
 
 #[tracing::instrument(skip_all, fields(user_id = %state.user_id(), %track_id))]
 pub async fn handle_inline(
@@ -53,29 +106,24 @@ pub async fn handle_inline(
 
     let mut redis_conn = app.redis_conn().await?;
 
-    if let RateLimitOutput::NeedToWait(duration) =
-        RateLimitService::enforce_limit(&mut redis_conn, state.user_id(), RateLimitAction::Analyze)
-            .await?
-    {
-        app.bot()
-            .answer_callback_query(q.id)
-            .text(t!(
-                "rate-limit.analysis",
-                duration = duration.pretty_format(),
-                locale = state.locale()
-            ))
-            .show_alert(true)
-            .await?;
-
-        return Ok(());
-    }
-
     let track = state
         .spotify()
         .await
         .short_track_cached(&mut redis_conn, TrackId::from_id(track_id)?)
         .await?;
 
+    // Analysis of any model from the chain is good enough
+    let mut cached = None;
+
+    for target in config.targets(AIFeature::Analysis) {
+        cached = TrackAnalysisService::find(app.db(), track.id(), state.locale(), target.model())
+            .await?;
+
+        if cached.is_some() {
+            break;
+        }
+    }
+
     let Some(hit) = app
         .lyrics()
         .search_for_track(&mut redis_conn, &track)
@@ -88,6 +136,39 @@ pub async fn handle_inline(
         return Ok(());
     };
 
+    // Cached analyses cost nothing, so they are not limited
+    let reservation = if cached.is_none() {
+        match AIUsageService::reserve(
+            app.db(),
+            &mut redis_conn,
+            config,
+            state.user_id(),
+            AIFeature::Analysis,
+        )
+        .await?
+        {
+            AIQuotaOutput::Allowed(reservation) => Some(reservation),
+            AIQuotaOutput::Exceeded { renews_on } => {
+                app.bot()
+                    .answer_callback_query(q.id)
+                    .text(t!(
+                        "rate-limit.ai-quota",
+                        date = renews_on.format("%Y-%m-%d"),
+                        locale = state.locale()
+                    ))
+                    .show_alert(true)
+                    .await?;
+
+                return Ok(());
+            },
+        }
+    } else {
+        None
+    };
+
+    // Pooled connection isn't held while analysis is streamed
+    drop(redis_conn);
+
     app.bot()
         .edit_text(
             &m,
@@ -101,7 +182,11 @@ pub async fn handle_inline(
         .link_preview_options(link_preview_small_top(track.url()))
         .await?;
 
-    let res = perform(app, state, config, &track, &hit.lyrics()).await;
+    let res = perform(app, state, config, &m, &track, &hit.lyrics(), cached).await;
+
+    if let Some(reservation) = reservation {
+        AIUsageService::release(&mut app.redis_conn().await?, reservation).await?;
+    }
 
     // I don't care about error
     app.bot().delete(&m).await.ok();
@@ -146,35 +231,27 @@ async fn perform(
     app: &App,
     state: &UserState,
     config: &AIConfig,
+    m: &Message,
     track: &ShortTrack,
     lyrics: &[&str],
+    cached: Option<AnalysisReport>,
 ) -> Result<(), anyhow::Error> {
-    let song_name = track.name_with_artists();
-
-    let model = config.model();
-
-    let prompt = t!(
-        "analysis.prompt",
-        song_name = song_name,
-        lyrics = lyrics.join("\n"),
-        locale = state.locale()
-    );
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(model)
-        .messages([ChatCompletionRequestUserMessage::from(prompt.as_ref()).into()])
-        .build()?;
-
-    let response = config.openai_client().chat().create(request).await?;
-
-    let choices = response.choices.first();
-
-    let Some(choice) = choices else { return Ok(()) };
+    let report = match cached {
+        Some(report) => report,
+        None => match generate(app, state, config, Some(m), track, lyrics).await? {
+            Some(report) => report,
+            None => anyhow::bail!("AI returned no analysis"),
+        },
+    };
 
-    let analysis_result = choice.message.content.clone().unwrap_or_default();
+    let analysis_result = render(state.locale(), &report);
 
     let status = TrackStatusService::get_status(app.db(), state.user_id(), track.id()).await;
-    let keyboard = InlineButtons::from_track_status(status, track.id(), state.locale());
+    let mut keyboard = InlineButtons::from_track_status(status, track.id(), state.locale());
+
+    keyboard.push(vec![
+        InlineButtons::Ask(track.id().into()).into_inline_keyboard_button(state.locale()),
+    ]);
 
     let checked = profanity::Manager::check(lyrics);
 
@@ -184,31 +261,41 @@ async fn perform(
 
     for profane_word in checked.get_profine_words().iter().sorted() {
         let definition = (|| {
-            WordDefinitionService::get_definition(app.db(), state.locale(), config, profane_word)
+            WordDefinitionService::get_definition(
+                app,
+                state.user_id(),
+                state.locale(),
+                config,
+                profane_word,
+            )
         })
         .retry(ExponentialBuilder::default())
         .await?;
 
+        // Words are listed without definitions once AI quota is used up
+        let definition = definition
+            .map(|definition| format!(" — {definition}"))
+            .unwrap_or_default();
+
         profane_words.push(format!(
-            "<tg-spoiler><code>{profane_word}</code></tg-spoiler> — {definition}"
+            "<tg-spoiler><code>{profane_word}</code></tg-spoiler>{definition}"
         ));
     }
 
-    let (profane_words_block, profane_words_doesnt_fit_block) = if profane_words.is_empty() {
-        (String::new(), String::new())
-    } else {
-        (
-            t!(
-                "analyze.profane-words",
-                profane_words = profane_words.join("\n\n"),
-                locale = state.locale()
-            )
-            .into(),
-            t!("analyze.profane-words-doesnt-fit", locale = state.locale()).into(),
+    let profane_words_block_gen = |profane_words: &str| -> String {
+        if profane_words.is_empty() {
+            return String::new();
+        }
+
+        t!(
+            "analyze.profane-words",
+            profane_words = profane_words,
+            locale = state.locale()
         )
+        .into()
     };
 
-    let message_text_gen = |analysis_result: &str, profane_words_block: &str| {
+    let message_text_gen = |analysis_result: &str, profane_words_block: &str| -> String {
         t!(
             "analysis.result",
             locale = state.locale(),
@@ -217,30 +304,235 @@ async fn perform(
             analysis_result = analysis_result,
             profane_words_block = profane_words_block,
         )
+        .into()
     };
 
-    let mut text = message_text_gen(&analysis_result, &profane_words_block);
+    let profane_words = profane_words.join("\n\n");
 
-    // Remove profane block if message is too long
-    if text.chars_len() > MESSAGE_MAX_LEN {
-        text = message_text_gen(&analysis_result, &profane_words_doesnt_fit_block);
-    }
+    let text = message_text_gen(&analysis_result, &profane_words_block_gen(&profane_words));
+
+    // Split into several messages if everything doesn't fit in one
+    let texts = if text.chars_len() <= MESSAGE_MAX_LEN {
+        vec![text]
+    } else {
+        let header_len = message_text_gen("", "").chars_len();
+        let profane_words_wrapper_len = profane_words_block_gen(" ").chars_len();
+
+        let mut analysis_parts = analysis_result
+            .chars_split(MESSAGE_MAX_LEN.saturating_sub(header_len))
+            .into_iter();
+
+        let first_part = analysis_parts.next().unwrap_or_default();
+
+        std::iter::once(message_text_gen(&first_part, ""))
+            .chain(analysis_parts)
+            .chain(
+                profane_words
+                    .chars_split(MESSAGE_MAX_LEN.saturating_sub(profane_words_wrapper_len))
+                    .iter()
+                    .map(|profane_words| profane_words_block_gen(profane_words)),
+            )
+            .collect()
+    };
+
+    let chat_id = state.chat_id()?;
+    let last = texts.len() - 1;
+
+    for (i, text) in texts.into_iter().enumerate() {
+        let mut request = app.bot().send_message(chat_id, text);
 
-    // If this didn't help, crop analysis result as well
-    if text.chars_len() > MESSAGE_MAX_LEN {
-        let analysis_len = analysis_result.chars_len() - (text.chars_len() - MESSAGE_MAX_LEN);
+        request = if i == 0 {
+            request.link_preview_options(link_preview_small_top(track.url()))
+        } else {
+            request.disable_link_preview(true)
+        };
 
-        text = message_text_gen(
-            &analysis_result.chars_crop(analysis_len),
-            &profane_words_doesnt_fit_block,
-        );
+        if i == last {
+            request = request.reply_markup(InlineKeyboardMarkup::new(keyboard.clone()));
+        }
+
+        request.await?;
     }
 
-    app.bot()
-        .send_message(state.chat_id()?, text)
+    Ok(())
+}
+
+/// Shows partial analysis in the waiting message while it is generated
+async fn show_progress(
+    app: &App,
+    state: &UserState,
+    m: &Message,
+    track: &ShortTrack,
+    analysis_result: &str,
+) {
+    let text_gen = |analysis_result: &str| {
+        t!(
+            "analysis.streaming",
+            locale = state.locale(),
+            track_name = track.track_tg_link(),
+            album_name = track.album_tg_link(),
+            analysis_result = analysis_result,
+        )
+    };
+
+    let available = MESSAGE_MAX_LEN.saturating_sub(text_gen("").chars_len());
+
+    // Escaping makes text a bit longer, so keep some room for it
+    let analysis_result = html::escape(&analysis_result.chars_crop(available * 9 / 10));
+
+    let res = app
+        .bot()
+        .edit_text(m, text_gen(&analysis_result))
         .link_preview_options(link_preview_small_top(track.url()))
-        .reply_markup(InlineKeyboardMarkup::new(keyboard))
-        .await?;
+        .await;
 
-    Ok(())
+    if let Err(err) = res {
+        tracing::warn!(err = ?err, "Failed to show analysis progress");
+    }
+}
+
+fn render(locale: &str, report: &AnalysisReport) -> String {
+    let content = report
+        .content
+        .iter()
+        .map(|(category, rating)| {
+            let icon = match rating.level {
+                ContentLevel::None => "✅",
+                ContentLevel::Mild => "⚠️",
+                ContentLevel::Strong => "⛔",
+            };
+
+            let category = t!(format!("analysis.category-{category}"), locale = locale);
+
+            if rating.level == ContentLevel::None || rating.details.is_empty() {
+                format!("{icon} <b>{category}</b>")
+            } else {
+                format!(
+                    "{icon} <b>{category}:</b> {}",
+                    html::escape(&rating.details)
+                )
+            }
+        })
+        .join("\n");
+
+    t!(
+        "analysis.report",
+        locale = locale,
+        summary = html::escape(&report.summary),
+        description = html::escape(&report.description),
+        mood = html::escape(&report.mood),
+        themes = html::escape(&report.themes.join(", ")),
+        age_rating = report.age_rating.as_ref(),
+        content = content,
+    )
+    .into()
+}
+
+/// Requests analysis from AI and saves it for other users.
+/// Description is shown in the `progress` message while the rest is generated
+pub async fn generate(
+    app: &App,
+    state: &UserState,
+    config: &AIConfig,
+    progress: Option<&Message>,
+    track: &ShortTrack,
+    lyrics: &[&str],
+) -> anyhow::Result<Option<AnalysisReport>> {
+    let song_name = track.name_with_artists();
+
+    let prompt = t!(
+        "analysis.prompt",
+        song_name = song_name,
+        lyrics = lyrics.join("\n"),
+        locale = state.locale()
+    );
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .messages([ChatCompletionRequestUserMessage::from(prompt.as_ref()).into()])
+        .tools(ChatCompletionTool {
+            function: FunctionObjectArgs::default()
+                .name(AnalysisReport::TOOL_NAME)
+                .description("Save structured analysis of the song lyrics")
+                .strict(true)
+                .parameters(AnalysisReport::schema())
+                .build()?,
+        })
+        .tool_choice(ChatCompletionToolChoiceOption::Function(
+            ChatCompletionNamedToolChoice::from(AnalysisReport::TOOL_NAME),
+        ))
+        .stream_options(ChatCompletionStreamOptions {
+            include_usage: Some(true),
+            include_obfuscation: None,
+        })
+        .build()?;
+
+    let (target, mut stream) = config.chat_stream(AIFeature::Analysis, request).await?;
+
+    let mut usage = None;
+    let mut arguments = String::new();
+    let mut shown_at = Instant::now();
+    let mut shown_len = 0;
+
+    while let Some(response) = stream.next().await {
+        let response = response?;
+
+        // Comes with the last chunk
+        if response.usage.is_some() {
+            usage = response.usage;
+        }
+
+        let tool_calls = response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.delta.tool_calls)
+            .unwrap_or_default();
+
+        for tool_call in tool_calls {
+            if let Some(chunk) = tool_call.function.and_then(|function| function.arguments) {
+                arguments.push_str(&chunk);
+            }
+        }
+
+        let Some(m) = progress else {
+            continue;
+        };
+
+        if shown_at.elapsed() >= STREAM_EDIT_INTERVAL && arguments.len() != shown_len {
+            let description =
+                ai::partial_string_field(&arguments, "description").unwrap_or_default();
+
+            show_progress(app, state, m, track, &description).await;
+
+            shown_at = Instant::now();
+            shown_len = arguments.len();
+        }
+    }
+
+    AIUsageService::record(
+        app.db(),
+        state.user_id(),
+        AIFeature::Analysis,
+        target,
+        usage.as_ref(),
+    )
+    .await?;
+
+    if arguments.is_empty() {
+        return Ok(None);
+    }
+
+    let report: AnalysisReport =
+        serde_json::from_str(&arguments).context("AI returned invalid analysis report")?;
+
+    TrackAnalysisService::save(
+        app.db(),
+        track.id(),
+        state.locale(),
+        target.model(),
+        &report,
+    )
+    .await?;
+
+    Ok(Some(report))
 }
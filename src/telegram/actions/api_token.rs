@@ -0,0 +1,75 @@
+use teloxide::payloads::SendMessageSetters as _;
+use teloxide::prelude::Requester as _;
+use teloxide::types::ChatId;
+
+use crate::app::App;
+use crate::services::ApiTokenService;
+use crate::telegram::commands::UserCommandDisplay;
+use crate::telegram::handlers::HandleStatus;
+use crate::telegram::keyboards::StartKeyboard;
+use crate::user::UserState;
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_issue(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+) -> anyhow::Result<HandleStatus> {
+    let token = ApiTokenService::issue(app.db(), state.user_id()).await?;
+
+    tracing::info!(user_id = state.user_id(), "User issued API token");
+
+    let base_url = app
+        .server_public_url()
+        .map(|url| {
+            format!(
+                "\n{}",
+                t!(
+                    "api.base-url",
+                    locale = state.locale(),
+                    url = format!("{url}/api/v1")
+                )
+            )
+        })
+        .unwrap_or_default();
+
+    app.bot()
+        .send_message(
+            chat_id,
+            t!(
+                "api.token-issued",
+                locale = state.locale(),
+                token = token,
+                base_url = base_url,
+                command = UserCommandDisplay::RevokeApiToken,
+            ),
+        )
+        .reply_markup(StartKeyboard::markup(state.locale()))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_revoke(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+) -> anyhow::Result<HandleStatus> {
+    let text = if ApiTokenService::revoke(app.db(), state.user_id()).await? {
+        t!("api.token-revoked", locale = state.locale())
+    } else {
+        t!(
+            "api.token-not-found",
+            locale = state.locale(),
+            command = UserCommandDisplay::ApiToken,
+        )
+    };
+
+    app.bot()
+        .send_message(chat_id, text)
+        .reply_markup(StartKeyboard::markup(state.locale()))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
@@ -0,0 +1,590 @@
+use anyhow::Context as _;
+use async_openai::types::chat::{
+    ChatCompletionRequestAssistantMessage,
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessage,
+    ChatCompletionRequestUserMessage,
+    CreateChatCompletionRequestArgs,
+};
+use chrono::{Duration, NaiveDate};
+use indoc::formatdoc;
+use rspotify::model::TrackId;
+use serde::{Deserialize, Serialize};
+use teloxide::dispatching::dialogue::serializer::Bincode;
+use teloxide::dispatching::dialogue::{Dialogue, RedisStorage};
+use teloxide::payloads::{AnswerCallbackQuerySetters as _, SendMessageSetters as _};
+use teloxide::prelude::Requester as _;
+use teloxide::sugar::request::RequestLinkPreviewExt as _;
+use teloxide::types::{
+    CallbackQuery,
+    ChatId,
+    InlineKeyboardButtonKind,
+    InlineKeyboardMarkup,
+    Message,
+    ReplyParameters,
+};
+use teloxide::utils::html;
+
+use crate::ai::{AIConfig, AIFeature};
+use crate::app::App;
+use crate::lyrics::SearchResult as _;
+use crate::services::{AIQuotaOutput, AIUsageService};
+use crate::spotify::{CurrentlyPlaying, ShortTrack};
+use crate::telegram::commands::UserCommandDisplay;
+use crate::telegram::handlers::HandleStatus;
+use crate::telegram::inline_buttons::InlineButtons;
+use crate::telegram::{MESSAGE_MAX_LEN, actions};
+use crate::user::UserState;
+use crate::utils::{Clock, StringUtils as _};
+
+/// Chat is forgotten after that time without questions
+const CHAT_TTL: Duration = Duration::hours(6);
+/// Previous questions and answers given to AI
+const MAX_TURNS: usize = 10;
+const QUESTION_MAX_LEN: usize = 500;
+const LYRICS_MAX_LEN: usize = 6000;
+
+type AskDialogueHandle = Dialogue<AskDialogue, RedisStorage<Bincode>>;
+
+/// State of the chat about a track, kept in the dialogue storage
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum AskDialogue {
+    #[default]
+    Idle,
+    Chatting(TrackChat),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrackChat {
+    track_id: String,
+    track_name: String,
+    /// Track metadata and lyrics given to AI
+    context: String,
+    turns: Vec<ChatTurn>,
+    /// Bot messages user can reply to for continuing the chat
+    message_ids: Vec<i32>,
+    /// Unix timestamp of the last question
+    updated_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ChatTurn {
+    question: String,
+    answer: String,
+}
+
+impl TrackChat {
+    fn is_active(&self, now: i64) -> bool {
+        now - self.updated_at < CHAT_TTL.num_seconds()
+    }
+
+    fn push_turn(&mut self, question: String, answer: String, message_id: i32, now: i64) {
+        self.turns.push(ChatTurn { question, answer });
+        self.message_ids.push(message_id);
+        self.updated_at = now;
+
+        // Intro message is counted as well
+        let turns_excess = self.turns.len().saturating_sub(MAX_TURNS);
+        let messages_excess = self.message_ids.len().saturating_sub(MAX_TURNS + 1);
+
+        self.turns.drain(..turns_excess);
+        self.message_ids.drain(..messages_excess);
+    }
+}
+
+/// `/ask` with a question asks about the current track, without it just starts the chat
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle(
+    app: &'static App,
+    state: &UserState,
+    m: &Message,
+    question: &str,
+) -> anyhow::Result<HandleStatus> {
+    let Some(config) = app.ai() else {
+        app.bot()
+            .send_message(m.chat.id, t!("ask.disabled", locale = state.locale()))
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    };
+
+    if !state.is_spotify_authed().await {
+        actions::login::send_login_invite(app, state).await?;
+
+        return Ok(HandleStatus::Handled);
+    }
+
+    let track = match state.spotify().await.current_playing_wrapped().await {
+        CurrentlyPlaying::Err(err) => return Err(err.into()),
+        CurrentlyPlaying::None(reason) => {
+            app.bot()
+                .send_message(m.chat.id, reason.localize(state.locale()))
+                .await?;
+
+            return Ok(HandleStatus::Handled);
+        },
+        CurrentlyPlaying::Ok(track, ..) => track,
+    };
+
+    let dialogue = AskDialogueHandle::new(app.dialogue_storage(), m.chat.id);
+    let question = question.trim();
+
+    let chat = match active_chat(&dialogue).await? {
+        Some(chat) if chat.track_id == track.id() && !question.is_empty() => chat,
+        _ => {
+            let Some(chat) = start(app, state, config, m.chat.id, &track).await? else {
+                return Ok(HandleStatus::Handled);
+            };
+
+            chat
+        },
+    };
+
+    if question.is_empty() {
+        send_intro(app, state, &dialogue, m.chat.id, chat).await?;
+
+        return Ok(HandleStatus::Handled);
+    }
+
+    answer(app, state, config, &dialogue, m, chat, question).await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+/// Starts the chat about the track from the analysis message
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id(), %track_id))]
+pub async fn handle_inline(
+    app: &'static App,
+    state: &UserState,
+    q: CallbackQuery,
+    m: Message,
+    track_id: &str,
+) -> anyhow::Result<()> {
+    let Some(config) = app.ai() else {
+        app.bot()
+            .answer_callback_query(q.id)
+            .text(t!("ask.disabled", locale = state.locale()))
+            .show_alert(true)
+            .await?;
+
+        return Ok(());
+    };
+
+    app.bot().answer_callback_query(q.id).await?;
+
+    let track = state
+        .spotify()
+        .await
+        .short_track_cached(&mut app.redis_conn().await?, TrackId::from_id(track_id)?)
+        .await?;
+
+    let dialogue = AskDialogueHandle::new(app.dialogue_storage(), m.chat.id);
+
+    let Some(chat) = start(app, state, config, m.chat.id, &track).await? else {
+        return Ok(());
+    };
+
+    send_intro(app, state, &dialogue, m.chat.id, chat).await?;
+
+    Ok(())
+}
+
+/// Replies to the chat messages continue the chat, replies to analysis messages start a new one
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_reply(
+    app: &'static App,
+    state: &UserState,
+    m: &Message,
+    reply: &Message,
+) -> anyhow::Result<HandleStatus> {
+    let Some(question) = m.text().map(str::trim).filter(|text| !text.is_empty()) else {
+        return Ok(HandleStatus::Skipped);
+    };
+
+    if !reply.from.as_ref().is_some_and(|user| user.is_bot) {
+        return Ok(HandleStatus::Skipped);
+    }
+
+    let Some(config) = app.ai() else {
+        return Ok(HandleStatus::Skipped);
+    };
+
+    let dialogue = AskDialogueHandle::new(app.dialogue_storage(), m.chat.id);
+
+    let active = active_chat(&dialogue).await?;
+    let track_id = reply.reply_markup().and_then(track_id_from_markup);
+
+    let chat = match (active, track_id) {
+        (Some(chat), _) if chat.message_ids.contains(&reply.id.0) => chat,
+        (Some(chat), Some(track_id)) if chat.track_id == track_id => chat,
+        (_, Some(track_id)) => {
+            if !state.is_spotify_authed().await {
+                return Ok(HandleStatus::Skipped);
+            }
+
+            let track = state
+                .spotify()
+                .await
+                .short_track_cached(&mut app.redis_conn().await?, TrackId::from_id(track_id)?)
+                .await?;
+
+            let Some(chat) = start(app, state, config, m.chat.id, &track).await? else {
+                return Ok(HandleStatus::Handled);
+            };
+
+            chat
+        },
+        _ => return Ok(HandleStatus::Skipped),
+    };
+
+    answer(app, state, config, &dialogue, m, chat, question).await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+/// Forgotten chat is removed from the storage
+async fn active_chat(dialogue: &AskDialogueHandle) -> anyhow::Result<Option<TrackChat>> {
+    let state = dialogue.get().await?;
+
+    let chat = match state {
+        Some(AskDialogue::Chatting(chat)) => chat,
+        Some(AskDialogue::Idle) | None => return Ok(None),
+    };
+
+    if !chat.is_active(Clock::now().and_utc().timestamp()) {
+        dialogue.exit().await?;
+
+        return Ok(None);
+    }
+
+    Ok(Some(chat))
+}
+
+async fn send_quota_exceeded(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+    renews_on: NaiveDate,
+) -> anyhow::Result<()> {
+    app.bot()
+        .send_message(
+            chat_id,
+            t!(
+                "rate-limit.ai-quota",
+                date = renews_on.format("%Y-%m-%d"),
+                locale = state.locale()
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Collects what AI needs to know about the track.
+/// Returns `None` if AI quota is used up, lyrics aren't fetched then
+async fn start(
+    app: &App,
+    state: &UserState,
+    config: &AIConfig,
+    chat_id: ChatId,
+    track: &ShortTrack,
+) -> anyhow::Result<Option<TrackChat>> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    if let Some(renews_on) =
+        AIUsageService::exceeded_until(app.db(), &mut redis_conn, config, state.user_id()).await?
+    {
+        send_quota_exceeded(app, state, chat_id, renews_on).await?;
+
+        return Ok(None);
+    }
+
+    let hit = app
+        .lyrics()
+        .search_for_track(&mut redis_conn, track)
+        .await?;
+
+    let lyrics = hit.map_or_else(
+        || "Lyrics are not available".to_owned(),
+        |hit| hit.lyrics().join("\n").chars_crop(LYRICS_MAX_LEN),
+    );
+
+    let released = track
+        .album_release_date()
+        .map_or_else(|| "unknown".to_owned(), |date| date.to_string());
+
+    let audience = if state.user().kid_mode() {
+        "The user is a child: keep answers child-friendly, never quote or explain profane words"
+    } else {
+        "The user is an adult"
+    };
+
+    let context = formatdoc!(
+        "
+            TRACK: {track_name}
+            ALBUM: {album_name}
+            RELEASED: {released}
+            DURATION: {minutes}:{seconds:02}
+            EXPLICIT: {explicit}
+            AUDIENCE: {audience}
+
+            LYRICS:
+            {lyrics}
+        ",
+        track_name = track.name_with_artists(),
+        album_name = track.album_name(),
+        minutes = track.duration_secs() / 60,
+        seconds = track.duration_secs() % 60,
+        explicit = if track.is_explicit() { "yes" } else { "no" },
+    );
+
+    Ok(Some(TrackChat {
+        track_id: track.id().to_owned(),
+        track_name: track.name_with_artists(),
+        context,
+        turns: vec![],
+        message_ids: vec![],
+        updated_at: Clock::now().and_utc().timestamp(),
+    }))
+}
+
+async fn send_intro(
+    app: &App,
+    state: &UserState,
+    dialogue: &AskDialogueHandle,
+    chat_id: ChatId,
+    mut chat: TrackChat,
+) -> anyhow::Result<()> {
+    let m = app
+        .bot()
+        .send_message(
+            chat_id,
+            t!(
+                "ask.started",
+                track_name = html::escape(&chat.track_name),
+                command = UserCommandDisplay::Ask,
+                locale = state.locale()
+            ),
+        )
+        .await?;
+
+    chat.message_ids.push(m.id.0);
+
+    dialogue.update(AskDialogue::Chatting(chat)).await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id(), track_id = %chat.track_id))]
+async fn answer(
+    app: &App,
+    state: &UserState,
+    config: &AIConfig,
+    dialogue: &AskDialogueHandle,
+    m: &Message,
+    mut chat: TrackChat,
+    question: &str,
+) -> anyhow::Result<()> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    let reservation = match AIUsageService::reserve(
+        app.db(),
+        &mut redis_conn,
+        config,
+        state.user_id(),
+        AIFeature::Chat,
+    )
+    .await?
+    {
+        AIQuotaOutput::Allowed(reservation) => reservation,
+        AIQuotaOutput::Exceeded { renews_on } => {
+            send_quota_exceeded(app, state, m.chat.id, renews_on).await?;
+
+            return Ok(());
+        },
+    };
+
+    let question = question.chars_crop(QUESTION_MAX_LEN);
+
+    let req = CreateChatCompletionRequestArgs::default()
+        .messages(chat_messages(&chat, &question))
+        .max_completion_tokens(800_u32)
+        .build()?;
+
+    let res = config.chat(AIFeature::Chat, req).await;
+
+    if let Ok((target, response)) = &res {
+        AIUsageService::record(
+            app.db(),
+            state.user_id(),
+            AIFeature::Chat,
+            target,
+            response.usage.as_ref(),
+        )
+        .await?;
+    }
+
+    AIUsageService::release(&mut redis_conn, reservation).await?;
+
+    let (_, response) = res?;
+
+    let answer = response
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .map(|content| content.trim().to_owned())
+        .filter(|content| !content.is_empty())
+        .context("AI returned empty answer")?;
+
+    let text_gen = |answer: &str| {
+        t!(
+            "ask.answer",
+            track_name = html::escape(&chat.track_name),
+            answer = answer,
+            locale = state.locale()
+        )
+    };
+
+    // Escaping makes text a bit longer, so keep some room for it
+    let available = MESSAGE_MAX_LEN.saturating_sub(text_gen("").chars_len());
+    let text = text_gen(&html::escape(&answer.chars_crop(available * 9 / 10)));
+
+    let sent = app
+        .bot()
+        .send_message(m.chat.id, text)
+        .reply_parameters(ReplyParameters::new(m.id))
+        .disable_link_preview(true)
+        .await?;
+
+    chat.push_turn(
+        question,
+        answer,
+        sent.id.0,
+        Clock::now().and_utc().timestamp(),
+    );
+
+    dialogue.update(AskDialogue::Chatting(chat)).await?;
+
+    Ok(())
+}
+
+fn chat_messages(chat: &TrackChat, question: &str) -> Vec<ChatCompletionRequestMessage> {
+    let system_prompt = formatdoc!(
+        "
+            You are a music expert chatting with a user about a song.
+            Answer questions about the track, its artist and lyrics using the information below and your own knowledge.
+            Keep answers short, plain text without markdown, in the language of the question.
+            If you don't know something, say so instead of making it up.
+            Politely decline questions unrelated to the music.
+
+            {context}
+        ",
+        context = chat.context,
+    );
+
+    let mut messages =
+        vec![ChatCompletionRequestSystemMessage::from(system_prompt.as_str()).into()];
+
+    for turn in &chat.turns {
+        messages.push(ChatCompletionRequestUserMessage::from(turn.question.as_str()).into());
+        messages.push(ChatCompletionRequestAssistantMessage::from(turn.answer.as_str()).into());
+    }
+
+    messages.push(ChatCompletionRequestUserMessage::from(question).into());
+
+    messages
+}
+
+/// Track of the analysis message, taken from its Ask button.
+/// Other track messages don't start chats, a reply to a check notification isn't a question
+fn track_id_from_markup(markup: &InlineKeyboardMarkup) -> Option<String> {
+    markup
+        .inline_keyboard
+        .iter()
+        .flatten()
+        .find_map(|button| match &button.kind {
+            InlineKeyboardButtonKind::CallbackData(data) => match data.parse() {
+                Ok(InlineButtons::Ask(id)) => Some(id),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    fn chat(updated_at: i64) -> TrackChat {
+        TrackChat {
+            track_id: "track".into(),
+            track_name: "Artist — Track".into(),
+            context: String::new(),
+            turns: vec![],
+            message_ids: vec![1],
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_push_turn() {
+        let mut chat = chat(0);
+
+        for i in 0..15 {
+            chat.push_turn(format!("q{i}"), format!("a{i}"), i + 2, 100);
+        }
+
+        assert_eq!(chat.turns.len(), MAX_TURNS);
+        assert_eq!(chat.turns[0].question, "q5");
+        assert_eq!(chat.message_ids.len(), MAX_TURNS + 1);
+        assert_eq!(chat.message_ids.last(), Some(&16));
+        assert_eq!(chat.updated_at, 100);
+
+        assert!(chat.is_active(100 + CHAT_TTL.num_seconds() - 1));
+        assert!(!chat.is_active(100 + CHAT_TTL.num_seconds()));
+    }
+
+    #[test]
+    fn test_track_id_from_markup() {
+        let markup = InlineKeyboardMarkup::new(vec![
+            vec![InlineButtons::Magic.into_inline_keyboard_button("en")],
+            vec![InlineButtons::Ask("abc".into()).into_inline_keyboard_button("en")],
+        ]);
+
+        assert_eq!(track_id_from_markup(&markup), Some("abc".into()));
+
+        let markup = InlineKeyboardMarkup::new(vec![
+            vec![InlineButtons::Dislike("abc".into()).into_inline_keyboard_button("en")],
+            vec![InlineButtons::Ignore("abc".into()).into_inline_keyboard_button("en")],
+        ]);
+
+        assert_eq!(track_id_from_markup(&markup), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_forgotten_chat_removed() {
+        let test = TestApp::start().await.unwrap();
+        let chat_id = ChatId(TestApp::user_id().parse().unwrap());
+        let dialogue = AskDialogueHandle::new(test.app.dialogue_storage(), chat_id);
+
+        dialogue
+            .update(AskDialogue::Chatting(chat(
+                Clock::now().and_utc().timestamp(),
+            )))
+            .await
+            .unwrap();
+
+        assert!(active_chat(&dialogue).await.unwrap().is_some());
+
+        dialogue
+            .update(AskDialogue::Chatting(chat(0)))
+            .await
+            .unwrap();
+
+        assert!(active_chat(&dialogue).await.unwrap().is_none());
+        assert!(dialogue.get().await.unwrap().is_none());
+    }
+}
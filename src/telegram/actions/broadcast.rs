@@ -33,19 +33,39 @@ pub async fn handle(
         return Ok(HandleStatus::Handled);
     };
 
+    let (sent, errors) = broadcast(app, locale, |chat_id| async move {
+        app.bot()
+            .copy_message(chat_id, reply.chat.id, reply.id)
+            .await?;
+
+        Ok(())
+    })
+    .await?;
+
+    let message = formatdoc!("Sent to {sent} users. Errors {errors}");
+
+    app.bot().send_message(m.chat.id, message).await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+/// Sends message to every user with given locale, returns amount of sent messages and errors
+pub async fn broadcast<F, Fut>(
+    app: &App,
+    locale: UserLocale,
+    deliver: F,
+) -> anyhow::Result<(usize, usize)>
+where
+    F: Fn(ChatId) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
     let users = UserService::get_users_for_locale(app.db(), locale).await?;
 
     let mut errors = 0;
     let mut sent = 0;
 
     for user in &users {
-        let send_fn = || async {
-            app.bot()
-                .copy_message(ChatId(user.id.parse()?), reply.chat.id, reply.id)
-                .await?;
-
-            anyhow::Ok(())
-        };
+        let send_fn = || async { deliver(ChatId(user.id.parse()?)).await };
 
         let res = send_fn.retry(ExponentialBuilder::default()).await;
 
@@ -58,9 +78,5 @@ pub async fn handle(
         }
     }
 
-    let message = formatdoc!("Sent to {sent} users. Errors {errors}");
-
-    app.bot().send_message(m.chat.id, message).await?;
-
-    Ok(HandleStatus::Handled)
+    Ok((sent, errors))
 }
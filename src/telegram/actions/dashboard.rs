@@ -0,0 +1,91 @@
+use teloxide::payloads::SendMessageSetters as _;
+use teloxide::prelude::Requester as _;
+use teloxide::types::{
+    ChatId,
+    InlineKeyboardButton,
+    InlineKeyboardButtonKind,
+    InlineKeyboardMarkup,
+};
+
+use crate::app::App;
+use crate::services::WebSessionService;
+use crate::telegram::handlers::HandleStatus;
+use crate::user::UserState;
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle(app: &App, state: &UserState, chat_id: ChatId) -> anyhow::Result<HandleStatus> {
+    let Some(base_url) = app.server_public_url() else {
+        app.bot()
+            .send_message(
+                chat_id,
+                t!("dashboard.unavailable", locale = state.locale()),
+            )
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    };
+
+    let token =
+        WebSessionService::create_login_token(&mut app.redis_conn().await?, state.user_id())
+            .await?;
+
+    let url = format!("{base_url}/dashboard/login?token={token}");
+
+    app.bot()
+        .send_message(
+            chat_id,
+            t!(
+                "dashboard.link",
+                locale = state.locale(),
+                minutes = WebSessionService::login_token_ttl().num_minutes(),
+            ),
+        )
+        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton {
+                text: t!("dashboard.open", locale = state.locale()).into(),
+                kind: InlineKeyboardButtonKind::Url(url.parse()?),
+            },
+        ]]))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_admin(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+) -> anyhow::Result<HandleStatus> {
+    let Some(base_url) = app.server_public_url() else {
+        app.bot()
+            .send_message(chat_id, "SERVER_PUBLIC_URL is not configured")
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    };
+
+    let token =
+        WebSessionService::create_login_token(&mut app.redis_conn().await?, state.user_id())
+            .await?;
+
+    let url = format!("{base_url}/admin/login?token={token}");
+
+    app.bot()
+        .send_message(
+            chat_id,
+            format!(
+                "Admin panel link works once and expires in {minutes} minutes",
+                minutes = WebSessionService::login_token_ttl().num_minutes(),
+            ),
+        )
+        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton {
+                text: "Open admin panel".into(),
+                kind: InlineKeyboardButtonKind::Url(url.parse()?),
+            },
+        ]]))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
@@ -0,0 +1,105 @@
+use teloxide::prelude::*;
+use teloxide::sugar::request::RequestLinkPreviewExt as _;
+use teloxide::utils::html;
+
+use crate::app::App;
+use crate::queue::dead_letter::{self, DeadLetter};
+use crate::queue::track_check::{self, TrackCheckQueueTask};
+use crate::telegram::commands_admin::AdminCommandDisplay;
+use crate::telegram::handlers::HandleStatus;
+
+const LIST_LIMIT: usize = 20;
+/// Telegram message limit is 4096, leave some space for the rest
+const ERROR_MAX_LEN: usize = 3000;
+
+fn summary(letter: &DeadLetter<TrackCheckQueueTask>) -> String {
+    format!(
+        "<code>{id}</code> {failed_at} user <code>{user_id}</code>\n{track}",
+        id = letter.id,
+        failed_at = letter.failed_at.format("%Y-%m-%d %H:%M:%S"),
+        user_id = letter.task.user_id(),
+        track = letter.task.track().track_tg_link(),
+    )
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn handle_list(app: &'static App, m: &Message) -> anyhow::Result<HandleStatus> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    let depth = dead_letter::depth(&mut redis_conn, track_check::DEAD_LETTER_QUEUE).await?;
+    let letters: Vec<DeadLetter<TrackCheckQueueTask>> =
+        dead_letter::list(&mut redis_conn, track_check::DEAD_LETTER_QUEUE, LIST_LIMIT).await?;
+
+    let text = if letters.is_empty() {
+        "No dead letters".to_owned()
+    } else {
+        let letters: Vec<_> = letters.iter().map(summary).collect();
+
+        format!(
+            "Dead letters of track checks: <b>{depth}</b>\n\n{letters}\n\nInspect with /{show} and replay with /{replay}",
+            letters = letters.join("\n\n"),
+            show = AdminCommandDisplay::DeadLetter,
+            replay = AdminCommandDisplay::ReplayDeadLetter,
+        )
+    };
+
+    app.bot()
+        .send_message(m.chat.id, text)
+        .disable_link_preview(true)
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn handle_show(app: &'static App, m: &Message, id: &str) -> anyhow::Result<HandleStatus> {
+    let letter: Option<DeadLetter<TrackCheckQueueTask>> = dead_letter::get(
+        &mut app.redis_conn().await?,
+        track_check::DEAD_LETTER_QUEUE,
+        id,
+    )
+    .await?;
+
+    let text = match letter {
+        None => format!("Dead letter <code>{}</code> not found", html::escape(id)),
+        Some(letter) => {
+            let error: String = letter.error.chars().take(ERROR_MAX_LEN).collect();
+
+            format!(
+                "{summary}\nAttempts: {attempts}\n\n<pre>{error}</pre>",
+                summary = summary(&letter),
+                attempts = letter.attempts,
+                error = html::escape(&error),
+            )
+        },
+    };
+
+    app.bot()
+        .send_message(m.chat.id, text)
+        .disable_link_preview(true)
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+/// Replays one letter by id or all of them with `all`
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn handle_replay(
+    app: &'static App,
+    m: &Message,
+    id: &str,
+) -> anyhow::Result<HandleStatus> {
+    let text = if id == "all" {
+        let replayed = track_check::replay_all(app).await?;
+
+        format!("Replayed {replayed} dead letters")
+    } else if track_check::replay(app, id).await? {
+        format!("Dead letter <code>{id}</code> replayed")
+    } else {
+        format!("Dead letter <code>{}</code> not found", html::escape(id))
+    };
+
+    app.bot().send_message(m.chat.id, text).await?;
+
+    Ok(HandleStatus::Handled)
+}
@@ -13,6 +13,7 @@ use teloxide::types::InlineKeyboardMarkup;
 
 use crate::app::App;
 use crate::entity::prelude::*;
+use crate::infrastructure::error::RustifyError;
 use crate::lyrics::SearchResult as _;
 use crate::profanity::LineResult;
 use crate::services::{
@@ -23,6 +24,7 @@ use crate::services::{
     WordStatsService,
 };
 use crate::spotify::{CurrentlyPlaying, ShortTrack};
+use crate::telegram::actions;
 use crate::telegram::handlers::HandleStatus;
 use crate::telegram::inline_buttons::InlineButtons;
 use crate::telegram::utils::link_preview_small_top;
@@ -64,7 +66,7 @@ pub async fn handle_current(
 
             return Ok(HandleStatus::Handled);
         },
-        CurrentlyPlaying::Ok(track, _) => *track,
+        CurrentlyPlaying::Ok(track, ..) => *track,
     };
 
     common(app, state, chat_id, track).await
@@ -159,7 +161,7 @@ async fn common(
     let features = match state
         .spotify()
         .await
-        .track_features(track.raw_id().clone())
+        .request(|spotify| spotify.track_features(track.raw_id().clone()))
         .await
     {
         Ok(features) => {
@@ -218,7 +220,7 @@ async fn common(
         let artists = match state
             .spotify()
             .await
-            .artists(artist_ids.iter().cloned())
+            .request(|spotify| spotify.artists(artist_ids.iter().cloned()))
             .await
         {
             // HACK: 403 "Spotify is unavailable in this country" error
@@ -230,7 +232,7 @@ async fn common(
             Err(err) => {
                 tracing::error!("Err from artists fetching {:?}", err);
 
-                return Err(err.into());
+                return Err(RustifyError::from_spotify(err).await.into());
             },
             Ok(artists) => artists,
         };
@@ -339,6 +341,13 @@ async fn common(
             InlineButtons::Analyze(track.id().to_owned())
                 .into_inline_keyboard_button(state.locale()),
         ]);
+
+        if actions::translate::is_translatable(hit.language(), state.locale()) {
+            keyboard.push(vec![
+                InlineButtons::Translate(track.id().to_owned())
+                    .into_inline_keyboard_button(state.locale()),
+            ]);
+        }
     }
 
     app.bot()
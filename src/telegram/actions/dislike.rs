@@ -6,7 +6,15 @@ use teloxide::types::{InlineKeyboardMarkup, ReplyMarkup};
 use super::super::inline_buttons::InlineButtons;
 use crate::app::App;
 use crate::entity::prelude::*;
-use crate::services::{RateLimitAction, RateLimitOutput, RateLimitService, TrackStatusService};
+use crate::queue::webhook;
+use crate::services::{
+    RateLimitAction,
+    RateLimitOutput,
+    RateLimitService,
+    RecommendasionFeedbackService,
+    TrackStatusService,
+    WebhookEvent,
+};
 use crate::spotify::{CurrentlyPlaying, ShortTrack};
 use crate::telegram::actions;
 use crate::telegram::handlers::HandleStatus;
@@ -55,12 +63,32 @@ pub async fn handle(
 
             return Ok(HandleStatus::Handled);
         },
-        CurrentlyPlaying::Ok(track, _) => track,
+        CurrentlyPlaying::Ok(track, ..) => track,
     };
 
     TrackStatusService::set_status(app.db(), state.user_id(), track.id(), TrackStatus::Disliked)
         .await?;
 
+    webhook::emit(
+        app,
+        state.user_id(),
+        WebhookEvent::TrackDisliked,
+        webhook::track_data(&track),
+    )
+    .await;
+
+    if let Err(err) = RecommendasionFeedbackService::record_reaction(
+        app.db(),
+        &mut app.redis_conn().await?,
+        state.user_id(),
+        &track,
+        RecommendasionOutcome::Disliked,
+    )
+    .await
+    {
+        tracing::error!(err = ?err, "Failed to record Recommendasion feedback");
+    }
+
     let keyboard =
         InlineButtons::from_track_status(TrackStatus::Disliked, track.id(), state.locale());
 
@@ -92,6 +120,26 @@ pub async fn handle_inline(
     TrackStatusService::set_status(app.db(), state.user_id(), track_id, TrackStatus::Disliked)
         .await?;
 
+    webhook::emit(
+        app,
+        state.user_id(),
+        WebhookEvent::TrackDisliked,
+        webhook::track_data(&track),
+    )
+    .await;
+
+    if let Err(err) = RecommendasionFeedbackService::record_reaction(
+        app.db(),
+        &mut app.redis_conn().await?,
+        state.user_id(),
+        &track,
+        RecommendasionOutcome::Disliked,
+    )
+    .await
+    {
+        tracing::error!(err = ?err, "Failed to record Recommendasion feedback");
+    }
+
     let keyboard =
         InlineButtons::from_track_status(TrackStatus::Disliked, track.id(), state.locale());
 
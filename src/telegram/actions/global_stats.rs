@@ -6,7 +6,13 @@ use teloxide::types::ReplyParameters;
 
 use crate::app::App;
 use crate::entity::prelude::*;
-use crate::services::{TrackLanguageStatsService, TrackStatusService, UserService, UserStats};
+use crate::services::{
+    SpotifyRateLimitService,
+    TrackLanguageStatsService,
+    TrackStatusService,
+    UserService,
+    UserStats,
+};
 use crate::telegram::handlers::HandleStatus;
 use crate::user::UserState;
 
@@ -32,6 +38,7 @@ pub async fn handle(
         lyrics_musixmatch,
         lyrics_lrclib,
         lyrics_analyzed,
+        ..
     } = UserService::get_stats(app.db(), None).await?;
 
     let user_locales = UserService::count_users_locales(app.db())
@@ -76,6 +83,23 @@ pub async fn handle(
         })
         .join("\n");
 
+    let budget = SpotifyRateLimitService::budget(
+        &mut app.redis_conn().await?,
+        app.spotify_rate_limiter().client_id(),
+    )
+    .await?;
+
+    let spotify_budget = format!(
+        "• Available <code>{tokens:.0}/{capacity:.0}</code> requests, refill <code>{refill}/s</code>\n• Retry-After {retry_after}",
+        tokens = budget.tokens,
+        capacity = budget.capacity,
+        refill = SpotifyRateLimitService::REFILL_PER_SECOND,
+        retry_after = budget.retry_after.map_or_else(
+            || "<code>none</code>".into(),
+            |retry_after| format!("<code>{}s</code>", retry_after.num_seconds())
+        ),
+    );
+
     let text = formatdoc!(
         r"
             📉 <b>Global stats</b> 📈
@@ -101,6 +125,10 @@ pub async fn handle(
             • MusixMatch <code>{lyrics_musixmatch} ({lyrics_musixmatch_ratio:.2}%)</code>
             • LrcLib <code>{lyrics_lrclib} ({lyrics_lrclib_ratio:.2}%)</code>
 
+            <b>Spotify API budget</b>
+
+            {spotify_budget}
+
             <b>Locales stats</b>
 
             {user_locales}
@@ -7,6 +7,7 @@ use super::super::inline_buttons::InlineButtons;
 use crate::app::App;
 use crate::entity::prelude::*;
 use crate::services::TrackStatusService;
+use crate::telegram::actions;
 use crate::telegram::utils::link_preview_small_top;
 use crate::user::UserState;
 
@@ -14,10 +15,17 @@ use crate::user::UserState;
 pub async fn handle_inline(
     app: &'static App,
     state: &UserState,
-    _q: CallbackQuery,
+    q: CallbackQuery,
     m: Message,
     track_id: &str,
 ) -> anyhow::Result<()> {
+    // Ignored tracks aren't checked anymore
+    if actions::kid_safe::check_settings_locked(app, state, m.chat.id).await? {
+        app.bot().answer_callback_query(q.id).await?;
+
+        return Ok(());
+    }
+
     let track = state
         .spotify()
         .await
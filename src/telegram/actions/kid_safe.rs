@@ -0,0 +1,138 @@
+use teloxide::prelude::*;
+use teloxide::types::ChatId;
+
+use crate::app::App;
+use crate::entity::prelude::UserProfile;
+use crate::services::{RateLimitAction, RateLimitOutput, RateLimitService, UserService};
+use crate::spotify::scopes::Feature;
+use crate::telegram::actions;
+use crate::telegram::commands::UserCommandDisplay;
+use crate::telegram::handlers::HandleStatus;
+use crate::user::UserState;
+use crate::utils::DurationPrettyFormat as _;
+
+/// Kid-safe profile turns on the strictest checks and locks settings with a PIN
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle(
+    app: &'static App,
+    state: &UserState,
+    m: &Message,
+    pin: &str,
+) -> anyhow::Result<HandleStatus> {
+    let pin = pin.trim();
+
+    if pin.is_empty() {
+        app.bot()
+            .send_message(
+                m.chat.id,
+                t!(
+                    "kid-safe.usage",
+                    locale = state.locale(),
+                    command = UserCommandDisplay::KidSafe
+                ),
+            )
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    }
+
+    // Child shouldn't find the PIN in the chat history
+    if let Err(err) = app.bot().delete_message(m.chat.id, m.id).await {
+        tracing::warn!(err = ?err, "Failed to delete message with PIN");
+    }
+
+    if state.user().profile.is_kid_safe() {
+        return turn_off(app, state, m.chat.id, pin).await;
+    }
+
+    if !UserService::is_valid_settings_pin(pin) {
+        app.bot()
+            .send_message(
+                m.chat.id,
+                t!("kid-safe.invalid-pin", locale = state.locale()),
+            )
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    }
+
+    UserService::set_profile(app.db(), state.user_id(), UserProfile::KidSafe, Some(pin)).await?;
+
+    app.bot()
+        .send_message(m.chat.id, t!("kid-safe.on", locale = state.locale()))
+        .await?;
+
+    actions::login::ensure_features(app, state, &[Feature::AutoSkip]).await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+async fn turn_off(
+    app: &'static App,
+    state: &UserState,
+    chat_id: ChatId,
+    pin: &str,
+) -> anyhow::Result<HandleStatus> {
+    if let RateLimitOutput::NeedToWait(duration) = RateLimitService::enforce_limit(
+        &mut app.redis_conn().await?,
+        state.user_id(),
+        RateLimitAction::SettingsPin,
+    )
+    .await?
+    {
+        app.bot()
+            .send_message(
+                chat_id,
+                t!(
+                    "rate-limit.settings-pin",
+                    duration = duration.pretty_format(),
+                    locale = state.locale()
+                ),
+            )
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    }
+
+    let pin_hash = UserService::settings_pin_hash(state.user_id(), pin);
+
+    if state.user().settings_pin_hash.as_deref() != Some(pin_hash.as_str()) {
+        app.bot()
+            .send_message(chat_id, t!("kid-safe.wrong-pin", locale = state.locale()))
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    }
+
+    UserService::set_profile(app.db(), state.user_id(), UserProfile::Standard, None).await?;
+
+    app.bot()
+        .send_message(chat_id, t!("kid-safe.off", locale = state.locale()))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+/// Tells user that settings are locked by kid-safe profile. Returns `true` if they are
+pub async fn check_settings_locked(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+) -> anyhow::Result<bool> {
+    if !state.user().profile.is_kid_safe() {
+        return Ok(false);
+    }
+
+    app.bot()
+        .send_message(
+            chat_id,
+            t!(
+                "kid-safe.settings-locked",
+                locale = state.locale(),
+                command = UserCommandDisplay::KidSafe
+            ),
+        )
+        .await?;
+
+    Ok(true)
+}
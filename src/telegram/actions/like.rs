@@ -2,8 +2,15 @@ use rspotify::prelude::OAuthClient as _;
 use teloxide::prelude::*;
 
 use crate::app::App;
-use crate::services::{RateLimitAction, RateLimitOutput, RateLimitService};
-use crate::spotify::CurrentlyPlaying;
+use crate::entity::prelude::RecommendasionOutcome;
+use crate::services::{
+    RateLimitAction,
+    RateLimitOutput,
+    RateLimitService,
+    RecommendasionFeedbackService,
+};
+use crate::spotify::scopes::Feature;
+use crate::spotify::{ClientResultExt as _, CurrentlyPlaying};
 use crate::telegram::actions;
 use crate::telegram::handlers::HandleStatus;
 use crate::telegram::keyboards::StartKeyboard;
@@ -23,6 +30,10 @@ pub async fn handle(
         return Ok(HandleStatus::Handled);
     }
 
+    if !actions::login::ensure_features(app, state, &[Feature::Collection]).await? {
+        return Ok(HandleStatus::Handled);
+    }
+
     let mut redis_conn = app.redis_conn().await?;
 
     if let RateLimitOutput::NeedToWait(duration) =
@@ -52,15 +63,29 @@ pub async fn handle(
 
             return Ok(HandleStatus::Handled);
         },
-        CurrentlyPlaying::Ok(track, _) => track,
+        CurrentlyPlaying::Ok(track, ..) => track,
     };
 
     state
         .spotify()
         .await
-        .current_user_saved_tracks_add([track.raw_id().clone()])
+        .request(|spotify| spotify.current_user_saved_tracks_add([track.raw_id().clone()]))
+        .await
+        .typed()
         .await?;
 
+    if let Err(err) = RecommendasionFeedbackService::record_reaction(
+        app.db(),
+        &mut redis_conn,
+        state.user_id(),
+        &track,
+        RecommendasionOutcome::Liked,
+    )
+    .await
+    {
+        tracing::error!(err = ?err, "Failed to record Recommendasion feedback");
+    }
+
     app.bot()
         .send_message(m.chat.id, format!("Liked {}", track.track_tg_link()))
         .reply_markup(StartKeyboard::markup(state.locale()))
@@ -7,30 +7,80 @@ use teloxide::types::{
 };
 
 use crate::app::App;
+use crate::spotify::scopes::Feature;
 use crate::telegram::handlers::HandleStatus;
 use crate::user::UserState;
 
+fn login_markup(url: &str, locale: &str) -> anyhow::Result<ReplyMarkup> {
+    Ok(ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(
+        #[rustfmt::skip]
+        vec![
+            vec![InlineKeyboardButton {
+                text: t!("login.button", locale = locale).into(),
+                kind: InlineKeyboardButtonKind::Url(url.parse()?),
+            }]
+        ],
+    )))
+}
+
 #[tracing::instrument(skip_all)]
 pub async fn send_login_invite(
     app: &'static App,
     state: &UserState,
 ) -> anyhow::Result<HandleStatus> {
-    let url = app.spotify_manager().get_authorize_url(state).await?;
+    let url = app
+        .spotify_manager()
+        .get_authorize_url(state, &Feature::enabled_for(state.user()))
+        .await?;
+
     app.bot()
         .send_message(
             state.chat_id()?,
             t!("login.invite", locale = state.locale()),
         )
-        .reply_markup(ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(
-            #[rustfmt::skip]
-            vec![
-                vec![InlineKeyboardButton {
-                    text: t!("login.button", locale = state.locale()).into(),
-                    kind: InlineKeyboardButtonKind::Url(url.parse()?),
-                }]
-            ],
-        )))
+        .reply_markup(login_markup(&url, state.locale())?)
         .await?;
 
     Ok(HandleStatus::Handled)
 }
+
+/// Checks that user granted Spotify scopes for features, otherwise asks to extend the consent.
+/// Returns `false` when consent is requested
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id(), ?features))]
+pub async fn ensure_features(
+    app: &'static App,
+    state: &UserState,
+    features: &[Feature],
+) -> anyhow::Result<bool> {
+    if state.has_features(features).await {
+        return Ok(true);
+    }
+
+    let mut requested = Feature::enabled_for(state.user());
+    requested.extend_from_slice(features);
+
+    let url = app
+        .spotify_manager()
+        .get_authorize_url(state, &requested)
+        .await?;
+
+    let features = features
+        .iter()
+        .map(|feature| format!("• {}", feature.localize(state.locale())))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    app.bot()
+        .send_message(
+            state.chat_id()?,
+            t!(
+                "login.consent-required",
+                locale = state.locale(),
+                features = features
+            ),
+        )
+        .reply_markup(login_markup(&url, state.locale())?)
+        .await?;
+
+    Ok(false)
+}
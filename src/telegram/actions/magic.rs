@@ -1,4 +1,3 @@
-use futures::StreamExt as _;
 use rand::seq::SliceRandom as _;
 use rspotify::model::{Id as _, UserId};
 use rspotify::prelude::{BaseClient as _, OAuthClient as _};
@@ -13,7 +12,8 @@ use teloxide::types::{CallbackQuery, ChatId, InlineKeyboardMarkup, Message, Repl
 
 use crate::app::App;
 use crate::services::{RateLimitAction, RateLimitOutput, RateLimitService, UserService};
-use crate::spotify::ShortPlaylist;
+use crate::spotify::scopes::Feature;
+use crate::spotify::{ClientResultExt as _, PAGE_LIMIT, ShortPlaylist};
 use crate::telegram::actions;
 use crate::telegram::handlers::HandleStatus;
 use crate::telegram::inline_buttons::InlineButtons;
@@ -32,29 +32,43 @@ async fn get_playlist(
 
     let spotify = state.spotify().await;
 
-    let mut playlists_stream = spotify.user_playlists(spotify_user_id.clone());
-
     if let Some(magic_playlist_id) = magic_playlist_id {
-        while let Some(playlist) = playlists_stream.next().await {
-            let playlist = playlist?;
-            if playlist.id.id() == magic_playlist_id {
-                spotify
-                    .playlist_replace_items(playlist.id.clone(), [])
-                    .await?;
-
-                return Ok(playlist.into());
-            }
+        let playlists = spotify
+            .paginate(None, |spotify, offset| {
+                spotify.user_playlists_manual(
+                    spotify_user_id.clone(),
+                    Some(PAGE_LIMIT),
+                    Some(offset),
+                )
+            })
+            .await?;
+
+        if let Some(playlist) = playlists
+            .into_iter()
+            .find(|playlist| playlist.id.id() == magic_playlist_id)
+        {
+            spotify
+                .request(|spotify| spotify.playlist_replace_items(playlist.id.clone(), []))
+                .await
+                .typed()
+                .await?;
+
+            return Ok(playlist.into());
         }
     }
 
     let playlist = spotify
-        .user_playlist_create(
-            spotify_user_id,
-            playlist_name,
-            Some(false),
-            Some(false),
-            Some("Autogenerated Playlist by Rustify Bot"),
-        )
+        .request(|spotify| {
+            spotify.user_playlist_create(
+                spotify_user_id,
+                playlist_name,
+                Some(false),
+                Some(false),
+                Some("Autogenerated Playlist by Rustify Bot"),
+            )
+        })
+        .await
+        .typed()
         .await?;
 
     Ok(playlist.into())
@@ -73,6 +87,10 @@ pub async fn handle_inline(
         return Ok(());
     }
 
+    if !actions::login::ensure_features(app, state, &[Feature::MagicPlaylist]).await? {
+        return Ok(());
+    }
+
     let mut redis_conn = app.redis_conn().await?;
 
     if let RateLimitOutput::NeedToWait(duration) =
@@ -145,17 +163,17 @@ async fn generate_playlist(
     state: &UserState,
     spotify_user: rspotify::model::PrivateUser,
 ) -> Result<ShortPlaylist, anyhow::Error> {
-    let mut track_ids = {
-        let mut track_ids = vec![];
+    let mut track_ids: Vec<_> = {
         let spotify = state.spotify().await;
-        let mut saved_tracks = spotify.current_user_saved_tracks(None);
-        while let Some(track) = saved_tracks.next().await {
-            let track = track?;
-            if let Some(track_id) = track.track.id {
-                track_ids.push(track_id.into());
-            }
-        }
-        track_ids
+
+        spotify
+            .paginate(None, |spotify, offset| {
+                spotify.current_user_saved_tracks_manual(None, Some(PAGE_LIMIT), Some(offset))
+            })
+            .await?
+            .into_iter()
+            .filter_map(|track| track.track.id.map(Into::into))
+            .collect()
     };
 
     track_ids.shuffle(&mut rand::rng());
@@ -173,7 +191,11 @@ async fn generate_playlist(
 
     for chunk in track_ids.chunks(100) {
         spotify
-            .playlist_add_items(playlist.id().clone(), chunk.iter().cloned(), None)
+            .request(|spotify| {
+                spotify.playlist_add_items(playlist.id().clone(), chunk.iter().cloned(), None)
+            })
+            .await
+            .typed()
             .await?;
     }
 
@@ -192,6 +214,10 @@ pub async fn handle(
         return Ok(HandleStatus::Handled);
     }
 
+    if !actions::login::ensure_features(app, state, &[Feature::MagicPlaylist]).await? {
+        return Ok(HandleStatus::Handled);
+    }
+
     let header = t!("magic.header", locale = state.locale());
 
     app.bot()
@@ -212,3 +238,103 @@ pub async fn handle(
 
     Ok(HandleStatus::Handled)
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use rspotify::model::Type as SpotifyType;
+
+    use super::*;
+    use crate::entity::prelude::UserAISlopDetection;
+    use crate::spotify::mock::track;
+    use crate::testing::TestApp;
+    use crate::tick;
+
+    const TRACK_IDS: [&str; 3] = [
+        "4uLU6hMCjMI75M1A2tKUQC",
+        "7GhIk7Il098yCjg4BQjzvb",
+        "0VjIjW4GlUZAMYd2vXMi3b",
+    ];
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_magic_playlist_flow() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        let state = test.login(&user_id).await.unwrap();
+
+        {
+            let mut spotify = test.spotify.state();
+
+            for id in TRACK_IDS {
+                spotify.add_track(track(id, 200));
+                spotify.saved_tracks.push(id.to_owned());
+            }
+        }
+
+        let spotify_user = state.spotify_user().await.unwrap().unwrap();
+        let playlist = generate_playlist(test.app, &state, spotify_user.clone())
+            .await
+            .unwrap();
+
+        let mut track_ids = test.spotify.state().playlists[playlist.id().id()]
+            .track_ids
+            .clone();
+        track_ids.sort_unstable();
+
+        let mut expected = TRACK_IDS.map(str::to_owned).to_vec();
+        expected.sort_unstable();
+
+        assert_eq!(track_ids, expected);
+
+        // Regenerating reuses the playlist
+        let state = test.app.user_state(&user_id).await.unwrap();
+
+        assert_eq!(
+            state.user().magic_playlist.as_deref(),
+            Some(playlist.id().id())
+        );
+
+        let regenerated = generate_playlist(test.app, &state, spotify_user)
+            .await
+            .unwrap();
+
+        assert_eq!(regenerated.id(), playlist.id());
+        assert_eq!(test.spotify.state().playlists.len(), 1);
+        assert_eq!(
+            test.spotify.state().playlists[playlist.id().id()]
+                .track_ids
+                .len(),
+            TRACK_IDS.len()
+        );
+
+        // Listened track is removed from the playlist, track checks aren't needed for it
+        UserService::set_cfg_check_profanity(test.app.db(), &user_id, false)
+            .await
+            .unwrap();
+        UserService::set_cfg_ai_slop_detection(
+            test.app.db(),
+            &user_id,
+            UserAISlopDetection::Ignore,
+        )
+        .await
+        .unwrap();
+
+        {
+            let mut spotify = test.spotify.state();
+
+            spotify.play(TRACK_IDS[0], Duration::seconds(10));
+            spotify.playing.as_mut().unwrap().context =
+                Some((SpotifyType::Playlist, playlist.id().uri()));
+        }
+
+        tick::user::check(test.app, &user_id).await.unwrap();
+
+        let track_ids = test.spotify.state().playlists[playlist.id().id()]
+            .track_ids
+            .clone();
+
+        assert_eq!(track_ids.len(), TRACK_IDS.len() - 1);
+        assert!(!track_ids.contains(&TRACK_IDS[0].to_owned()));
+    }
+}
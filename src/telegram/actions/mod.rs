@@ -1,11 +1,16 @@
 pub mod admin_users;
 pub mod ai_slop_detection;
 pub mod analyze;
+pub mod api_token;
+pub mod ask;
 pub mod broadcast;
+pub mod dashboard;
+pub mod dead_letters;
 pub mod details;
 pub mod dislike;
 pub mod global_stats;
 pub mod ignore;
+pub mod kid_safe;
 pub mod language;
 pub mod like;
 pub mod login;
@@ -16,5 +21,8 @@ pub mod skippage;
 pub mod song_links;
 pub mod start;
 pub mod stats;
+pub mod translate;
 pub mod user_word_whitelist;
+pub mod web_app;
+pub mod webhook;
 pub mod word_definition;
@@ -12,38 +12,53 @@ use async_openai::types::chat::{
     FunctionObjectArgs,
 };
 use backon::{ExponentialBuilder, Retryable as _};
-use futures::StreamExt as _;
+use chrono::{Months, NaiveDate};
 use indoc::formatdoc;
 use itertools::Itertools as _;
-use rspotify::model::SearchType;
-use rspotify::prelude::{BaseClient as _, OAuthClient as _};
+use rspotify::model::{PlayableId, PlayableItem, SearchType};
+use rspotify::prelude::{BaseClient as _, OAuthClient};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use strum::IntoEnumIterator as _;
 use teloxide::payloads::{
     AnswerCallbackQuerySetters as _,
+    EditMessageReplyMarkupSetters as _,
     EditMessageTextSetters as _,
     SendMessageSetters as _,
 };
 use teloxide::prelude::Requester as _;
 use teloxide::sugar::bot::BotMessagesExt as _;
 use teloxide::sugar::request::RequestLinkPreviewExt as _;
-use teloxide::types::{CallbackQuery, ChatId, InlineKeyboardMarkup, Message, ReplyMarkup};
+use teloxide::types::{
+    CallbackQuery,
+    ChatId,
+    InlineKeyboardButton,
+    InlineKeyboardButtonKind,
+    InlineKeyboardMarkup,
+    Message,
+    ReplyMarkup,
+};
+use teloxide::utils::html;
 
-use crate::app::{AIConfig, App};
-use crate::entity::prelude::TrackStatus;
+use crate::ai::{AIConfig, AIFeature};
+use crate::app::App;
+use crate::entity::prelude::{RecommendasionFeedbackModel, TrackStatus};
 use crate::services::{
-    RateLimitAction,
-    RateLimitOutput,
-    RateLimitService,
+    AIQuotaOutput,
+    AIUsageService,
+    RecommendasionFeedbackService,
+    RecommendasionMode,
+    RecommendasionOptions,
     RecommendasionService,
     TrackStatusService,
 };
-use crate::spotify::ShortTrack;
+use crate::spotify::scopes::Feature;
+use crate::spotify::{ClientResultExt as _, PAGE_LIMIT, ShortPlaylist, ShortTrack};
 use crate::telegram::actions;
 use crate::telegram::handlers::HandleStatus;
 use crate::telegram::inline_buttons::InlineButtons;
 use crate::user::{SpotifyWrapperType, UserState};
-use crate::utils::{DurationPrettyFormat as _, StringUtils as _};
+use crate::utils::{Clock, StringUtils as _};
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RecommendationsRaw {
@@ -96,6 +111,136 @@ impl Recommendations {
 pub struct UserData {
     pub liked: Vec<ShortTrack>,
     pub recommended: Vec<ShortTrack>,
+    /// Currently playing track, used by "similar to current track" mode
+    pub current: Option<ShortTrack>,
+    /// How previously recommended tracks worked out, latest first
+    pub feedback: Vec<RecommendasionFeedbackModel>,
+}
+
+#[must_use]
+pub fn get_keyboard(
+    options: &RecommendasionOptions,
+    locale: &str,
+) -> Vec<Vec<InlineKeyboardButton>> {
+    let mut keyboard: Vec<_> = RecommendasionMode::iter()
+        .map(|mode| {
+            InlineButtons::RecommendasionMode(mode, options.mode == mode)
+                .into_inline_keyboard_button(locale)
+        })
+        .chunks(2)
+        .into_iter()
+        .map(Iterator::collect)
+        .collect();
+
+    keyboard.push(vec![
+        InlineButtons::RecommendasionPlaylist(options.save_to_playlist)
+            .into_inline_keyboard_button(locale),
+    ]);
+
+    keyboard.push(vec![
+        InlineButtons::Recommendasion.into_inline_keyboard_button(locale),
+    ]);
+
+    keyboard
+}
+
+fn new_releases_since(today: NaiveDate) -> NaiveDate {
+    today.checked_sub_months(Months::new(12)).unwrap_or(today)
+}
+
+/// Instructions from user choices, `None` if there is nothing to steer
+fn steering_prompt(
+    options: &RecommendasionOptions,
+    current: Option<&ShortTrack>,
+    today: NaiveDate,
+) -> Option<String> {
+    let mut lines = vec![];
+
+    match options.mode {
+        RecommendasionMode::Favorites => {},
+        RecommendasionMode::DeepCuts => lines.push(
+            "MODE: Deep cuts. Recommend lesser-known album tracks and B-sides, avoid well-known hits"
+                .to_owned(),
+        ),
+        RecommendasionMode::SimilarToCurrent => {
+            if let Some(track) = current {
+                lines.push(format!(
+                    "MODE: Similar to the track I'm listening to now: {}. Match its style, mood and tempo",
+                    track.name_with_artists()
+                ));
+            }
+        },
+        RecommendasionMode::NewReleases => lines.push(format!(
+            "MODE: New releases. Only recommend tracks released after {}",
+            new_releases_since(today)
+        )),
+    }
+
+    if let Some(hint) = &options.hint {
+        lines.push(format!(
+            "MY WISHES (mood, genre or anything else, follow them unless they break the rules): {hint}"
+        ));
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+/// Amount of feedback entries of each kind given to AI
+const FEEDBACK_PROMPT_LIMIT: usize = 20;
+
+fn feedback_prompt(feedback: &[RecommendasionFeedbackModel]) -> Option<String> {
+    let (hits, misses): (Vec<_>, Vec<_>) = feedback
+        .iter()
+        .partition(|feedback| feedback.outcome.is_hit());
+
+    let mut lines = vec![];
+
+    if !hits.is_empty() {
+        lines.push("PREVIOUS RECOMMENDATIONS I ENJOYED (recommend more like these):".to_owned());
+        lines.extend(
+            hits.iter()
+                .take(FEEDBACK_PROMPT_LIMIT)
+                .map(|feedback| feedback.track_name.clone()),
+        );
+    }
+
+    if !misses.is_empty() {
+        lines.push(
+            "PREVIOUS RECOMMENDATIONS I SKIPPED OR DISLIKED (avoid similar tracks):".to_owned(),
+        );
+        lines.extend(
+            misses
+                .iter()
+                .take(FEEDBACK_PROMPT_LIMIT)
+                .map(|feedback| feedback.track_name.clone()),
+        );
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+fn welcome_text(options: &RecommendasionOptions, locale: &str) -> String {
+    let welcome = t!("recommendasion.welcome", locale = locale);
+
+    match &options.hint {
+        Some(hint) => format!(
+            "{welcome}\n\n{}",
+            t!(
+                "recommendasion.hint-current",
+                hint = html::escape(hint),
+                locale = locale
+            )
+        ),
+        None => welcome.into_owned(),
+    }
 }
 
 #[tracing::instrument(
@@ -115,6 +260,10 @@ pub async fn handle(
         return Ok(HandleStatus::Handled);
     }
 
+    if !actions::login::ensure_features(app, state, &[Feature::Recommendations]).await? {
+        return Ok(HandleStatus::Handled);
+    }
+
     let Some(_) = app.ai() else {
         app.bot()
             .send_message(
@@ -126,21 +275,189 @@ pub async fn handle(
         return Ok(HandleStatus::Handled);
     };
 
+    let options =
+        RecommendasionService::get_options(&mut app.redis_conn().await?, state.user_id()).await?;
+
     app.bot()
-        .send_message(
-            chat_id,
-            t!("recommendasion.welcome", locale = state.locale()),
-        )
+        .send_message(chat_id, welcome_text(&options, state.locale()))
         .reply_markup(ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(
-            vec![vec![
-                InlineButtons::Recommendasion.into_inline_keyboard_button(state.locale()),
-            ]],
+            get_keyboard(&options, state.locale()),
         )))
         .await?;
 
     Ok(HandleStatus::Handled)
 }
 
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id(), ?mode))]
+pub async fn handle_inline_mode(
+    app: &'static App,
+    state: &UserState,
+    q: CallbackQuery,
+    m: Message,
+    mode: RecommendasionMode,
+) -> anyhow::Result<()> {
+    app.bot().answer_callback_query(q.id).await?;
+
+    let mut redis_conn = app.redis_conn().await?;
+
+    let mut options = RecommendasionService::get_options(&mut redis_conn, state.user_id()).await?;
+
+    if options.mode != mode {
+        options.mode = mode;
+
+        RecommendasionService::save_options(&mut redis_conn, state.user_id(), &options).await?;
+
+        app.bot()
+            .edit_reply_markup(&m)
+            .reply_markup(InlineKeyboardMarkup::new(get_keyboard(
+                &options,
+                state.locale(),
+            )))
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id(), enabled))]
+pub async fn handle_inline_playlist(
+    app: &'static App,
+    state: &UserState,
+    q: CallbackQuery,
+    m: Message,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    app.bot().answer_callback_query(q.id).await?;
+
+    let mut redis_conn = app.redis_conn().await?;
+
+    let mut options = RecommendasionService::get_options(&mut redis_conn, state.user_id()).await?;
+
+    options.save_to_playlist = enabled;
+
+    RecommendasionService::save_options(&mut redis_conn, state.user_id(), &options).await?;
+
+    app.bot()
+        .edit_reply_markup(&m)
+        .reply_markup(InlineKeyboardMarkup::new(get_keyboard(
+            &options,
+            state.locale(),
+        )))
+        .await?;
+
+    if enabled {
+        actions::login::ensure_features(app, state, &[Feature::RecommendationsPlaylist]).await?;
+    }
+
+    Ok(())
+}
+
+/// Reply to a Recommendasion™ message sets free-text hint, "-" clears it
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_hint(
+    app: &'static App,
+    state: &UserState,
+    m: &Message,
+    reply: &Message,
+) -> anyhow::Result<HandleStatus> {
+    let is_recommendasion = reply.reply_markup().is_some_and(|markup| {
+        markup
+            .inline_keyboard
+            .iter()
+            .flatten()
+            .any(|button| match &button.kind {
+                InlineKeyboardButtonKind::CallbackData(data) => {
+                    matches!(data.parse(), Ok(InlineButtons::Recommendasion))
+                },
+                _ => false,
+            })
+    });
+
+    let Some(text) = m.text().filter(|_| is_recommendasion) else {
+        return Ok(HandleStatus::Skipped);
+    };
+
+    let hint = Some(text.trim())
+        .filter(|hint| *hint != "-")
+        .map(|hint| hint.chars_crop(RecommendasionService::HINT_MAX_LEN));
+
+    let mut redis_conn = app.redis_conn().await?;
+
+    let mut options = RecommendasionService::get_options(&mut redis_conn, state.user_id()).await?;
+
+    options.hint.clone_from(&hint);
+
+    RecommendasionService::save_options(&mut redis_conn, state.user_id(), &options).await?;
+
+    let text = hint.map_or_else(
+        || t!("recommendasion.hint-cleared", locale = state.locale()),
+        |hint| {
+            t!(
+                "recommendasion.hint-saved",
+                hint = html::escape(&hint),
+                locale = state.locale()
+            )
+        },
+    );
+
+    app.bot()
+        .send_message(m.chat.id, text)
+        .reply_markup(ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(
+            get_keyboard(&options, state.locale()),
+        )))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+#[allow(clippy::significant_drop_tightening)]
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+async fn save_to_playlist(
+    state: &UserState,
+    tracks: &[ShortTrack],
+) -> anyhow::Result<ShortPlaylist> {
+    let spotify = state.spotify().await;
+
+    let spotify_user = spotify
+        .request(OAuthClient::current_user)
+        .await
+        .typed()
+        .await?;
+
+    let playlist_name = format!("Recommendasion™ {}", Clock::now().format("%Y-%m-%d"));
+
+    let playlist = spotify
+        .request(|spotify| {
+            spotify.user_playlist_create(
+                spotify_user.id,
+                &playlist_name,
+                Some(false),
+                Some(false),
+                Some("Recommendations generated by Rustify Bot"),
+            )
+        })
+        .await
+        .typed()
+        .await?;
+
+    let track_ids: Vec<PlayableId<'_>> = tracks
+        .iter()
+        .map(|track| track.raw_id().clone().into())
+        .collect();
+
+    for chunk in track_ids.chunks(100) {
+        spotify
+            .request(|spotify| {
+                spotify.playlist_add_items(playlist.id.clone(), chunk.iter().cloned(), None)
+            })
+            .await
+            .typed()
+            .await?;
+    }
+
+    Ok(playlist.into())
+}
+
 #[tracing::instrument(
     skip_all,
     fields(
@@ -159,6 +476,10 @@ pub async fn handle_inline(
         return Ok(());
     }
 
+    if !actions::login::ensure_features(app, state, &[Feature::Recommendations]).await? {
+        return Ok(());
+    }
+
     let Some(config) = app.ai() else {
         app.bot()
             .edit_text(&m, t!("recommendasion.disabled", locale = state.locale()))
@@ -169,46 +490,86 @@ pub async fn handle_inline(
 
     let mut redis_conn = app.redis_conn().await?;
 
-    tracing::info!(user_id = state.user_id(), "User called Recommendasion");
+    let options = RecommendasionService::get_options(&mut redis_conn, state.user_id()).await?;
+
+    tracing::info!(user_id = state.user_id(), mode = ?options.mode, "User called Recommendasion");
+
+    if options.save_to_playlist
+        && !actions::login::ensure_features(app, state, &[Feature::RecommendationsPlaylist]).await?
+    {
+        return Ok(());
+    }
 
-    let Some(_) = state
+    let playback = state
         .spotify()
         .await
-        .current_playback(None, None::<&[rspotify::model::AdditionalType]>)
-        .await?
-    else {
+        .request(|spotify| {
+            spotify.current_playback(None, None::<&[rspotify::model::AdditionalType]>)
+        })
+        .await
+        .typed()
+        .await?;
+
+    // Playlist doesn't need an active device
+    if playback.is_none() && !options.save_to_playlist {
         app.bot()
             .edit_text(
                 &m,
                 t!("recommendasion.device-not-found", locale = state.locale()),
             )
-            .reply_markup(InlineKeyboardMarkup::new(vec![vec![
-                InlineButtons::Recommendasion.into_inline_keyboard_button(state.locale()),
-            ]]))
+            .reply_markup(InlineKeyboardMarkup::new(get_keyboard(
+                &options,
+                state.locale(),
+            )))
             .await?;
 
         return Ok(());
-    };
+    }
+
+    let current = playback.and_then(|playback| match playback.item {
+        Some(PlayableItem::Track(track)) if track.id.is_some() => Some(ShortTrack::new(track)),
+        _ => None,
+    });
 
-    if let RateLimitOutput::NeedToWait(duration) = RateLimitService::enforce_limit(
+    if options.mode == RecommendasionMode::SimilarToCurrent && current.is_none() {
+        app.bot()
+            .edit_text(
+                &m,
+                t!("recommendasion.nothing-playing", locale = state.locale()),
+            )
+            .reply_markup(InlineKeyboardMarkup::new(get_keyboard(
+                &options,
+                state.locale(),
+            )))
+            .await?;
+
+        return Ok(());
+    }
+
+    let reservation = match AIUsageService::reserve(
+        app.db(),
         &mut redis_conn,
+        config,
         state.user_id(),
-        RateLimitAction::Recommendasion,
+        AIFeature::Recommendasion,
     )
     .await?
     {
-        app.bot()
-            .answer_callback_query(q.id)
-            .text(t!(
-                "rate-limit.recommendasion",
-                duration = duration.pretty_format(),
-                locale = state.locale()
-            ))
-            .show_alert(true)
-            .await?;
+        AIQuotaOutput::Allowed(reservation) => reservation,
+        AIQuotaOutput::Exceeded { renews_on } => {
+            app.bot()
+                .answer_callback_query(q.id)
+                .text(t!(
+                    "rate-limit.ai-quota",
+                    date = renews_on.format("%Y-%m-%d"),
+                    locale = state.locale()
+                ))
+                .show_alert(true)
+                .await?;
 
-        return Ok(());
-    }
+            return Ok(());
+        },
+    };
 
     app.bot()
         .edit_text(
@@ -228,31 +589,62 @@ pub async fn handle_inline(
         .await?,
 
         liked: get_liked_tracks(&state.spotify().await).await?,
+
+        current,
+
+        feedback: RecommendasionFeedbackService::recent(app.db(), state.user_id(), 50).await?,
     };
 
     app.bot()
         .edit_text(&m, t!("recommendasion.ask-ai", locale = state.locale()))
         .await?;
 
-    let recommendations = get_recommendations(app, state, config, &mut user_data).await?;
+    let recommendations = get_recommendations(app, state, config, &options, &mut user_data).await;
+
+    AIUsageService::release(&mut redis_conn, reservation).await?;
+
+    let recommendations = recommendations?;
 
     let slop_rate = recommendations.slop.len() * 100
         / (recommendations.slop.len() + recommendations.recommended.len() + 1);
 
-    app.bot()
-        .edit_text(&m, t!("recommendasion.queue", locale = state.locale()))
-        .await?;
+    let recommendation_links: Vec<_> = recommendations
+        .recommended
+        .iter()
+        .map(ShortTrack::track_tg_link)
+        .collect();
 
-    let mut recommendation_links = vec![];
-    for recommendation in &recommendations.recommended {
-        state
-            .spotify()
-            .await
-            .add_item_to_queue(recommendation.raw_id().clone().into(), None)
+    let header = if options.save_to_playlist {
+        app.bot()
+            .edit_text(&m, t!("recommendasion.playlist", locale = state.locale()))
             .await?;
 
-        recommendation_links.push(recommendation.track_tg_link());
-    }
+        let playlist = save_to_playlist(state, &recommendations.recommended).await?;
+
+        t!(
+            "recommendasion.result-playlist",
+            playlist_url = playlist.url(),
+            locale = state.locale()
+        )
+    } else {
+        app.bot()
+            .edit_text(&m, t!("recommendasion.queue", locale = state.locale()))
+            .await?;
+
+        for recommendation in &recommendations.recommended {
+            state
+                .spotify()
+                .await
+                .request(|spotify| {
+                    spotify.add_item_to_queue(recommendation.raw_id().clone().into(), None)
+                })
+                .await
+                .typed()
+                .await?;
+        }
+
+        t!("recommendasion.result-queue", locale = state.locale())
+    };
 
     let mut disliked_links = recommendations.disliked_formatted();
 
@@ -269,6 +661,7 @@ pub async fn handle_inline(
 
     let text = t!(
         "recommendasion.result",
+        header = header,
         recommendation_links = recommendation_links.join("\n"),
         slop_rate = slop_rate,
         slop_links = disliked_links.join("\n"),
@@ -278,9 +671,10 @@ pub async fn handle_inline(
     app.bot()
         .edit_text(&m, text)
         .disable_link_preview(true)
-        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
-            InlineButtons::Recommendasion.into_inline_keyboard_button(state.locale()),
-        ]]))
+        .reply_markup(InlineKeyboardMarkup::new(get_keyboard(
+            &options,
+            state.locale(),
+        )))
         .await?;
 
     Ok(())
@@ -291,6 +685,7 @@ async fn get_recommendations(
     app: &'static App,
     state: &UserState,
     config: &AIConfig,
+    options: &RecommendasionOptions,
     user_data: &mut UserData,
 ) -> Result<Recommendations, anyhow::Error> {
     let attempts = 10;
@@ -299,7 +694,7 @@ async fn get_recommendations(
     let mut recommendations = Recommendations::default();
     for attempt in 0..attempts {
         let recommendations_result =
-            (|| get_recommendations_attempt(app, state, config, &*user_data))
+            (|| get_recommendations_attempt(app, state, config, options, &*user_data))
                 .retry(ExponentialBuilder::default())
                 .notify(|err: &anyhow::Error, dur: Duration| {
                     tracing::warn!(
@@ -338,16 +733,15 @@ async fn get_recommendations(
 async fn get_liked_tracks(
     spotify: &SpotifyWrapperType<'_>,
 ) -> Result<Vec<ShortTrack>, anyhow::Error> {
-    let mut saved_tracks = spotify.current_user_saved_tracks(None);
-    let mut liked_tracks: Vec<ShortTrack> = vec![];
-    while let Some(track) = saved_tracks.next().await {
-        let track = track?;
-        liked_tracks.push(track.track.into());
+    let liked_tracks = spotify
+        .paginate(Some(100), |spotify, offset| {
+            spotify.current_user_saved_tracks_manual(None, Some(PAGE_LIMIT), Some(offset))
+        })
+        .await?
+        .into_iter()
+        .map(|track| track.track.into())
+        .collect();
 
-        if liked_tracks.len() >= 100 {
-            break;
-        }
-    }
     Ok(liked_tracks)
 }
 
@@ -356,9 +750,13 @@ async fn get_recommendations_attempt(
     app: &App,
     state: &UserState,
     config: &AIConfig,
+    options: &RecommendasionOptions,
     user_data: &UserData,
 ) -> anyhow::Result<Recommendations> {
-    let recommendations_raw = get_raw_recommendations(config, user_data).await?;
+    let recommendations_raw =
+        get_raw_recommendations(app, state, config, options, user_data).await?;
+
+    let new_releases_since = new_releases_since(Clock::now().date());
 
     let mut recommendations = Recommendations::default();
 
@@ -369,17 +767,14 @@ async fn get_recommendations_attempt(
 
         let artist = track_recommendation.artist_name.chars_crop(100);
 
+        let query = format!("track:{track} artist:{artist}");
+
         let rspotify::model::SearchResult::Tracks(res) = state
             .spotify()
             .await
-            .search(
-                &format!("track:{track} artist:{artist}"),
-                SearchType::Track,
-                None,
-                None,
-                Some(1),
-                None,
-            )
+            .request(|spotify| spotify.search(&query, SearchType::Track, None, None, Some(1), None))
+            .await
+            .typed()
             .await?
         else {
             anyhow::bail!("Searching for tracks must return tracks")
@@ -401,6 +796,18 @@ async fn get_recommendations_attempt(
             continue;
         }
 
+        if state.user().skip_explicit() && track.is_explicit() {
+            continue;
+        }
+
+        if options.mode == RecommendasionMode::NewReleases
+            && track
+                .album_release_date()
+                .is_none_or(|date| date < new_releases_since)
+        {
+            continue;
+        }
+
         if user_data
             .recommended
             .iter()
@@ -423,9 +830,14 @@ async fn get_recommendations_attempt(
     Ok(recommendations)
 }
 
-#[tracing::instrument(skip_all)]
+/// Unlike analysis it isn't streamed: tracks come as one tool call, which is parsed
+/// and searched on Spotify as a whole, so there is nothing to show partially
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
 async fn get_raw_recommendations(
+    app: &App,
+    state: &UserState,
     config: &AIConfig,
+    options: &RecommendasionOptions,
     user_data: &UserData,
 ) -> Result<RecommendationsRaw, anyhow::Error> {
     let liked_tracks = user_data
@@ -463,14 +875,26 @@ async fn get_raw_recommendations(
         "
     );
 
+    let mut messages = vec![
+        ChatCompletionRequestSystemMessage::from(system_prompt.as_str()).into(),
+        ChatCompletionRequestUserMessage::from(liked_prompt.as_str()).into(),
+    ];
+
+    if let Some(steering_prompt) =
+        steering_prompt(options, user_data.current.as_ref(), Clock::now().date())
+    {
+        messages.push(ChatCompletionRequestUserMessage::from(steering_prompt.as_str()).into());
+    }
+
+    if let Some(feedback_prompt) = feedback_prompt(&user_data.feedback) {
+        messages.push(ChatCompletionRequestUserMessage::from(feedback_prompt.as_str()).into());
+    }
+
+    messages.push(ChatCompletionRequestUserMessage::from(task_prompt.as_str()).into());
+
     let req = CreateChatCompletionRequestArgs::default()
-        .model(config.model())
         // .temperature(2.0)
-        .messages([
-            ChatCompletionRequestSystemMessage::from(system_prompt.as_str()).into(),
-            ChatCompletionRequestUserMessage::from(liked_prompt.as_str()).into(),
-            ChatCompletionRequestUserMessage::from(task_prompt.as_str()).into(),
-        ])
+        .messages(messages)
         .tools(ChatCompletionTool {
             function: FunctionObjectArgs::default()
                 .name("recommend_tracks")
@@ -510,11 +934,18 @@ async fn get_raw_recommendations(
         ))
         .build()?;
 
-    let response_message = config
-        .openai_client()
-        .chat()
-        .create(req)
-        .await?
+    let (target, response) = config.chat(AIFeature::Recommendasion, req).await?;
+
+    AIUsageService::record(
+        app.db(),
+        state.user_id(),
+        AIFeature::Recommendasion,
+        target,
+        response.usage.as_ref(),
+    )
+    .await?;
+
+    let response_message = response
         .choices
         .first()
         .context("No choices returned from OpenAI API")?
@@ -538,3 +969,71 @@ async fn get_raw_recommendations(
 
     Ok(tracks)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::prelude::RecommendasionOutcome;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_steering_prompt() {
+        let today = date("2026-10-19");
+
+        assert_eq!(
+            steering_prompt(&RecommendasionOptions::default(), None, today),
+            None
+        );
+
+        // Nothing is playing, mode can't be applied
+        let options = RecommendasionOptions {
+            mode: RecommendasionMode::SimilarToCurrent,
+            ..Default::default()
+        };
+        assert_eq!(steering_prompt(&options, None, today), None);
+
+        let options = RecommendasionOptions {
+            mode: RecommendasionMode::NewReleases,
+            hint: Some("rainy day jazz".into()),
+            save_to_playlist: false,
+        };
+        let prompt = steering_prompt(&options, None, today).unwrap();
+
+        assert!(prompt.contains("released after 2025-10-19"));
+        assert!(prompt.ends_with("rainy day jazz"));
+    }
+
+    #[test]
+    fn test_feedback_prompt() {
+        assert_eq!(feedback_prompt(&[]), None);
+
+        let feedback = |track_name: &str, outcome| RecommendasionFeedbackModel {
+            id: 0,
+            user_id: "user".into(),
+            track_id: track_name.into(),
+            track_name: track_name.into(),
+            outcome,
+            created_at: Clock::now(),
+            updated_at: Clock::now(),
+        };
+
+        let prompt = feedback_prompt(&[
+            feedback("Hit", RecommendasionOutcome::Listened),
+            feedback("Miss", RecommendasionOutcome::Skipped),
+            feedback("Like", RecommendasionOutcome::Liked),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            prompt,
+            "PREVIOUS RECOMMENDATIONS I ENJOYED (recommend more like these):\nHit\nLike\nPREVIOUS RECOMMENDATIONS I SKIPPED OR DISLIKED (avoid similar tracks):\nMiss"
+        );
+
+        let prompt = feedback_prompt(&[feedback("Miss", RecommendasionOutcome::Disliked)]).unwrap();
+
+        assert!(!prompt.contains("ENJOYED"));
+    }
+}
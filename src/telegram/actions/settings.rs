@@ -1,8 +1,10 @@
-use sea_orm::{ActiveModelTrait as _, IntoActiveModel as _, Set};
 use teloxide::prelude::Requester as _;
 use teloxide::types::ChatId;
 
 use crate::app::App;
+use crate::services::UserService;
+use crate::spotify::scopes::Feature;
+use crate::telegram::actions;
 use crate::telegram::handlers::HandleStatus;
 use crate::user::UserState;
 
@@ -12,12 +14,13 @@ pub async fn handle_toggle_profanity_check(
     state: &UserState,
     chat_id: ChatId,
 ) -> anyhow::Result<HandleStatus> {
-    let new_status = !state.user().cfg_check_profanity;
+    if actions::kid_safe::check_settings_locked(app, state, chat_id).await? {
+        return Ok(HandleStatus::Handled);
+    }
 
-    let mut user_model = state.user().clone().into_active_model();
+    let new_status = !state.user().cfg_check_profanity;
 
-    user_model.cfg_check_profanity = Set(new_status);
-    user_model.save(app.db()).await?;
+    UserService::set_cfg_check_profanity(app.db(), state.user_id(), new_status).await?;
 
     let text = if new_status {
         t!("settings.profanity-check-on", locale = state.locale())
@@ -30,18 +33,86 @@ pub async fn handle_toggle_profanity_check(
     Ok(HandleStatus::Handled)
 }
 
+/// Kid mode skips tracks which AI analysis rated as not suitable for kids
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_toggle_kid_mode(
+    app: &'static App,
+    state: &UserState,
+    chat_id: ChatId,
+) -> anyhow::Result<HandleStatus> {
+    if app.ai().is_none() {
+        app.bot()
+            .send_message(chat_id, t!("analysis.disabled", locale = state.locale()))
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    }
+
+    if actions::kid_safe::check_settings_locked(app, state, chat_id).await? {
+        return Ok(HandleStatus::Handled);
+    }
+
+    let new_status = !state.user().cfg_kid_mode;
+
+    UserService::set_cfg_kid_mode(app.db(), state.user_id(), new_status).await?;
+
+    let text = if new_status {
+        t!("settings.kid-mode-on", locale = state.locale())
+    } else {
+        t!("settings.kid-mode-off", locale = state.locale())
+    };
+
+    app.bot().send_message(chat_id, text).await?;
+
+    if new_status {
+        actions::login::ensure_features(app, state, &[Feature::AutoSkip]).await?;
+    }
+
+    Ok(HandleStatus::Handled)
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_toggle_skip_explicit(
+    app: &'static App,
+    state: &UserState,
+    chat_id: ChatId,
+) -> anyhow::Result<HandleStatus> {
+    if actions::kid_safe::check_settings_locked(app, state, chat_id).await? {
+        return Ok(HandleStatus::Handled);
+    }
+
+    let new_status = !state.user().cfg_skip_explicit;
+
+    UserService::set_cfg_skip_explicit(app.db(), state.user_id(), new_status).await?;
+
+    let text = if new_status {
+        t!("settings.skip-explicit-on", locale = state.locale())
+    } else {
+        t!("settings.skip-explicit-off", locale = state.locale())
+    };
+
+    app.bot().send_message(chat_id, text).await?;
+
+    if new_status {
+        actions::login::ensure_features(app, state, &[Feature::AutoSkip]).await?;
+    }
+
+    Ok(HandleStatus::Handled)
+}
+
 #[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
 pub async fn handle_toggle_skip_tracks(
-    app: &App,
+    app: &'static App,
     state: &UserState,
     chat_id: ChatId,
 ) -> anyhow::Result<HandleStatus> {
-    let new_status = !state.user().cfg_skip_tracks;
+    if actions::kid_safe::check_settings_locked(app, state, chat_id).await? {
+        return Ok(HandleStatus::Handled);
+    }
 
-    let mut user_model = state.user().clone().into_active_model();
+    let new_status = !state.user().cfg_skip_tracks;
 
-    user_model.cfg_skip_tracks = Set(new_status);
-    user_model.save(app.db()).await?;
+    UserService::set_cfg_skip_tracks(app.db(), state.user_id(), new_status).await?;
 
     let text = if new_status {
         t!("settings.skip-on", locale = state.locale())
@@ -51,5 +122,10 @@ pub async fn handle_toggle_skip_tracks(
 
     app.bot().send_message(chat_id, text).await?;
 
+    if new_status {
+        actions::login::ensure_features(app, state, &[Feature::AutoSkip, Feature::Collection])
+            .await?;
+    }
+
     Ok(HandleStatus::Handled)
 }
@@ -6,6 +6,7 @@ use teloxide::types::{CallbackQuery, ChatId, InlineKeyboardMarkup, Message, Repl
 
 use crate::app::App;
 use crate::services::{SkippageService, UserService};
+use crate::spotify::scopes::Feature;
 use crate::telegram::actions;
 use crate::telegram::commands::UserCommandDisplay;
 use crate::telegram::handlers::HandleStatus;
@@ -48,6 +49,10 @@ pub async fn handle_inline(
         ]]))
         .await?;
 
+    if to_enable {
+        actions::login::ensure_features(app, state, &[Feature::AutoSkip]).await?;
+    }
+
     Ok(())
 }
 
@@ -136,5 +141,7 @@ pub async fn handle(
         )
         .await?;
 
+    actions::login::ensure_features(app, state, &[Feature::AutoSkip]).await?;
+
     Ok(HandleStatus::Handled)
 }
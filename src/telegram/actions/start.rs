@@ -2,7 +2,13 @@ use teloxide::prelude::*;
 
 use crate::app::App;
 use crate::entity::prelude::*;
-use crate::services::{NotificationService, SpotifyPollingBackoffService, UserService};
+use crate::queue::webhook;
+use crate::services::{
+    NotificationService,
+    SpotifyPollingBackoffService,
+    UserService,
+    WebhookEvent,
+};
 use crate::telegram::handlers::HandleStatus;
 use crate::telegram::keyboards::{LanguageKeyboard, StartKeyboard};
 use crate::user::UserState;
@@ -24,6 +30,16 @@ pub async fn handle(
             tracing::info!(user_id = state.user_id(), "User were reactivated");
         }
 
+        if state.user().status != UserStatus::Active {
+            webhook::emit(
+                app,
+                state.user_id(),
+                WebhookEvent::UserStatusChanged,
+                webhook::user_status_data(UserStatus::Active),
+            )
+            .await;
+        }
+
         let text = if was_inactive {
             t!("status.reactivated", locale = state.locale())
         } else {
@@ -4,7 +4,13 @@ use teloxide::types::ReplyParameters;
 
 use crate::app::App;
 use crate::entity::prelude::*;
-use crate::services::{TrackLanguageStatsService, TrackStatusService, UserService, UserStats};
+use crate::services::{
+    RecommendasionFeedbackService,
+    TrackLanguageStatsService,
+    TrackStatusService,
+    UserService,
+    UserStats,
+};
 use crate::telegram::handlers::HandleStatus;
 use crate::user::UserState;
 
@@ -61,6 +67,20 @@ pub async fn handle(
         languages
     };
 
+    let (hits, total) = RecommendasionFeedbackService::hit_rate(app.db(), state.user_id()).await?;
+
+    let recommendasion_hit_rate = if total == 0 {
+        t!("actions.stats-hit-rate-empty", locale = state.locale())
+    } else {
+        t!(
+            "actions.stats-hit-rate",
+            locale = state.locale(),
+            rate = hits * 100 / total,
+            hits = hits,
+            total = total,
+        )
+    };
+
     let text = t!(
         "actions.stats",
         locale = state.locale(),
@@ -73,6 +93,7 @@ pub async fn handle(
         ignored = ignored,
         lyrics_profane = lyrics_profane,
         languages = languages,
+        recommendasion_hit_rate = recommendasion_hit_rate,
     );
 
     app.bot()
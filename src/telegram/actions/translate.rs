@@ -0,0 +1,440 @@
+use std::time::Duration;
+
+use anyhow::Context as _;
+use async_openai::types::chat::{
+    ChatCompletionMessageToolCalls,
+    ChatCompletionNamedToolChoice,
+    ChatCompletionRequestUserMessage,
+    ChatCompletionTool,
+    ChatCompletionToolChoiceOption,
+    CreateChatCompletionRequestArgs,
+    FunctionObjectArgs,
+};
+use backon::{ExponentialBuilder, Retryable as _};
+use indoc::formatdoc;
+use isolang::Language;
+use rspotify::model::TrackId;
+use teloxide::payloads::{AnswerCallbackQuerySetters as _, SendMessageSetters as _};
+use teloxide::prelude::Requester as _;
+use teloxide::sugar::bot::BotMessagesExt as _;
+use teloxide::sugar::request::RequestLinkPreviewExt as _;
+use teloxide::types::{CallbackQuery, ChatId, Message};
+use teloxide::utils::html;
+
+use crate::ai::{AIConfig, AIFeature};
+use crate::app::App;
+use crate::lyrics::SearchResult as _;
+use crate::profanity::{self, LineResult};
+use crate::queue::track_check;
+use crate::services::{
+    AIQuotaOutput,
+    AIUsageService,
+    TrackCheckCacheService,
+    TrackCheckKind,
+    TrackTranslation,
+    TrackTranslationService,
+};
+use crate::spotify::ShortTrack;
+use crate::telegram::MESSAGE_MAX_LEN;
+use crate::telegram::utils::link_preview_small_top;
+use crate::user::UserState;
+use crate::utils::StringUtils as _;
+
+/// Extra requests when AI translation doesn't match the lyrics
+const TRANSLATION_RETRIES: usize = 2;
+
+/// Lyrics in a known language other than the one of the user locale
+#[must_use]
+pub fn is_translatable(language: Language, locale: &str) -> bool {
+    language != Language::Und
+        && Language::from_639_1(locale).is_some_and(|target| target != language)
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id(), %track_id))]
+pub async fn handle_inline(
+    app: &'static App,
+    state: &UserState,
+    q: CallbackQuery,
+    m: Message,
+    track_id: &str,
+) -> anyhow::Result<()> {
+    let Some(config) = app.ai() else {
+        app.bot()
+            .answer_callback_query(q.id)
+            .text(t!("translation.disabled", locale = state.locale()))
+            .show_alert(true)
+            .await?;
+
+        return Ok(());
+    };
+
+    let mut redis_conn = app.redis_conn().await?;
+
+    let track = state
+        .spotify()
+        .await
+        .short_track_cached(&mut redis_conn, TrackId::from_id(track_id)?)
+        .await?;
+
+    let Some(hit) = app
+        .lyrics()
+        .search_for_track(&mut redis_conn, &track)
+        .await?
+    else {
+        app.bot()
+            .answer_callback_query(q.id)
+            .text(t!("analysis.lyrics-not-found", locale = state.locale()))
+            .show_alert(true)
+            .await?;
+
+        return Ok(());
+    };
+
+    let cached = TrackTranslationService::find(app.db(), track.id(), state.locale()).await?;
+
+    // Cached translations cost nothing, so they are not limited
+    let reservation = if cached.is_none() {
+        match AIUsageService::reserve(
+            app.db(),
+            &mut redis_conn,
+            config,
+            state.user_id(),
+            AIFeature::Translation,
+        )
+        .await?
+        {
+            AIQuotaOutput::Allowed(reservation) => Some(reservation),
+            AIQuotaOutput::Exceeded { renews_on } => {
+                app.bot()
+                    .answer_callback_query(q.id)
+                    .text(t!(
+                        "rate-limit.ai-quota",
+                        date = renews_on.format("%Y-%m-%d"),
+                        locale = state.locale()
+                    ))
+                    .show_alert(true)
+                    .await?;
+
+                return Ok(());
+            },
+        }
+    } else {
+        None
+    };
+
+    app.bot().answer_callback_query(q.id).await?;
+
+    let progress = app
+        .bot()
+        .send_message(
+            m.chat.id,
+            t!(
+                "translation.waiting",
+                locale = state.locale(),
+                track_name = track.track_tg_link(),
+                album_name = track.album_tg_link(),
+            ),
+        )
+        .link_preview_options(link_preview_small_top(track.url()))
+        .await?;
+
+    let lyrics = hit.lyrics();
+
+    let res = match cached {
+        Some(translation) => Ok(translation),
+        None => generate(app, state, config, &track, &lyrics).await,
+    };
+
+    if let Some(reservation) = reservation {
+        AIUsageService::release(&mut redis_conn, reservation).await?;
+    }
+
+    // I don't care about error
+    app.bot().delete(&progress).await.ok();
+
+    match res {
+        Ok(translation) => {
+            send(
+                app,
+                state,
+                m.chat.id,
+                &track,
+                hit.language(),
+                &lyrics,
+                &translation,
+            )
+            .await?;
+        },
+        Err(err) => {
+            app.bot()
+                .send_message(
+                    m.chat.id,
+                    t!(
+                        "translation.failed",
+                        locale = state.locale(),
+                        track_name = track.track_tg_link(),
+                        album_name = track.album_tg_link(),
+                    ),
+                )
+                .link_preview_options(link_preview_small_top(track.url()))
+                .await?;
+
+            tracing::warn!(err = ?err, "Lyrics translation failed");
+        },
+    }
+
+    Ok(())
+}
+
+async fn send(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+    track: &ShortTrack,
+    language: Language,
+    lyrics: &[&str],
+    translation: &TrackTranslation,
+) -> anyhow::Result<()> {
+    let translated: Vec<_> = translation.lines.iter().map(String::as_str).collect();
+
+    // English translation is checked like original English lyrics
+    let check_profanity = Language::from_639_1(state.locale()) == Some(Language::Eng)
+        && state.user().check_profanity();
+
+    let (translated, profanity_line): (Vec<String>, String) = if check_profanity {
+        let checked = profanity::Manager::check(&translated);
+
+        let profanity_line = t!(
+            "translation.profanity",
+            locale = state.locale(),
+            profanity = checked.typ.to_string(),
+        );
+
+        (
+            checked.iter().map(LineResult::highlighted).collect(),
+            profanity_line.into(),
+        )
+    } else {
+        (
+            translated.iter().map(|line| html::escape(line)).collect(),
+            String::new(),
+        )
+    };
+
+    let text_gen = |lyrics: &str| -> String {
+        t!(
+            "translation.result",
+            locale = state.locale(),
+            track_name = track.track_tg_link(),
+            album_name = track.album_tg_link(),
+            source_language = language.to_name(),
+            target_language = state.user().locale.language(),
+            profanity_line = profanity_line,
+            lyrics = lyrics,
+        )
+        .into()
+    };
+
+    let lyrics = render_lines(lyrics, &translated).join("\n\n");
+
+    let text = text_gen(&lyrics);
+
+    // Split into several messages if everything doesn't fit in one
+    let texts = if text.chars_len() <= MESSAGE_MAX_LEN {
+        vec![text]
+    } else {
+        let header_len = text_gen("").chars_len();
+
+        let mut parts = lyrics
+            .chars_split(MESSAGE_MAX_LEN.saturating_sub(header_len))
+            .into_iter();
+
+        let first_part = parts.next().unwrap_or_default();
+
+        std::iter::once(text_gen(&first_part))
+            .chain(parts)
+            .collect()
+    };
+
+    for (i, text) in texts.into_iter().enumerate() {
+        let request = app.bot().send_message(chat_id, text);
+
+        if i == 0 {
+            request
+                .link_preview_options(link_preview_small_top(track.url()))
+                .await?;
+        } else {
+            request.disable_link_preview(true).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Original line in italic with its translation below, empty lines are dropped
+fn render_lines(original: &[&str], translated: &[String]) -> Vec<String> {
+    original
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let line = format!("<i>{}</i>", html::escape(line.trim()));
+
+            match translated
+                .get(i)
+                .filter(|translated| !translated.trim().is_empty())
+            {
+                Some(translated) => format!("{line}\n{}", translated.trim()),
+                None => line,
+            }
+        })
+        .collect()
+}
+
+/// Requests translation from AI and saves it for other users.
+/// Translation with lines not matching the original ones is requested again
+#[tracing::instrument(skip_all, fields(
+    user_id = %state.user_id(),
+    track_id = %track.id(),
+    track_name = %track.name_with_artists()
+))]
+async fn generate(
+    app: &App,
+    state: &UserState,
+    config: &AIConfig,
+    track: &ShortTrack,
+    lyrics: &[&str],
+) -> anyhow::Result<TrackTranslation> {
+    let (model, translation) = (|| generate_attempt(app, state, config, track, lyrics))
+        .retry(ExponentialBuilder::default().with_max_times(TRANSLATION_RETRIES))
+        .notify(|err: &anyhow::Error, dur: Duration| {
+            tracing::warn!(
+                err = ?err,
+                "Translation is failed. Retry in {dur} sec",
+                dur = dur.as_secs()
+            );
+        })
+        .await?;
+
+    TrackTranslationService::save(app.db(), track.id(), state.locale(), &model, &translation)
+        .await?;
+
+    // Profanity of non English lyrics is checked with their English translation
+    if state.locale() == track_check::ENGLISH_TRANSLATION {
+        TrackCheckCacheService::clear(
+            &mut app.redis_conn().await?,
+            TrackCheckKind::Lyrics,
+            track.id(),
+        )
+        .await?;
+    }
+
+    Ok(translation)
+}
+
+/// Returns model which translated the lyrics with the translation
+async fn generate_attempt(
+    app: &App,
+    state: &UserState,
+    config: &AIConfig,
+    track: &ShortTrack,
+    lyrics: &[&str],
+) -> anyhow::Result<(String, TrackTranslation)> {
+    let target_language = state.user().locale.language();
+
+    let prompt = formatdoc!(
+        "
+            Translate the lyrics of the song {song_name} into {target_language} line by line.
+            Lines are given as a JSON array. Return exactly one translated line for each of them in the same order, keep empty lines empty.
+            Keep the meaning and the tone, don't censor or soften anything, don't add explanations.
+
+            {lines}
+        ",
+        song_name = track.name_with_artists(),
+        lines = serde_json::to_string(lyrics)?,
+    );
+
+    let req = CreateChatCompletionRequestArgs::default()
+        .messages([ChatCompletionRequestUserMessage::from(prompt.as_str()).into()])
+        .tools(ChatCompletionTool {
+            function: FunctionObjectArgs::default()
+                .name(TrackTranslation::TOOL_NAME)
+                .description("Save line by line translation of the song lyrics")
+                .strict(true)
+                .parameters(TrackTranslation::schema())
+                .build()?,
+        })
+        .tool_choice(ChatCompletionToolChoiceOption::Function(
+            ChatCompletionNamedToolChoice::from(TrackTranslation::TOOL_NAME),
+        ))
+        .build()?;
+
+    let (target, response) = config.chat(AIFeature::Translation, req).await?;
+
+    AIUsageService::record(
+        app.db(),
+        state.user_id(),
+        AIFeature::Translation,
+        target,
+        response.usage.as_ref(),
+    )
+    .await?;
+
+    let tool_call = response
+        .choices
+        .into_iter()
+        .next()
+        .context("No choices returned from AI")?
+        .message
+        .tool_calls
+        .and_then(|tool_calls| tool_calls.into_iter().next())
+        .context("No tool call found in response")?;
+
+    let ChatCompletionMessageToolCalls::Function(tool_call) = tool_call else {
+        anyhow::bail!("Expected function tool call");
+    };
+
+    let translation: TrackTranslation = serde_json::from_str(&tool_call.function.arguments)
+        .context("AI returned invalid translation")?;
+
+    // Lines are shown under the original ones, shifted translation is misleading
+    if translation.lines.len() != lyrics.len() {
+        anyhow::bail!(
+            "Translation has {} lines instead of {}",
+            translation.lines.len(),
+            lyrics.len()
+        );
+    }
+
+    Ok((target.model().to_owned(), translation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_translatable() {
+        assert!(is_translatable(Language::Spa, "en"));
+        assert!(is_translatable(Language::Eng, "ru"));
+        assert!(!is_translatable(Language::Eng, "en"));
+        assert!(!is_translatable(Language::Und, "en"));
+    }
+
+    #[test]
+    fn test_render_lines() {
+        let lines = render_lines(
+            &["Hola <amigo>", "", "Adiós", "Extra"],
+            &["Hello <amigo>".into(), String::new(), "Goodbye".into()],
+        );
+
+        assert_eq!(
+            lines,
+            [
+                "<i>Hola &lt;amigo&gt;</i>\nHello <amigo>",
+                "<i>Adiós</i>\nGoodbye",
+                "<i>Extra</i>",
+            ]
+        );
+    }
+}
@@ -5,6 +5,7 @@ use teloxide::types::ChatId;
 
 use crate::app::App;
 use crate::services::UserWordWhitelistService;
+use crate::telegram::actions;
 use crate::telegram::commands::UserCommandDisplay;
 use crate::telegram::handlers::HandleStatus;
 use crate::telegram::keyboards::StartKeyboard;
@@ -18,15 +19,19 @@ pub async fn handle_add_word(
     chat_id: ChatId,
     word: String,
 ) -> anyhow::Result<HandleStatus> {
+    if actions::kid_safe::check_settings_locked(app, state, chat_id).await? {
+        return Ok(HandleStatus::Handled);
+    }
+
     let count_words =
         UserWordWhitelistService::count_ok_words_for_user(app.db(), state.user_id()).await?;
 
     let validate = |word: &str| {
-        if count_words >= 20 {
+        if count_words >= UserWordWhitelistService::MAX_WORDS {
             return Some(t!(
                 "user-word-whitelist.limit-amount",
                 locale = state.locale(),
-                limit = 20
+                limit = UserWordWhitelistService::MAX_WORDS
             ));
         }
 
@@ -38,11 +43,11 @@ pub async fn handle_add_word(
             ));
         }
 
-        if word.chars_len() > 16 {
+        if word.chars_len() > UserWordWhitelistService::MAX_WORD_LENGTH {
             return Some(t!(
                 "user-word-whitelist.limit-length",
                 locale = state.locale(),
-                limit = 16,
+                limit = UserWordWhitelistService::MAX_WORD_LENGTH,
             ));
         }
 
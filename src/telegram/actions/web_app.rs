@@ -0,0 +1,42 @@
+use teloxide::payloads::SendMessageSetters as _;
+use teloxide::prelude::Requester as _;
+use teloxide::types::{
+    ChatId,
+    InlineKeyboardButton,
+    InlineKeyboardButtonKind,
+    InlineKeyboardMarkup,
+    WebAppInfo,
+};
+
+use crate::app::App;
+use crate::telegram::handlers::HandleStatus;
+use crate::user::UserState;
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle(app: &App, state: &UserState, chat_id: ChatId) -> anyhow::Result<HandleStatus> {
+    // Telegram opens Mini Apps only via HTTPS
+    let Some(base_url) = app
+        .server_public_url()
+        .filter(|url| url.starts_with("https://"))
+    else {
+        app.bot()
+            .send_message(chat_id, t!("web-app.unavailable", locale = state.locale()))
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    };
+
+    app.bot()
+        .send_message(chat_id, t!("web-app.link", locale = state.locale()))
+        .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton {
+                text: t!("web-app.open", locale = state.locale()).into(),
+                kind: InlineKeyboardButtonKind::WebApp(WebAppInfo {
+                    url: format!("{base_url}/app").parse()?,
+                }),
+            },
+        ]]))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
@@ -0,0 +1,212 @@
+use itertools::Itertools as _;
+use strum::IntoEnumIterator as _;
+use teloxide::payloads::SendMessageSetters as _;
+use teloxide::prelude::Requester as _;
+use teloxide::types::ChatId;
+use teloxide::utils::html;
+
+use crate::app::App;
+use crate::services::{WebhookEvent, WebhookService};
+use crate::telegram::commands::UserCommandDisplay;
+use crate::telegram::handlers::HandleStatus;
+use crate::telegram::keyboards::StartKeyboard;
+use crate::user::UserState;
+
+fn available_events() -> String {
+    WebhookEvent::iter()
+        .map(|event| format!("<code>{event}</code>"))
+        .join(", ")
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id(), %is_global))]
+pub async fn handle_add(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+    args: &str,
+    is_global: bool,
+) -> anyhow::Result<HandleStatus> {
+    let mut args = args.split_whitespace();
+    let url = args.next().unwrap_or_default();
+    let events = args.join(",");
+
+    let count = WebhookService::count_for_user(app.db(), state.user_id()).await?;
+    let url_valid = url.is_empty() || WebhookService::validate_url(url).await.is_ok();
+
+    let validate = || {
+        if url.is_empty() {
+            return Err(t!(
+                "webhook.add-provide-url",
+                locale = state.locale(),
+                command = UserCommandDisplay::AddWebhook,
+                events = available_events(),
+            ));
+        }
+
+        if !url_valid {
+            return Err(t!(
+                "webhook.invalid-url",
+                locale = state.locale(),
+                url = html::escape(url),
+            ));
+        }
+
+        let Ok(events) = WebhookEvent::parse_list(&events) else {
+            return Err(t!(
+                "webhook.invalid-events",
+                locale = state.locale(),
+                events = html::escape(&events),
+                available = available_events(),
+            ));
+        };
+
+        if count >= WebhookService::MAX_PER_USER {
+            return Err(t!(
+                "webhook.limit-amount",
+                locale = state.locale(),
+                limit = WebhookService::MAX_PER_USER,
+            ));
+        }
+
+        Ok(events)
+    };
+
+    let events = match validate() {
+        Ok(events) => events,
+        Err(text) => {
+            app.bot()
+                .send_message(chat_id, text)
+                .reply_markup(StartKeyboard::markup(state.locale()))
+                .await?;
+
+            return Ok(HandleStatus::Handled);
+        },
+    };
+
+    let webhook = WebhookService::add(app.db(), state.user_id(), url, &events, is_global).await?;
+
+    tracing::info!(
+        user_id = state.user_id(),
+        webhook_id = webhook.id,
+        is_global,
+        "User added webhook"
+    );
+
+    app.bot()
+        .send_message(
+            chat_id,
+            t!(
+                "webhook.added",
+                locale = state.locale(),
+                id = webhook.id,
+                url = html::escape(&webhook.url),
+                secret = webhook.secret,
+                command = UserCommandDisplay::Webhooks,
+            ),
+        )
+        .reply_markup(StartKeyboard::markup(state.locale()))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_remove(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+    id: &str,
+) -> anyhow::Result<HandleStatus> {
+    let Ok(id) = id.trim().trim_start_matches('#').parse::<i32>() else {
+        app.bot()
+            .send_message(
+                chat_id,
+                t!(
+                    "webhook.remove-provide-id",
+                    locale = state.locale(),
+                    command = UserCommandDisplay::RemoveWebhook,
+                ),
+            )
+            .reply_markup(StartKeyboard::markup(state.locale()))
+            .await?;
+
+        return Ok(HandleStatus::Handled);
+    };
+
+    let removed = WebhookService::remove(app.db(), state.user_id(), id).await?;
+
+    let text = if removed {
+        t!("webhook.removed", locale = state.locale(), id = id)
+    } else {
+        t!(
+            "webhook.doesnt-exist",
+            locale = state.locale(),
+            id = id,
+            command = UserCommandDisplay::Webhooks,
+        )
+    };
+
+    app.bot()
+        .send_message(chat_id, text)
+        .reply_markup(StartKeyboard::markup(state.locale()))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %state.user_id()))]
+pub async fn handle_list(
+    app: &App,
+    state: &UserState,
+    chat_id: ChatId,
+) -> anyhow::Result<HandleStatus> {
+    let webhooks = WebhookService::list_for_user(app.db(), state.user_id()).await?;
+
+    let text = if webhooks.is_empty() {
+        t!(
+            "webhook.empty",
+            locale = state.locale(),
+            command = UserCommandDisplay::AddWebhook,
+        )
+    } else {
+        let webhooks = webhooks
+            .iter()
+            .map(|webhook| {
+                let events = if webhook.events.is_empty() {
+                    t!("webhook.all-events", locale = state.locale()).to_string()
+                } else {
+                    html::escape(&webhook.events.replace(',', ", "))
+                };
+
+                let global = if webhook.is_global {
+                    format!(
+                        " <b>({})</b>",
+                        t!("webhook.global", locale = state.locale())
+                    )
+                } else {
+                    String::new()
+                };
+
+                format!(
+                    "• <code>#{id}</code> <code>{url}</code>{global}\n  <i>{events}</i>",
+                    id = webhook.id,
+                    url = html::escape(&webhook.url),
+                )
+            })
+            .join("\n");
+
+        t!(
+            "webhook.list",
+            locale = state.locale(),
+            webhooks = webhooks,
+            command = UserCommandDisplay::RemoveWebhook,
+        )
+    };
+
+    app.bot()
+        .send_message(chat_id, text)
+        .reply_markup(StartKeyboard::markup(state.locale()))
+        .await?;
+
+    Ok(HandleStatus::Handled)
+}
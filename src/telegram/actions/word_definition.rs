@@ -9,10 +9,12 @@ use crate::services::{WordDefinitionService, WordStatsService};
 use crate::telegram::commands_admin::AdminCommandDisplay;
 use crate::telegram::handlers::HandleStatus;
 use crate::telegram::inline_buttons_admin::AdminInlineButtons;
+use crate::user::UserState;
 
 #[tracing::instrument(skip_all, fields(%locale, %word))]
 async fn generate_and_send_definition(
     app: &'static App,
+    state: &UserState,
     message: &Message,
     locale: String,
     word: String,
@@ -41,8 +43,19 @@ async fn generate_and_send_definition(
         WordDefinitionService::clear_definition(app.db(), &locale, &word).await?;
     }
 
-    let definition =
-        WordDefinitionService::get_definition(app.db(), &locale, ai_config, &word).await?;
+    let Some(definition) =
+        WordDefinitionService::get_definition(app, state.user_id(), &locale, ai_config, &word)
+            .await?
+    else {
+        app.bot()
+            .edit_text(
+                message,
+                "Your AI quota is used up. Word definition is not generated.",
+            )
+            .await?;
+
+        return Ok(());
+    };
 
     let keyboard = InlineKeyboardMarkup::new(vec![vec![
         AdminInlineButtons::RegenerateWordDefinition {
@@ -68,6 +81,7 @@ async fn generate_and_send_definition(
 #[tracing::instrument(skip_all, fields(%locale, %word))]
 pub async fn handle_definition(
     app: &'static App,
+    state: &UserState,
     m: &Message,
     locale: String,
     word: String,
@@ -91,7 +105,7 @@ pub async fn handle_definition(
 
     let message = app.bot().send_message(m.chat.id, "Starting...").await?;
 
-    generate_and_send_definition(app, &message, locale, word, refresh).await?;
+    generate_and_send_definition(app, state, &message, locale, word, refresh).await?;
 
     Ok(HandleStatus::Handled)
 }
@@ -99,12 +113,13 @@ pub async fn handle_definition(
 #[tracing::instrument(skip_all, fields(%locale, %word))]
 pub async fn handle_inline_regenerate(
     app: &'static App,
+    state: &UserState,
     _q: CallbackQuery,
     m: Message,
     locale: String,
     word: String,
 ) -> anyhow::Result<()> {
-    generate_and_send_definition(app, &m, locale, word, true).await?;
+    generate_and_send_definition(app, state, &m, locale, word, true).await?;
 
     Ok(())
 }
@@ -28,6 +28,8 @@ pub enum UserCommand {
     Recommendasion,
     #[command(description = "command.details")]
     Details,
+    #[command(description = "command.ask")]
+    Ask { question: String },
     #[command(description = "command.stats")]
     Stats,
     #[command(description = "command.login")]
@@ -37,6 +39,12 @@ pub enum UserCommand {
     ToggleTrackSkip,
     #[command(description = "command.toggle-profanity-check")]
     ToggleProfanityCheck,
+    #[command(description = "command.toggle-kid-mode")]
+    ToggleKidMode,
+    #[command(description = "command.toggle-explicit-skip")]
+    ToggleExplicitSkip,
+    #[command(description = "command.kid-safe")]
+    KidSafe { pin: String },
 
     #[command(description = "command.magic")]
     Magic,
@@ -67,6 +75,27 @@ pub enum UserCommand {
         rename = "ai_slop_detection"
     )]
     AISlopDetection,
+
+    #[command(description = "command.webhooks")]
+    Webhooks,
+
+    #[command(description = "command.add-webhook")]
+    AddWebhook { args: String },
+
+    #[command(description = "command.remove-webhook")]
+    RemoveWebhook { id: String },
+
+    #[command(description = "command.dashboard")]
+    Dashboard,
+
+    #[command(description = "command.api-token")]
+    ApiToken,
+
+    #[command(description = "command.revoke-api-token")]
+    RevokeApiToken,
+
+    #[command(description = "command.app")]
+    App,
 }
 
 impl UserCommand {
@@ -127,10 +156,14 @@ pub enum UserCommandDisplay {
     Dislike,
     Like,
     Details,
+    Ask,
     Stats,
     Login,
     ToggleTrackSkip,
     ToggleProfanityCheck,
+    ToggleKidMode,
+    ToggleExplicitSkip,
+    KidSafe,
     Help,
     AddWhitelistWord,
     RemoveWhitelistWord,
@@ -140,6 +173,13 @@ pub enum UserCommandDisplay {
     Language,
     Recommendasion,
     AISlopDetection,
+    Webhooks,
+    AddWebhook,
+    RemoveWebhook,
+    Dashboard,
+    ApiToken,
+    RevokeApiToken,
+    App,
 }
 
 impl std::fmt::Display for UserCommandDisplay {
@@ -150,10 +190,14 @@ impl std::fmt::Display for UserCommandDisplay {
             Self::Dislike => "dislike",
             Self::Like => "like",
             Self::Details => "details",
+            Self::Ask => "ask",
             Self::Stats => "stats",
             Self::Login => "login",
             Self::ToggleTrackSkip => "toggle_track_skip",
             Self::ToggleProfanityCheck => "toggle_profanity_check",
+            Self::ToggleKidMode => "toggle_kid_mode",
+            Self::ToggleExplicitSkip => "toggle_explicit_skip",
+            Self::KidSafe => "kid_safe",
             Self::Help => "help",
             Self::AddWhitelistWord => "add_word_to_whitelist",
             Self::RemoveWhitelistWord => "remove_word_from_whitelist",
@@ -163,6 +207,13 @@ impl std::fmt::Display for UserCommandDisplay {
             Self::Language => "language",
             Self::Recommendasion => "recommendasion",
             Self::AISlopDetection => "ai_slop_detection",
+            Self::Webhooks => "webhooks",
+            Self::AddWebhook => "add_webhook",
+            Self::RemoveWebhook => "remove_webhook",
+            Self::Dashboard => "dashboard",
+            Self::ApiToken => "api_token",
+            Self::RevokeApiToken => "revoke_api_token",
+            Self::App => "app",
         };
 
         f.write_str(string)
@@ -184,10 +235,14 @@ mod tests {
             UserCommand::Like => UserCommandDisplay::Like,
             UserCommand::Recommendasion => UserCommandDisplay::Recommendasion,
             UserCommand::Details => UserCommandDisplay::Details,
+            UserCommand::Ask { .. } => UserCommandDisplay::Ask,
             UserCommand::Stats => UserCommandDisplay::Stats,
             UserCommand::Login => UserCommandDisplay::Login,
             UserCommand::ToggleTrackSkip => UserCommandDisplay::ToggleTrackSkip,
             UserCommand::ToggleProfanityCheck => UserCommandDisplay::ToggleProfanityCheck,
+            UserCommand::ToggleKidMode => UserCommandDisplay::ToggleKidMode,
+            UserCommand::ToggleExplicitSkip => UserCommandDisplay::ToggleExplicitSkip,
+            UserCommand::KidSafe { .. } => UserCommandDisplay::KidSafe,
             UserCommand::Help => UserCommandDisplay::Help,
             UserCommand::AddWhitelistWord { .. } => UserCommandDisplay::AddWhitelistWord,
             UserCommand::RemoveWhitelistWord { .. } => UserCommandDisplay::RemoveWhitelistWord,
@@ -196,6 +251,13 @@ mod tests {
             UserCommand::Skippage { .. } => UserCommandDisplay::Skippage,
             UserCommand::Language => UserCommandDisplay::Language,
             UserCommand::AISlopDetection => UserCommandDisplay::AISlopDetection,
+            UserCommand::Webhooks => UserCommandDisplay::Webhooks,
+            UserCommand::AddWebhook { .. } => UserCommandDisplay::AddWebhook,
+            UserCommand::RemoveWebhook { .. } => UserCommandDisplay::RemoveWebhook,
+            UserCommand::Dashboard => UserCommandDisplay::Dashboard,
+            UserCommand::ApiToken => UserCommandDisplay::ApiToken,
+            UserCommand::RevokeApiToken => UserCommandDisplay::RevokeApiToken,
+            UserCommand::App => UserCommandDisplay::App,
         };
     }
 
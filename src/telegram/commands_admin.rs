@@ -23,9 +23,27 @@ pub enum AdminCommand {
     #[command(description = "List word definitions by locale (en, ru, etc)")]
     ListWordDefinitions { locale: String },
 
+    #[command(description = "Remove saved AI analyses of the track (id or URI)")]
+    ResetTrackAnalysis { track_id: String },
+
     #[command(description = "List users")]
     Users { user_id: String },
 
+    #[command(description = "Add webhook receiving events of all users")]
+    AddGlobalWebhook { url: String },
+
+    #[command(description = "Open admin web panel")]
+    AdminPanel,
+
+    #[command(description = "List track checks failed all retries")]
+    DeadLetters,
+
+    #[command(description = "Show dead letter with its error")]
+    DeadLetter { id: String },
+
+    #[command(description = "Put dead letter back to the queue (id or all)")]
+    ReplayDeadLetter { id: String },
+
     #[command(description = "Build Info")]
     BuildInfo,
 }
@@ -37,7 +55,13 @@ pub enum AdminCommandDisplay {
     GetWordDefinition,
     ResetWordDefinition,
     ListWordDefinitions,
+    ResetTrackAnalysis,
     Users,
+    AddGlobalWebhook,
+    AdminPanel,
+    DeadLetters,
+    DeadLetter,
+    ReplayDeadLetter,
     BuildInfo,
 }
 
@@ -50,7 +74,13 @@ impl std::fmt::Display for AdminCommandDisplay {
             Self::GetWordDefinition => "get_word_definition",
             Self::ResetWordDefinition => "reset_word_definition",
             Self::ListWordDefinitions => "list_word_definitions",
+            Self::ResetTrackAnalysis => "reset_track_analysis",
             Self::Users => "users",
+            Self::AddGlobalWebhook => "add_global_webhook",
+            Self::AdminPanel => "admin_panel",
+            Self::DeadLetters => "dead_letters",
+            Self::DeadLetter => "dead_letter",
+            Self::ReplayDeadLetter => "replay_dead_letter",
             Self::BuildInfo => "build_info",
         };
 
@@ -72,7 +102,13 @@ mod tests {
             AdminCommand::GetWordDefinition { .. } => AdminCommandDisplay::GetWordDefinition,
             AdminCommand::ResetWordDefinition { .. } => AdminCommandDisplay::ResetWordDefinition,
             AdminCommand::ListWordDefinitions { .. } => AdminCommandDisplay::ListWordDefinitions,
+            AdminCommand::ResetTrackAnalysis { .. } => AdminCommandDisplay::ResetTrackAnalysis,
             AdminCommand::Users { .. } => AdminCommandDisplay::Users,
+            AdminCommand::AddGlobalWebhook { .. } => AdminCommandDisplay::AddGlobalWebhook,
+            AdminCommand::AdminPanel => AdminCommandDisplay::AdminPanel,
+            AdminCommand::DeadLetters => AdminCommandDisplay::DeadLetters,
+            AdminCommand::DeadLetter { .. } => AdminCommandDisplay::DeadLetter,
+            AdminCommand::ReplayDeadLetter { .. } => AdminCommandDisplay::ReplayDeadLetter,
             AdminCommand::BuildInfo => AdminCommandDisplay::BuildInfo,
         };
     }
@@ -44,17 +44,37 @@ pub async fn handle(
             return actions::broadcast::handle(app, state, m, &locale).await;
         },
         AdminCommand::GetWordDefinition { locale, word } => {
-            return actions::word_definition::handle_definition(app, m, locale, word, false).await;
+            return actions::word_definition::handle_definition(app, state, m, locale, word, false)
+                .await;
         },
         AdminCommand::ResetWordDefinition { locale, word } => {
-            return actions::word_definition::handle_definition(app, m, locale, word, true).await;
+            return actions::word_definition::handle_definition(app, state, m, locale, word, true)
+                .await;
         },
         AdminCommand::ListWordDefinitions { locale } => {
             return actions::word_definition::handle_list(app, m, locale).await;
         },
+        AdminCommand::ResetTrackAnalysis { track_id } => {
+            return actions::analyze::handle_reset(app, m, &track_id).await;
+        },
         AdminCommand::Users { user_id } => {
             return actions::admin_users::handle_command(app, state, m, user_id).await;
         },
+        AdminCommand::AddGlobalWebhook { url } => {
+            return actions::webhook::handle_add(app, state, m.chat.id, &url, true).await;
+        },
+        AdminCommand::AdminPanel => {
+            return actions::dashboard::handle_admin(app, state, m.chat.id).await;
+        },
+        AdminCommand::DeadLetters => {
+            return actions::dead_letters::handle_list(app, m).await;
+        },
+        AdminCommand::DeadLetter { id } => {
+            return actions::dead_letters::handle_show(app, m, &id).await;
+        },
+        AdminCommand::ReplayDeadLetter { id } => {
+            return actions::dead_letters::handle_replay(app, m, &id).await;
+        },
         AdminCommand::BuildInfo => {
             app.bot()
                 .send_message(
@@ -62,6 +62,9 @@ pub async fn handle(
         UserCommand::Like => {
             return actions::like::handle(app, state, m).await;
         },
+        UserCommand::Ask { question } => {
+            return actions::ask::handle(app, state, m, &question).await;
+        },
         UserCommand::Stats => return actions::stats::handle(app, state, m).await,
         UserCommand::Details => {
             return actions::details::handle_current(app, state, m.chat.id).await;
@@ -89,6 +92,15 @@ pub async fn handle(
         UserCommand::ToggleProfanityCheck => {
             return actions::settings::handle_toggle_profanity_check(app, state, m.chat.id).await;
         },
+        UserCommand::ToggleKidMode => {
+            return actions::settings::handle_toggle_kid_mode(app, state, m.chat.id).await;
+        },
+        UserCommand::ToggleExplicitSkip => {
+            return actions::settings::handle_toggle_skip_explicit(app, state, m.chat.id).await;
+        },
+        UserCommand::KidSafe { pin } => {
+            return actions::kid_safe::handle(app, state, m, &pin).await;
+        },
         UserCommand::AddWhitelistWord { word } => {
             return actions::user_word_whitelist::handle_add_word(app, state, m.chat.id, word)
                 .await;
@@ -109,6 +121,27 @@ pub async fn handle(
         UserCommand::AISlopDetection => {
             return actions::ai_slop_detection::handle(app, state, m.chat.id).await;
         },
+        UserCommand::Webhooks => {
+            return actions::webhook::handle_list(app, state, m.chat.id).await;
+        },
+        UserCommand::AddWebhook { args } => {
+            return actions::webhook::handle_add(app, state, m.chat.id, &args, false).await;
+        },
+        UserCommand::RemoveWebhook { id } => {
+            return actions::webhook::handle_remove(app, state, m.chat.id, &id).await;
+        },
+        UserCommand::Dashboard => {
+            return actions::dashboard::handle(app, state, m.chat.id).await;
+        },
+        UserCommand::ApiToken => {
+            return actions::api_token::handle_issue(app, state, m.chat.id).await;
+        },
+        UserCommand::RevokeApiToken => {
+            return actions::api_token::handle_revoke(app, state, m.chat.id).await;
+        },
+        UserCommand::App => {
+            return actions::web_app::handle(app, state, m.chat.id).await;
+        },
         UserCommand::Skippage { days } => {
             return actions::skippage::handle(app, state, m.chat.id, days).await;
         },
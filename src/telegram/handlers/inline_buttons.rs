@@ -36,7 +36,8 @@ pub async fn handle(
 
         match button {
             AdminInlineButtons::RegenerateWordDefinition { locale, word } => {
-                actions::word_definition::handle_inline_regenerate(app, q, m, locale, word).await?;
+                actions::word_definition::handle_inline_regenerate(app, state, q, m, locale, word)
+                    .await?;
             },
             AdminInlineButtons::WordDefinitionsPage { locale, page, .. } => {
                 actions::word_definition::handle_inline_list(app, q, m, locale, page).await?;
@@ -145,7 +146,13 @@ pub async fn handle(
             actions::ignore::handle_inline(app, state, q, m, &id).await?;
         },
         InlineButtons::Analyze(id) => {
-            actions::analyze::handle_inline(app, state, q, m, &id).await?;
+            Box::pin(actions::analyze::handle_inline(app, state, q, m, &id)).await?;
+        },
+        InlineButtons::Ask(id) => {
+            Box::pin(actions::ask::handle_inline(app, state, q, m, &id)).await?;
+        },
+        InlineButtons::Translate(id) => {
+            Box::pin(actions::translate::handle_inline(app, state, q, m, &id)).await?;
         },
         InlineButtons::SongLinks(id) => {
             actions::song_links::handle_inline(app, state, q, m, &id).await?;
@@ -154,7 +161,13 @@ pub async fn handle(
             actions::magic::handle_inline(app, state, q, m).await?;
         },
         InlineButtons::Recommendasion => {
-            actions::recommendasion::handle_inline(app, state, q, m).await?;
+            Box::pin(actions::recommendasion::handle_inline(app, state, q, m)).await?;
+        },
+        InlineButtons::RecommendasionMode(mode, _) => {
+            actions::recommendasion::handle_inline_mode(app, state, q, m, mode).await?;
+        },
+        InlineButtons::RecommendasionPlaylist(enabled) => {
+            actions::recommendasion::handle_inline_playlist(app, state, q, m, !enabled).await?;
         },
         InlineButtons::SkippageEnable(to_enable) => {
             actions::skippage::handle_inline(app, state, q, m, to_enable).await?;
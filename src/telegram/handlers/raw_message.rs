@@ -1,6 +1,6 @@
 use teloxide::types::Message;
 
-use super::HandleStatus;
+use super::{HandleStatus, return_if_handled};
 use crate::app::App;
 use crate::telegram::actions;
 use crate::user::UserState;
@@ -15,6 +15,10 @@ pub async fn handle(
         return Ok(HandleStatus::Skipped);
     };
 
+    if let Some(reply) = m.reply_to_message() {
+        return_if_handled!(actions::recommendasion::handle_hint(app, state, m, reply).await?);
+    }
+
     if text == "-" {
         return actions::dislike::handle(app, state, m).await;
     }
@@ -23,5 +27,9 @@ pub async fn handle(
         return actions::like::handle(app, state, m).await;
     }
 
+    if let Some(reply) = m.reply_to_message() {
+        return_if_handled!(actions::ask::handle_reply(app, state, m, reply).await?);
+    }
+
     Ok(HandleStatus::Skipped)
 }
@@ -5,17 +5,22 @@ use std::str::FromStr;
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind};
 
 use crate::entity::prelude::{TrackStatus, UserAISlopDetection};
+use crate::services::RecommendasionMode;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub enum InlineButtons {
     Dislike(String),
     Ignore(String),
     Analyze(String),
+    Ask(String),
+    Translate(String),
     SongLinks(String),
     Magic,
     AISlopDetection(UserAISlopDetection, bool),
     SkippageEnable(bool),
     Recommendasion,
+    RecommendasionMode(RecommendasionMode, bool),
+    RecommendasionPlaylist(bool),
 }
 
 impl InlineButtons {
@@ -25,9 +30,50 @@ impl InlineButtons {
             Self::Dislike(_) => t!("inline-buttons.dislike", locale = locale),
             Self::Ignore(_) => t!("inline-buttons.ignore", locale = locale),
             Self::Analyze(_) => t!("inline-buttons.analyze", locale = locale),
+            Self::Ask(_) => t!("inline-buttons.ask", locale = locale),
+            Self::Translate(_) => t!("inline-buttons.translate", locale = locale),
             Self::SongLinks(_) => t!("song-links.button", locale = locale),
             Self::Magic => t!("magic.button", locale = locale),
             Self::Recommendasion => t!("recommendasion.button", locale = locale),
+            Self::RecommendasionMode(mode, selected) => {
+                let mark = if *selected { "✅ " } else { "" };
+                match mode {
+                    RecommendasionMode::Favorites => {
+                        t!(
+                            "recommendasion.mode-favorites",
+                            locale = locale,
+                            mark = mark
+                        )
+                    },
+                    RecommendasionMode::DeepCuts => {
+                        t!(
+                            "recommendasion.mode-deep-cuts",
+                            locale = locale,
+                            mark = mark
+                        )
+                    },
+                    RecommendasionMode::SimilarToCurrent => t!(
+                        "recommendasion.mode-similar-to-current",
+                        locale = locale,
+                        mark = mark
+                    ),
+                    RecommendasionMode::NewReleases => {
+                        t!(
+                            "recommendasion.mode-new-releases",
+                            locale = locale,
+                            mark = mark
+                        )
+                    },
+                }
+            },
+            Self::RecommendasionPlaylist(enabled) => {
+                let mark = if *enabled { "✅ " } else { "" };
+                t!(
+                    "recommendasion.button-playlist",
+                    locale = locale,
+                    mark = mark
+                )
+            },
             Self::SkippageEnable(to_enable) => {
                 if *to_enable {
                     t!("skippage.enable-button", locale = locale)
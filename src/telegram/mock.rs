@@ -0,0 +1,380 @@
+//! In-process Telegram Bot API for tests.
+//!
+//! Records every call with its parameters and serves injected updates to `getUpdates`.
+//! Point the bot at it with `TELEGRAM_API_URL`.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse as _, Response};
+use axum::routing::post;
+use chrono::Utc;
+use serde_json::{Value, json};
+
+/// Telegram limit for `callback_data` of inline buttons
+pub const CALLBACK_DATA_MAX_LEN: usize = 64;
+pub const BOT_ID: i64 = 1;
+
+pub struct MockCall {
+    /// Lowercase method name, e.g. `sendmessage`
+    pub method: String,
+    pub params: Value,
+}
+
+#[derive(Default)]
+pub struct MockState {
+    pub calls: Vec<MockCall>,
+    pub updates: VecDeque<Value>,
+    next_update_id: i64,
+    next_message_id: i64,
+}
+
+impl MockState {
+    /// Queues update for `getUpdates`, `update_id` is assigned automatically
+    pub fn inject(&mut self, mut update: Value) {
+        self.next_update_id += 1;
+        update["update_id"] = json!(self.next_update_id);
+
+        self.updates.push_back(update);
+    }
+
+    pub fn inject_callback_query(&mut self, user_id: i64, data: &str) {
+        self.inject(json!({
+            "callback_query": {
+                "id": format!("{}", self.next_update_id + 1),
+                "from": user(user_id),
+                "chat_instance": "mock",
+                "data": data,
+            },
+        }));
+    }
+
+    /// Callback query of a button under bot message in the user chat
+    pub fn inject_button_press(&mut self, user_id: i64, data: &str) {
+        self.next_message_id += 1;
+
+        let mut message = message(self.next_message_id, user_id, "Track");
+        message["from"] = user(BOT_ID);
+
+        self.inject(json!({
+            "callback_query": {
+                "id": format!("{}", self.next_update_id + 1),
+                "from": user(user_id),
+                "message": message,
+                "chat_instance": "mock",
+                "data": data,
+            },
+        }));
+    }
+
+    pub fn inject_message(&mut self, user_id: i64, text: &str) {
+        self.next_message_id += 1;
+
+        let message = message(self.next_message_id, user_id, text);
+
+        self.inject(json!({ "message": message }));
+    }
+
+    /// Parameters of calls to `method`, case insensitive
+    #[must_use]
+    pub fn calls(&self, method: &str) -> Vec<Value> {
+        let method = method.to_lowercase();
+
+        self.calls
+            .iter()
+            .filter(|call| call.method == method)
+            .map(|call| call.params.clone())
+            .collect()
+    }
+}
+
+pub struct MockTelegram {
+    url: reqwest::Url,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTelegram {
+    /// Starts server on a random local port
+    pub async fn start() -> anyhow::Result<Self> {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?).parse()?;
+
+        let router = Router::new()
+            .route("/{token}/{method}", post(handle))
+            .with_state(state.clone());
+
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        Ok(Self { url, state })
+    }
+
+    #[must_use]
+    pub fn url(&self) -> reqwest::Url {
+        self.url.clone()
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().expect("Mock state lock")
+    }
+}
+
+fn user(id: i64) -> Value {
+    json!({ "id": id, "is_bot": id == BOT_ID, "first_name": format!("User {id}") })
+}
+
+fn message(message_id: i64, chat_id: i64, text: &str) -> Value {
+    json!({
+        "message_id": message_id,
+        "date": Utc::now().timestamp(),
+        "chat": { "id": chat_id, "type": "private", "first_name": format!("User {chat_id}") },
+        "from": user(chat_id),
+        "text": text,
+    })
+}
+
+fn ok(result: Value) -> Response {
+    let mut response = json!({ "ok": true });
+    response["result"] = result;
+
+    axum::Json(response).into_response()
+}
+
+fn bad_request(description: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        axum::Json(json!({
+            "ok": false,
+            "error_code": 400,
+            "description": format!("Bad Request: {description}"),
+        })),
+    )
+        .into_response()
+}
+
+/// Checks limits Telegram applies to inline keyboards
+fn validate_markup(params: &Value) -> Result<(), &'static str> {
+    let buttons = params["reply_markup"]["inline_keyboard"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_array)
+        .flatten();
+
+    for button in buttons {
+        if button["callback_data"]
+            .as_str()
+            .is_some_and(|data| data.is_empty() || data.len() > CALLBACK_DATA_MAX_LEN)
+        {
+            return Err("BUTTON_DATA_INVALID");
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Path((_token, method)): Path<(String, String)>,
+    body: Bytes,
+) -> Response {
+    respond(
+        &mut state.lock().expect("Mock state lock"),
+        &method.to_lowercase(),
+        &serde_json::from_slice(&body).unwrap_or_default(),
+    )
+}
+
+fn respond(state: &mut MockState, method: &str, params: &Value) -> Response {
+    if let Err(description) = validate_markup(params) {
+        return bad_request(description);
+    }
+
+    state.calls.push(MockCall {
+        method: method.to_owned(),
+        params: params.clone(),
+    });
+
+    let chat_id = params["chat_id"].as_i64().unwrap_or_default();
+    let text = params["text"].as_str().unwrap_or_default();
+
+    match method {
+        "getme" => ok(json!({
+            "id": BOT_ID,
+            "is_bot": true,
+            "first_name": "Rustify",
+            "username": "rustify_mock_bot",
+            "can_join_groups": false,
+            "can_read_all_group_messages": false,
+            "supports_inline_queries": false,
+            "has_main_web_app": false,
+        })),
+        "getupdates" => {
+            let offset = params["offset"].as_i64().unwrap_or_default();
+
+            state
+                .updates
+                .retain(|update| update["update_id"].as_i64() >= Some(offset));
+
+            ok(json!(state.updates))
+        },
+        "sendmessage" => {
+            state.next_message_id += 1;
+
+            let mut message = message(state.next_message_id, chat_id, text);
+            message["from"] = user(BOT_ID);
+
+            ok(message)
+        },
+        "editmessagetext" | "editmessagereplymarkup" => {
+            let mut message = message(
+                params["message_id"].as_i64().unwrap_or_default(),
+                chat_id,
+                text,
+            );
+            message["from"] = user(BOT_ID);
+
+            ok(message)
+        },
+        _ => ok(json!(true)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use sea_orm::Iterable as _;
+    use teloxide::prelude::*;
+    use teloxide::types::{InlineKeyboardMarkup, ParseMode, UpdateKind};
+
+    use super::*;
+    use crate::entity::prelude::{TrackStatus, UserAISlopDetection};
+    use crate::telegram::actions;
+    use crate::telegram::inline_buttons::InlineButtons;
+    use crate::telegram::inline_buttons_admin::AdminInlineButtons;
+
+    const USER_ID: i64 = 42;
+    const TRACK_ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+
+    fn bot(mock: &MockTelegram) -> teloxide::adaptors::DefaultParseMode<Bot> {
+        Bot::new("123456:TEST")
+            .set_api_url(mock.url())
+            .parse_mode(ParseMode::Html)
+    }
+
+    #[tokio::test]
+    async fn test_send_message_uses_html() {
+        let mock = MockTelegram::start().await.unwrap();
+
+        let message = bot(&mock)
+            .send_message(ChatId(USER_ID), "<b>Hello</b>")
+            .await
+            .unwrap();
+
+        assert_eq!(message.chat.id, ChatId(USER_ID));
+
+        let calls = mock.state().calls("sendMessage");
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["parse_mode"], "HTML");
+        assert_eq!(calls[0]["text"], "<b>Hello</b>");
+    }
+
+    #[tokio::test]
+    async fn test_inline_buttons_fit_telegram_limits() {
+        let mock = MockTelegram::start().await.unwrap();
+        let bot = bot(&mock);
+
+        let mut keyboards = vec![];
+
+        for status in [
+            TrackStatus::None,
+            TrackStatus::Disliked,
+            TrackStatus::Ignore,
+        ] {
+            keyboards.push(InlineButtons::from_track_status(status, TRACK_ID, "en"));
+        }
+
+        for status in UserAISlopDetection::iter() {
+            keyboards.push(actions::ai_slop_detection::get_keyboard(status, "en"));
+        }
+
+        for keyboard in keyboards {
+            bot.send_message(ChatId(USER_ID), "Track")
+                .reply_markup(InlineKeyboardMarkup::new(keyboard))
+                .await
+                .unwrap();
+        }
+
+        let calls = mock.state().calls("sendMessage");
+
+        for call in calls {
+            for row in call["reply_markup"]["inline_keyboard"].as_array().unwrap() {
+                for button in row.as_array().unwrap() {
+                    let data = button["callback_data"].as_str().unwrap();
+
+                    assert!(InlineButtons::from_str(data).is_ok(), "{data}");
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_callback_data_is_rejected() {
+        let mock = MockTelegram::start().await.unwrap();
+
+        let button = InlineButtons::Dislike("x".repeat(CALLBACK_DATA_MAX_LEN))
+            .into_inline_keyboard_button("en");
+
+        let res = bot(&mock)
+            .send_message(ChatId(USER_ID), "Track")
+            .reply_markup(InlineKeyboardMarkup::new([[button]]))
+            .await;
+
+        assert!(res.is_err());
+        assert!(mock.state().calls("sendMessage").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_injected_updates() {
+        let mock = MockTelegram::start().await.unwrap();
+        let bot = bot(&mock);
+
+        let data = AdminInlineButtons::RegenerateWordDefinition {
+            locale: "en".into(),
+            word: "hello".into(),
+        }
+        .to_string();
+
+        mock.state().inject_message(USER_ID, "/start");
+        mock.state().inject_callback_query(USER_ID, &data);
+
+        let updates = bot.get_updates().await.unwrap();
+
+        assert_eq!(updates.len(), 2);
+
+        let UpdateKind::Message(message) = &updates[0].kind else {
+            panic!("Message expected");
+        };
+
+        assert_eq!(message.text(), Some("/start"));
+
+        let UpdateKind::CallbackQuery(q) = &updates[1].kind else {
+            panic!("Callback query expected");
+        };
+
+        assert!(matches!(
+            AdminInlineButtons::from_str(q.data.as_deref().unwrap()),
+            Ok(AdminInlineButtons::RegenerateWordDefinition { .. })
+        ));
+
+        let offset = i32::try_from(updates[1].id.0).unwrap() + 1;
+
+        assert!(bot.get_updates().offset(offset).await.unwrap().is_empty());
+    }
+}
@@ -6,6 +6,8 @@ pub mod inline_buttons;
 pub mod inline_buttons_actions;
 pub mod inline_buttons_admin;
 pub mod keyboards;
+#[cfg(test)]
+pub mod mock;
 pub mod utils;
 
 pub const MESSAGE_MAX_LEN: usize = 4096;
@@ -0,0 +1,102 @@
+//! Application wired to Spotify and Telegram mocks for flow tests.
+//!
+//! Postgres and Redis are real, their URLs are read from `TEST_DATABASE_URL` and `TEST_REDIS_URL`.
+//! Tests using it are ignored by default, run them with `cargo test -- --ignored`.
+
+use anyhow::Context as _;
+use chrono::{Duration, Utc};
+use rand::RngExt as _;
+use rspotify::Token;
+
+use crate::app::App;
+use crate::services::UserService;
+use crate::spotify::auth::SpotifyAuthService;
+use crate::spotify::mock::{ACCESS_TOKEN, MockSpotify, REFRESH_TOKEN};
+use crate::spotify::scopes::{self, Feature};
+use crate::telegram::mock::MockTelegram;
+use crate::user::UserState;
+
+pub struct TestApp {
+    pub app: &'static App,
+    pub spotify: MockSpotify,
+    pub telegram: MockTelegram,
+}
+
+impl TestApp {
+    pub async fn start() -> anyhow::Result<Self> {
+        let spotify = MockSpotify::start().await?;
+        let telegram = MockTelegram::start().await?;
+
+        let vars = [
+            ("TELEGRAM_BOT_TOKEN", "123456:TEST".to_owned()),
+            ("TELEGRAM_API_URL", telegram.url().to_string()),
+            (
+                "DATABASE_URL",
+                std::env::var("TEST_DATABASE_URL").context("TEST_DATABASE_URL is not set")?,
+            ),
+            (
+                "REDIS_URL",
+                std::env::var("TEST_REDIS_URL").context("TEST_REDIS_URL is not set")?,
+            ),
+            ("SPOTIFY_ID", "client-id".to_owned()),
+            ("SPOTIFY_SECRET", "client-secret".to_owned()),
+            ("SPOTIFY_API_URL", spotify.api_url()),
+            ("SPOTIFY_AUTH_URL", spotify.auth_url()),
+            ("GENIUS_ACCESS_TOKEN", "genius-token".to_owned()),
+            // Nothing listens there, lyrics are never found
+            ("GENIUS_SERVICE_URL", "http://127.0.0.1:9".to_owned()),
+        ];
+
+        let app = App::init_from_iter(vars.map(|(key, value)| (key.to_owned(), value))).await?;
+
+        Ok(Self {
+            app,
+            spotify,
+            telegram,
+        })
+    }
+
+    /// Redis connection for tests of services which don't need the whole app
+    pub async fn redis_conn() -> anyhow::Result<deadpool_redis::Connection> {
+        let redis_url = std::env::var("TEST_REDIS_URL").context("TEST_REDIS_URL is not set")?;
+
+        Ok(deadpool_redis::Config::from_url(redis_url)
+            .create_pool(Some(deadpool_redis::Runtime::Tokio1))?
+            .get()
+            .await?)
+    }
+
+    /// Random Telegram user id, tests share database and Redis
+    #[must_use]
+    pub fn user_id() -> String {
+        rand::rng()
+            .random_range(1_000_000_000..i64::from(i32::MAX) * 1000)
+            .to_string()
+    }
+
+    /// User logged in to Spotify with scopes of all features
+    pub async fn login(&self, user_id: &str) -> anyhow::Result<UserState> {
+        UserService::upsert_by_id(self.app.db(), user_id).await?;
+
+        SpotifyAuthService::set_token(
+            self.app.db(),
+            user_id,
+            Token {
+                access_token: ACCESS_TOKEN.to_owned(),
+                refresh_token: Some(REFRESH_TOKEN.to_owned()),
+                expires_at: Some(Utc::now() + Duration::hours(1)),
+                scopes: scopes::scopes_for(&[
+                    Feature::AutoSkip,
+                    Feature::Collection,
+                    Feature::MagicPlaylist,
+                    Feature::Recommendations,
+                    Feature::RecommendationsPlaylist,
+                ]),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        self.app.user_state(user_id).await
+    }
+}
@@ -5,8 +5,10 @@ use teloxide::prelude::*;
 
 use crate::app::App;
 use crate::infrastructure::error_handler;
-use crate::services::{TrackStatusService, UserService};
-use crate::spotify::ShortTrack;
+use crate::queue::webhook;
+use crate::services::{TrackStatusService, UserService, WebhookEvent};
+use crate::spotify::scopes::Feature;
+use crate::spotify::{ClientResultExt as _, ShortTrack};
 use crate::user::UserState;
 
 #[tracing::instrument(
@@ -26,28 +28,41 @@ pub async fn handle(
         state
             .spotify()
             .await
-            .next_track(None)
+            .request(|spotify| spotify.next_track(None))
+            .await
+            .typed()
             .await
             .context("Skip current track")?;
 
         TrackStatusService::increase_skips(app.db(), state.user_id(), track.id()).await?;
 
+        webhook::emit(
+            app,
+            state.user_id(),
+            WebhookEvent::TrackAutoSkipped,
+            webhook::track_skipped_data(track, "disliked"),
+        )
+        .await;
+
         let Some(context) = context else {
             return Ok(());
         };
 
+        if !state.has_features(&[Feature::Collection]).await {
+            return Ok(());
+        }
+
         match context._type {
             SpotifyType::Playlist => {
                 let hate: Option<PlayableId> = Some(track.raw_id().clone().into());
+                let playlist_id = PlaylistId::from_id_or_uri(&context.uri)?;
 
                 let res = state
                     .spotify()
                     .await
-                    .playlist_remove_all_occurrences_of_items(
-                        PlaylistId::from_id_or_uri(&context.uri)?,
-                        hate,
-                        None,
-                    )
+                    .request(|spotify| {
+                        spotify.playlist_remove_all_occurrences_of_items(playlist_id, hate, None)
+                    })
                     .await;
 
                 // It's a bit too much to check if user owns this playlist
@@ -63,7 +78,11 @@ pub async fn handle(
                 state
                     .spotify()
                     .await
-                    .current_user_saved_tracks_delete(Some(track.raw_id().clone()))
+                    .request(|spotify| {
+                        spotify.current_user_saved_tracks_delete(Some(track.raw_id().clone()))
+                    })
+                    .await
+                    .typed()
                     .await?;
 
                 UserService::increase_stats_query(state.user_id())
@@ -94,3 +113,108 @@ pub async fn handle(
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use rspotify::model::Id as _;
+    use rspotify::prelude::OAuthClient as _;
+
+    use super::*;
+    use crate::spotify::CurrentlyPlaying;
+    use crate::spotify::mock::{USER_ID, track};
+    use crate::testing::TestApp;
+
+    const TRACK_ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+    const NEXT_TRACK_ID: &str = "7GhIk7Il098yCjg4BQjzvb";
+
+    async fn playing(state: &UserState) -> (ShortTrack, Option<SpotifyContext>) {
+        let CurrentlyPlaying::Ok(track, context, _) =
+            state.spotify().await.current_playing_wrapped().await
+        else {
+            panic!("Track should be playing");
+        };
+
+        (*track, context)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_skips_and_removes_from_playlist() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        let state = test.login(&user_id).await.unwrap();
+
+        let playlist = state
+            .spotify()
+            .await
+            .request(|spotify| {
+                spotify.user_playlist_create(
+                    rspotify::model::UserId::from_id(USER_ID).unwrap(),
+                    "Mix",
+                    None,
+                    None,
+                    None,
+                )
+            })
+            .await
+            .unwrap();
+
+        {
+            let mut spotify = test.spotify.state();
+
+            spotify.premium = true;
+            spotify.add_track(track(TRACK_ID, 200));
+            spotify.add_track(track(NEXT_TRACK_ID, 200));
+            spotify.queue.push_back(NEXT_TRACK_ID.to_owned());
+            spotify
+                .playlists
+                .get_mut(playlist.id.id())
+                .unwrap()
+                .track_ids = vec![TRACK_ID.to_owned(), NEXT_TRACK_ID.to_owned()];
+            spotify.play(TRACK_ID, Duration::seconds(10));
+            spotify.playing.as_mut().unwrap().context =
+                Some((SpotifyType::Playlist, playlist.id.uri()));
+        }
+
+        let (track, context) = playing(&state).await;
+
+        handle(test.app, &state, &track, context.as_ref())
+            .await
+            .unwrap();
+
+        assert_eq!(test.spotify.state().requested("POST /v1/me/player/next"), 1);
+        assert_eq!(
+            test.spotify.state().playlists[playlist.id.id()].track_ids,
+            vec![NEXT_TRACK_ID]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_notifies_without_premium() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        let state = test.login(&user_id).await.unwrap();
+
+        {
+            let mut spotify = test.spotify.state();
+
+            spotify.add_track(track(TRACK_ID, 200));
+            spotify.play(TRACK_ID, Duration::seconds(10));
+        }
+
+        let (track, context) = playing(&state).await;
+
+        handle(test.app, &state, &track, context.as_ref())
+            .await
+            .unwrap();
+
+        assert_eq!(test.spotify.state().requested("POST /v1/me/player/next"), 0);
+
+        let messages = test.telegram.state().calls("sendMessage");
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["chat_id"].to_string(), user_id);
+    }
+}
@@ -0,0 +1,66 @@
+use teloxide::prelude::Requester as _;
+use teloxide::types::ChatId;
+
+use crate::app::App;
+use crate::infrastructure::error::ErrorClass;
+use crate::infrastructure::error_handler::ErrorHandlingResult;
+use crate::services::{ErrorBudgetService, UserService};
+
+async fn send(app: &'static App, user_id: &str, key: &str) -> anyhow::Result<()> {
+    let Some(user) = UserService::get_by_id(app.db(), user_id).await? else {
+        return Ok(());
+    };
+
+    let locale = user.locale.as_ref();
+
+    app.bot()
+        .send_message(ChatId(user_id.parse()?), t!(key, locale = locale))
+        .await?;
+
+    Ok(())
+}
+
+/// Tells user that checks keep failing, once per budget exhaustion
+#[tracing::instrument(skip_all, fields(%user_id))]
+pub async fn failure(
+    app: &'static App,
+    user_id: &str,
+    res: &ErrorHandlingResult,
+) -> anyhow::Result<()> {
+    // User already knows what's wrong
+    if res.user_notified {
+        return Ok(());
+    }
+
+    let class = res.class.unwrap_or(ErrorClass::Permanent);
+
+    let notify = ErrorBudgetService::failure(&mut app.redis_conn().await?, user_id, class).await?;
+
+    if !notify {
+        return Ok(());
+    }
+
+    tracing::info!(?class, "Checks keep failing, notifying user");
+
+    let key = match class {
+        ErrorClass::Retryable => "error.not-working-retryable",
+        // User actionable errors notify user by policy, so they don't get here
+        ErrorClass::UserActionable | ErrorClass::Permanent => "error.not-working-permanent",
+    };
+
+    send(app, user_id, key).await
+}
+
+/// Tells user that checks work again, if they were notified about failures
+#[tracing::instrument(skip_all, fields(%user_id))]
+pub async fn success(app: &'static App, user_id: &str) -> anyhow::Result<()> {
+    let recovered = ErrorBudgetService::success(&mut app.redis_conn().await?, user_id).await?;
+
+    if !recovered {
+        return Ok(());
+    }
+
+    tracing::info!("Checks recovered, notifying user");
+
+    send(app, user_id, "error.not-working-recovered").await
+}
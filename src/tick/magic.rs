@@ -3,7 +3,7 @@ use rspotify::model::{Context as SpotifyContext, Id as _, PlaylistId, Type as Sp
 
 use crate::app::App;
 use crate::services::MagicService;
-use crate::spotify::ShortTrack;
+use crate::spotify::{ClientResultExt as _, ShortTrack};
 use crate::user::UserState;
 
 #[tracing::instrument(
@@ -49,11 +49,15 @@ pub async fn handle(
     state
         .spotify()
         .await
-        .playlist_remove_all_occurrences_of_items(
-            playlist_id,
-            Some(track.raw_id().to_owned().into()),
-            None,
-        )
+        .request(|spotify| {
+            spotify.playlist_remove_all_occurrences_of_items(
+                playlist_id,
+                Some(track.raw_id().to_owned().into()),
+                None,
+            )
+        })
+        .await
+        .typed()
         .await?;
 
     MagicService::set_already_removed(&mut redis_conn, state.user_id(), track.id()).await?;
@@ -1,16 +1,18 @@
 mod disliked_track;
+mod error_budget;
 mod magic;
+mod recommendasion;
 mod skippage;
-mod user;
+pub(crate) mod user;
 
 use std::sync::{Arc, LazyLock};
 use std::time::Duration;
 
 use anyhow::Context as _;
-use sea_orm::TransactionTrait as _;
+use itertools::Itertools as _;
 use teloxide::prelude::Requester as _;
 use teloxide::types::ChatId;
-use tokio::sync::{Semaphore, broadcast};
+use tokio::sync::{RwLock, Semaphore, broadcast};
 use tokio::time::Instant;
 use tracing::Instrument as _;
 use user::CheckUserResult;
@@ -18,13 +20,27 @@ use user::CheckUserResult;
 use crate::app::App;
 use crate::entity::prelude::UserStatus;
 use crate::infrastructure::error_handler;
-use crate::services::{SpotifyPollingBackoffService, UserService};
+use crate::queue::webhook;
+use crate::services::{
+    SpotifyPollingBackoffService,
+    SpotifyRateLimitService,
+    TickSchedulerService,
+    TickShardService,
+    UserService,
+    WebhookEvent,
+};
 use crate::spotify::auth::SpotifyAuthService;
 use crate::telegram::commands::UserCommandDisplay;
 use crate::utils;
 
-const CHECK_INTERVAL: Duration = Duration::from_secs(3);
-const PARALLEL_THREADS_COUNT: usize = 2;
+/// How often due users are taken from the schedule
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// How often schedule is synced with active users in the database
+const SYNC_INTERVAL: Duration = Duration::from_secs(15);
+/// How often shard leases are renewed, should be few times less than lease TTL
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// Max users taken from the schedule in one tick
+const MAX_USERS_PER_TICK: usize = 1000;
 
 pub static PROCESS_TIME_CHANNEL: LazyLock<(
     broadcast::Sender<CheckReport>,
@@ -37,18 +53,52 @@ pub struct CheckReport {
     pub users_process_time: Duration,
     pub users_checked: usize,
     pub users_processed: usize,
+    pub users_scheduled: usize,
     pub threads_count: usize,
+    pub worker_id: &'static str,
+    /// Owned shards with amount of scheduled users in them
+    pub shards: Vec<(u32, usize)>,
 }
 
 #[tracing::instrument(skip_all)]
-async fn process(app: &'static App) -> anyhow::Result<()> {
+async fn process(app: &'static App, shards: &RwLock<Vec<u32>>) -> anyhow::Result<()> {
     let start = Instant::now();
 
-    let user_ids = SpotifyAuthService::get_active_unsuspended_user_ids(app.db())
-        .await
-        .context("Get users for processing")?;
+    let budget = SpotifyRateLimitService::budget(
+        &mut app.redis_conn().await?,
+        app.spotify_rate_limiter().client_id(),
+    )
+    .await?;
+
+    if let Some(retry_after) = budget.retry_after {
+        tracing::warn!(%retry_after, "Spotify asked to retry later, skipping tick");
+
+        return Ok(());
+    }
+
+    if budget.is_low() {
+        // Leave the rest of the budget to interactive requests
+        tracing::warn!(
+            tokens = budget.tokens,
+            "Spotify rate budget is low, slowing down tick"
+        );
+
+        tokio::time::sleep(CHECK_INTERVAL * 3).await;
+    }
 
-    let semaphore = Arc::new(Semaphore::new(PARALLEL_THREADS_COUNT));
+    let mut redis_conn = app.redis_conn().await?;
+    let owned_shards = shards.read().await.clone();
+
+    let shards = TickSchedulerService::shard_sizes(&mut redis_conn, &owned_shards).await?;
+    let users_scheduled = shards.iter().map(|(_, size)| size).sum();
+    let threads_count = TickSchedulerService::threads_count(users_scheduled);
+
+    let user_ids =
+        TickSchedulerService::claim_due(&mut redis_conn, &owned_shards, MAX_USERS_PER_TICK)
+            .await
+            .context("Get users for processing")?;
+
+    let semaphore = Arc::new(Semaphore::new(threads_count));
     let user_ids_len = user_ids.len();
     let mut join_handles = Vec::with_capacity(user_ids_len);
 
@@ -67,11 +117,21 @@ async fn process(app: &'static App) -> anyhow::Result<()> {
 
                 let checked: anyhow::Result<_> = match res {
                     Err(mut err) => {
-                        error_handler::handle(&mut err, app, &user_id, "en").await;
+                        let res = error_handler::handle(&mut err, app, &user_id, "en").await;
+
+                        if let Err(err) = error_budget::failure(app, &user_id, &res).await {
+                            tracing::error!(err = ?err, "Failed to track error budget");
+                        }
 
                         Err(err)
                     },
-                    Ok(res) => Ok((user_id, res)),
+                    Ok(res) => {
+                        if let Err(err) = error_budget::success(app, &user_id).await {
+                            tracing::error!(err = ?err, "Failed to track error budget");
+                        }
+
+                        Ok((user_id, res))
+                    },
                 };
 
                 checked
@@ -81,18 +141,30 @@ async fn process(app: &'static App) -> anyhow::Result<()> {
     }
 
     let mut users_processed = 0;
-    let mut redis_conn = app.redis_conn().await?;
-    let mut users_to_suspend = vec![];
 
     for handle in join_handles {
         match handle.await.expect("Shouldn't fail") {
-            Ok((user_id, CheckUserResult::SkipSame)) => {
+            Ok((user_id, CheckUserResult::SkipSame { remaining })) => {
                 SpotifyPollingBackoffService::update_activity(&mut redis_conn, &user_id).await?;
+
+                TickSchedulerService::schedule_in(
+                    &mut redis_conn,
+                    &user_id,
+                    TickSchedulerService::playing_interval(remaining),
+                )
+                .await?;
             },
-            Ok((user_id, CheckUserResult::Complete)) => {
+            Ok((user_id, CheckUserResult::Complete { remaining })) => {
                 users_processed += 1;
 
                 SpotifyPollingBackoffService::update_activity(&mut redis_conn, &user_id).await?;
+
+                TickSchedulerService::schedule_in(
+                    &mut redis_conn,
+                    &user_id,
+                    TickSchedulerService::playing_interval(remaining),
+                )
+                .await?;
             },
             Ok((user_id, CheckUserResult::None(_))) => {
                 let suspend_for =
@@ -100,12 +172,23 @@ async fn process(app: &'static App) -> anyhow::Result<()> {
                         .await?;
 
                 if let Some(suspend_for) = suspend_for {
-                    users_to_suspend.push((user_id, suspend_for));
+                    TickSchedulerService::schedule_in(&mut redis_conn, &user_id, suspend_for)
+                        .await?;
                 } else {
+                    TickSchedulerService::remove(&mut redis_conn, &user_id).await?;
+
                     UserService::set_status(app.db(), &user_id, UserStatus::Inactive).await?;
 
                     tracing::info!(user_id, "User marked as inactive");
 
+                    webhook::emit(
+                        app,
+                        &user_id,
+                        WebhookEvent::UserStatusChanged,
+                        webhook::user_status_data(UserStatus::Inactive),
+                    )
+                    .await;
+
                     let (user, _) = UserService::upsert_by_id(app.db(), &user_id).await?;
 
                     let res = app
@@ -130,22 +213,15 @@ async fn process(app: &'static App) -> anyhow::Result<()> {
         }
     }
 
-    if !users_to_suspend.is_empty() {
-        let txn = app.db().begin().await?;
-
-        for (user_id, suspend_for) in users_to_suspend {
-            SpotifyAuthService::suspend_for(&txn, &user_id, suspend_for).await?;
-        }
-
-        txn.commit().await?;
-    }
-
     let report = CheckReport {
         check_interval: CHECK_INTERVAL,
         users_process_time: start.elapsed(),
-        threads_count: PARALLEL_THREADS_COUNT,
+        threads_count,
         users_checked: user_ids_len,
         users_processed,
+        users_scheduled,
+        worker_id: TickShardService::worker_id(),
+        shards,
     };
 
     PROCESS_TIME_CHANNEL.0.send(report).ok();
@@ -154,10 +230,86 @@ async fn process(app: &'static App) -> anyhow::Result<()> {
 }
 
 #[tracing::instrument(skip_all)]
-pub async fn check_playing(app: &'static App) {
-    utils::tick!(CHECK_INTERVAL, {
-        if let Err(err) = process(app).await {
-            tracing::error!(err = ?err, "Something went wrong");
+async fn sync_schedule(app: &'static App, shards: &RwLock<Vec<u32>>) -> anyhow::Result<()> {
+    let user_ids = SpotifyAuthService::get_active_unsuspended_user_ids(app.db())
+        .await
+        .context("Get users for scheduling")?;
+
+    let user_ids_by_shard = user_ids
+        .iter()
+        .map(|user_id| (TickShardService::shard_of(user_id), user_id.as_str()))
+        .into_group_map();
+
+    let owned_shards = shards.read().await.clone();
+    let mut redis_conn = app.redis_conn().await?;
+
+    for shard in owned_shards {
+        let user_ids = user_ids_by_shard
+            .get(&shard)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let removed = TickSchedulerService::sync(&mut redis_conn, shard, user_ids).await?;
+
+        if removed > 0 {
+            tracing::info!(shard, removed, "Removed inactive users from schedule");
         }
-    });
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn heartbeat(app: &'static App, shards: &RwLock<Vec<u32>>) -> anyhow::Result<()> {
+    let owned_shards = TickShardService::heartbeat(&mut app.redis_conn().await?).await?;
+
+    let previous = std::mem::replace(&mut *shards.write().await, owned_shards.clone());
+
+    if previous != owned_shards {
+        tracing::info!(
+            worker_id = TickShardService::worker_id(),
+            ?owned_shards,
+            "Tick shards reassigned"
+        );
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(worker_id = TickShardService::worker_id()))]
+pub async fn check_playing(app: &'static App) {
+    let shards = RwLock::new(vec![]);
+
+    tokio::join!(
+        Box::pin(async {
+            utils::tick!(HEARTBEAT_INTERVAL, {
+                if let Err(err) = heartbeat(app, &shards).await {
+                    tracing::error!(err = ?err, "Failed to renew tick shard leases");
+                }
+            });
+        }),
+        Box::pin(async {
+            utils::tick!(SYNC_INTERVAL, {
+                if let Err(err) = sync_schedule(app, &shards).await {
+                    tracing::error!(err = ?err, "Failed to sync tick schedule");
+                }
+            });
+        }),
+        Box::pin(async {
+            utils::tick!(CHECK_INTERVAL, {
+                if let Err(err) = process(app, &shards).await {
+                    tracing::error!(err = ?err, "Something went wrong");
+                }
+            });
+        }),
+    );
+
+    match app.redis_conn().await {
+        Ok(mut redis_conn) => {
+            if let Err(err) = TickShardService::release(&mut redis_conn).await {
+                tracing::error!(err = ?err, "Failed to release tick shards");
+            }
+        },
+        Err(err) => tracing::error!(err = ?err, "Failed to release tick shards"),
+    }
 }
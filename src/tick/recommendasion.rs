@@ -0,0 +1,86 @@
+use chrono::Duration;
+
+use crate::app::App;
+use crate::entity::prelude::RecommendasionOutcome;
+use crate::services::{
+    RecommendasionFeedbackService,
+    RecommendasionPlaying,
+    RecommendasionService,
+};
+use crate::spotify::ShortTrack;
+use crate::user::UserState;
+
+/// Share of the track which has to be played to count it as listened
+const LISTENED_SHARE: f64 = 0.8;
+/// Track which ended less than that before its end is listened as well
+const LISTENED_REMAINING_SECS: i64 = 30;
+
+/// Follows recommended track while it's playing and records how it ended
+#[tracing::instrument(
+    skip_all,
+    fields(
+        track_id = %track.id(),
+        track_name = %track.name_with_artists(),
+    )
+)]
+pub async fn handle(
+    app: &'static App,
+    state: &UserState,
+    track: &ShortTrack,
+    progress: Option<Duration>,
+) -> anyhow::Result<()> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    let playing = RecommendasionService::get_playing(&mut redis_conn, state.user_id()).await?;
+
+    if let Some(playing) = playing.filter(|playing| playing.track.id() != track.id()) {
+        let outcome = outcome_for(playing.track.duration_secs(), playing.progress_secs);
+
+        RecommendasionFeedbackService::record(app.db(), state.user_id(), &playing.track, outcome)
+            .await?;
+
+        RecommendasionService::remove_playing(&mut redis_conn, state.user_id()).await?;
+    }
+
+    let recommended =
+        RecommendasionService::is_recommended(&mut redis_conn, state.user_id(), track.id()).await?;
+
+    if !recommended {
+        return Ok(());
+    }
+
+    let playing = RecommendasionPlaying {
+        track: track.clone(),
+        progress_secs: progress.map_or(0, |progress| progress.num_seconds()),
+    };
+
+    RecommendasionService::save_playing(&mut redis_conn, state.user_id(), &playing).await?;
+
+    Ok(())
+}
+
+/// Outcome of a track by last seen progress before another track started
+fn outcome_for(duration_secs: i64, progress_secs: i64) -> RecommendasionOutcome {
+    let listened = progress_secs as f64 >= duration_secs as f64 * LISTENED_SHARE
+        || duration_secs - progress_secs <= LISTENED_REMAINING_SECS;
+
+    if listened {
+        RecommendasionOutcome::Listened
+    } else {
+        RecommendasionOutcome::Skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcome_by_progress() {
+        assert_eq!(outcome_for(200, 10), RecommendasionOutcome::Skipped);
+        assert_eq!(outcome_for(200, 150), RecommendasionOutcome::Skipped);
+        assert_eq!(outcome_for(200, 160), RecommendasionOutcome::Listened);
+        assert_eq!(outcome_for(600, 575), RecommendasionOutcome::Listened);
+        assert_eq!(outcome_for(600, 400), RecommendasionOutcome::Skipped);
+    }
+}
@@ -2,8 +2,9 @@ use anyhow::Context as _;
 use rspotify::clients::OAuthClient as _;
 
 use crate::app::App;
-use crate::services::SkippageService;
-use crate::spotify::ShortTrack;
+use crate::queue::webhook;
+use crate::services::{SkippageService, UserService, WebhookEvent};
+use crate::spotify::{ClientResultExt as _, ShortTrack};
 use crate::user::UserState;
 
 #[tracing::instrument(
@@ -38,9 +39,24 @@ pub async fn handle(
         state
             .spotify()
             .await
-            .next_track(None)
+            .request(|spotify| spotify.next_track(None))
+            .await
+            .typed()
             .await
             .context("Skip track in Spotify")?;
+
+        webhook::emit(
+            app,
+            state.user_id(),
+            WebhookEvent::TrackAutoSkipped,
+            webhook::track_skipped_data(track, "skippage"),
+        )
+        .await;
+
+        UserService::increase_stats_query(state.user_id())
+            .skipped_skippage()
+            .exec(app.db())
+            .await?;
     }
 
     SkippageService::save_current_playing(&mut redis_conn, state.user_id(), track.id()).await?;
@@ -1,4 +1,5 @@
 use anyhow::Context as _;
+use chrono::Duration;
 
 use super::skippage;
 use crate::app::App;
@@ -11,8 +12,14 @@ use crate::{queue, rickroll, spotify};
 #[allow(dead_code)]
 #[derive(Clone)]
 pub enum CheckUserResult {
-    SkipSame,
-    Complete,
+    /// Same track as on previous check, `remaining` is time left until its end
+    SkipSame {
+        remaining: Option<Duration>,
+    },
+    /// `remaining` is `None` when track was skipped during the check
+    Complete {
+        remaining: Option<Duration>,
+    },
     None(spotify::CurrentlyPlayingNoneReason),
 }
 
@@ -31,22 +38,28 @@ pub async fn check(app: &'static App, user_id: &str) -> anyhow::Result<CheckUser
 
     let playing = state.spotify().await.current_playing_wrapped().await;
 
-    let (track, context) = match playing {
+    let (track, context, progress) = match playing {
         CurrentlyPlaying::Err(err) => {
             return Err(err).context("Get currently playing track");
         },
         CurrentlyPlaying::None(reason) => {
             return Ok(CheckUserResult::None(reason));
         },
-        CurrentlyPlaying::Ok(track, context) => (track, context),
+        CurrentlyPlaying::Ok(track, context, progress) => (track, context, progress),
     };
 
+    let remaining = progress.map(|progress| Duration::seconds(track.duration_secs()) - progress);
+
     rickroll::queue(app, &state).await.ok();
 
+    if let Err(err) = super::recommendasion::handle(app, &state, &track, progress).await {
+        tracing::error!(err = ?err, "Failed to track Recommendasion feedback");
+    }
+
     let skippage_skipped = skippage::handle(app, &state, &track).await?;
 
     if skippage_skipped {
-        return Ok(CheckUserResult::Complete);
+        return Ok(CheckUserResult::Complete { remaining: None });
     }
 
     super::magic::handle(app, &state, &track, context.as_ref())
@@ -57,12 +70,18 @@ pub async fn check(app: &'static App, user_id: &str) -> anyhow::Result<CheckUser
 
     match status {
         TrackStatus::Disliked => {
-            if state.user().cfg_skip_tracks {
+            if state.user().skip_tracks() {
                 super::disliked_track::handle(app, &state, &track, context.as_ref()).await?;
+
+                return Ok(CheckUserResult::Complete { remaining: None });
             }
         },
         TrackStatus::None => {
-            if state.user().cfg_check_profanity || !state.user().cfg_ai_slop_detection.is_ignore() {
+            if state.user().check_profanity()
+                || state.user().kid_mode()
+                || state.user().skip_explicit()
+                || !state.user().ai_slop_detection().is_ignore()
+            {
                 let changed = UserService::sync_current_playing(
                     app.redis_conn().await?,
                     state.user_id(),
@@ -71,7 +90,7 @@ pub async fn check(app: &'static App, user_id: &str) -> anyhow::Result<CheckUser
                 .await?;
 
                 if !changed {
-                    return Ok(CheckUserResult::SkipSame);
+                    return Ok(CheckUserResult::SkipSame { remaining });
                 }
 
                 queue::track_check::queue(app, state.user_id(), &track)
@@ -82,5 +101,112 @@ pub async fn check(app: &'static App, user_id: &str) -> anyhow::Result<CheckUser
         TrackStatus::Ignore => {},
     }
 
-    Ok(CheckUserResult::Complete)
+    Ok(CheckUserResult::Complete { remaining })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spotify::CurrentlyPlayingNoneReason;
+    use crate::spotify::mock::track;
+    use crate::testing::TestApp;
+
+    const TRACK_ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+    const NEXT_TRACK_ID: &str = "7GhIk7Il098yCjg4BQjzvb";
+
+    async fn start() -> (TestApp, String) {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+
+        {
+            let mut spotify = test.spotify.state();
+
+            spotify.premium = true;
+            spotify.add_track(track(TRACK_ID, 200));
+            spotify.add_track(track(NEXT_TRACK_ID, 200));
+            spotify.queue.push_back(NEXT_TRACK_ID.to_owned());
+        }
+
+        test.login(&user_id).await.unwrap();
+
+        (test, user_id)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_check_nothing_playing() {
+        let (test, user_id) = start().await;
+
+        let res = check(test.app, &user_id).await.unwrap();
+
+        assert!(matches!(
+            res,
+            CheckUserResult::None(CurrentlyPlayingNoneReason::Nothing)
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_check_skips_disliked_track() {
+        let (test, user_id) = start().await;
+
+        test.spotify.state().play(TRACK_ID, Duration::seconds(10));
+
+        TrackStatusService::set_status(test.app.db(), &user_id, TRACK_ID, TrackStatus::Disliked)
+            .await
+            .unwrap();
+
+        let res = check(test.app, &user_id).await.unwrap();
+
+        assert!(matches!(res, CheckUserResult::Complete { remaining: None }));
+        assert_eq!(test.spotify.state().requested("POST /v1/me/player/next"), 1);
+        assert_eq!(
+            test.spotify.state().playing.as_ref().unwrap().track_id,
+            NEXT_TRACK_ID
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_check_kid_safe_skips_disliked_track() {
+        let (test, user_id) = start().await;
+
+        UserService::set_cfg_skip_tracks(test.app.db(), &user_id, false)
+            .await
+            .unwrap();
+        UserService::set_profile(test.app.db(), &user_id, UserProfile::KidSafe, Some("1234"))
+            .await
+            .unwrap();
+
+        test.spotify.state().play(TRACK_ID, Duration::seconds(10));
+
+        TrackStatusService::set_status(test.app.db(), &user_id, TRACK_ID, TrackStatus::Disliked)
+            .await
+            .unwrap();
+
+        let res = check(test.app, &user_id).await.unwrap();
+
+        assert!(matches!(res, CheckUserResult::Complete { remaining: None }));
+        assert_eq!(test.spotify.state().requested("POST /v1/me/player/next"), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_check_same_track_once() {
+        let (test, user_id) = start().await;
+
+        test.spotify.state().play(TRACK_ID, Duration::seconds(50));
+
+        let res = check(test.app, &user_id).await.unwrap();
+
+        assert!(matches!(
+            res,
+            CheckUserResult::Complete { remaining: Some(remaining) } if remaining == Duration::seconds(150)
+        ));
+
+        let res = check(test.app, &user_id).await.unwrap();
+
+        assert!(matches!(res, CheckUserResult::SkipSame { .. }));
+        assert_eq!(test.spotify.state().requested("POST /v1/me/player/next"), 0);
+    }
 }
@@ -1,12 +1,15 @@
+use std::collections::HashSet;
+
 use anyhow::Context as _;
 use rspotify::AuthCodeSpotify;
-use rspotify::clients::OAuthClient as _;
+use rspotify::clients::OAuthClient;
 use rspotify::model::{PrivateUser, SubscriptionLevel};
 use teloxide::types::ChatId;
 use tokio::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use crate::entity::prelude::UserModel;
-use crate::spotify::SpotifyWrapper;
+use crate::spotify::scopes::Feature;
+use crate::spotify::{ClientResultExt as _, RateLimiter, SpotifyWrapper};
 
 pub struct UserState {
     spotify: RwLock<AuthCodeSpotify>,
@@ -14,18 +17,25 @@ pub struct UserState {
     spotify_user: Mutex<Option<Option<PrivateUser>>>,
     user: UserModel,
     newly_created: bool,
+    rate_limiter: RateLimiter,
 }
 
 pub type SpotifyWrapperType<'a> = SpotifyWrapper<RwLockReadGuard<'a, AuthCodeSpotify>>;
 
 impl UserState {
     #[must_use]
-    pub fn new(user: UserModel, newly_created: bool, spotify: AuthCodeSpotify) -> Self {
+    pub fn new(
+        user: UserModel,
+        newly_created: bool,
+        spotify: AuthCodeSpotify,
+        rate_limiter: RateLimiter,
+    ) -> Self {
         Self {
             spotify: RwLock::new(spotify),
             spotify_user: Mutex::default(),
             user,
             newly_created,
+            rate_limiter,
         }
     }
 
@@ -45,10 +55,14 @@ impl UserState {
         self.user().locale.language()
     }
 
+    /// Spotify client, each request through it takes a slot in the app-wide rate budget
     pub async fn spotify(&self) -> SpotifyWrapperType<'_> {
-        let lock = self.spotify.read().await;
+        SpotifyWrapper::new(self.spotify.read().await).with_rate_limiter(self.rate_limiter.clone())
+    }
 
-        SpotifyWrapper::new(lock)
+    /// Spotify client without rate limiting, only for access to token and config
+    pub async fn spotify_unlimited(&self) -> RwLockReadGuard<'_, AuthCodeSpotify> {
+        self.spotify.read().await
     }
 
     pub async fn spotify_write(&self) -> RwLockWriteGuard<'_, AuthCodeSpotify> {
@@ -64,7 +78,7 @@ impl UserState {
     }
 
     pub async fn is_spotify_authed(&self) -> bool {
-        self.spotify()
+        self.spotify_unlimited()
             .await
             .token
             .lock()
@@ -73,12 +87,36 @@ impl UserState {
             .is_some()
     }
 
+    /// Scopes of the current Spotify token
+    pub async fn granted_scopes(&self) -> HashSet<String> {
+        let token = self.spotify_unlimited().await.token.clone();
+        let token = token.lock().await.expect("Failed to acquire lock");
+
+        token
+            .as_ref()
+            .map(|token| token.scopes.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether user granted Spotify scopes needed for all features
+    pub async fn has_features(&self, features: &[Feature]) -> bool {
+        let granted = self.granted_scopes().await;
+
+        features.iter().all(|feature| feature.is_granted(&granted))
+    }
+
     pub async fn spotify_user(&self) -> anyhow::Result<Option<PrivateUser>> {
         let mut lock = self.spotify_user.lock().await;
 
         if lock.is_none() {
             let user = if self.is_spotify_authed().await {
-                let me = self.spotify().await.me().await?;
+                let me = self
+                    .spotify()
+                    .await
+                    .request(OAuthClient::me)
+                    .await
+                    .typed()
+                    .await?;
 
                 Some(me)
             } else {
@@ -130,6 +130,11 @@ pub trait StringUtils {
     fn chars_len(&self) -> usize;
 
     fn chars_crop(&self, len: usize) -> String;
+
+    /// Splits Telegram HTML into parts not longer than `max_len` chars,
+    /// preferring paragraph, line and word boundaries.
+    /// Tags and entities are kept whole, tags open at a split are reopened in the next part
+    fn chars_split(&self, max_len: usize) -> Vec<String>;
 }
 
 impl<T> StringUtils for T
@@ -143,6 +148,133 @@ where
     fn chars_crop(&self, len: usize) -> String {
         self.as_ref().chars().take(len).collect()
     }
+
+    fn chars_split(&self, max_len: usize) -> Vec<String> {
+        let max_len = max_len.max(1);
+
+        let mut parts = vec![];
+        let mut open: Vec<HtmlTag<'_>> = vec![];
+        let mut rest = self.as_ref().trim();
+
+        while !rest.is_empty() {
+            let reopen: String = open.iter().map(|tag| tag.opening).collect();
+
+            let mut stack = open.clone();
+            let mut len = reopen.chars_len();
+            let mut has_text = false;
+            let mut fits = true;
+            // The latest split for paragraph, line, word and char boundaries
+            let mut splits: [Option<(usize, Vec<HtmlTag<'_>>)>; 4] = Default::default();
+            // Split after the first text when nothing else fits
+            let mut forced = None;
+
+            for (at, token) in html_tokens(rest) {
+                if has_text && len + closing_len(&stack) <= max_len {
+                    let head = &rest[..at];
+                    let boundary = ["\n\n", "\n", " "]
+                        .iter()
+                        .position(|separator| head.ends_with(separator))
+                        .unwrap_or(3);
+
+                    splits[boundary] = Some((at, stack.clone()));
+                }
+
+                len += token.chars_len();
+
+                if let Some(tag) = token.strip_prefix("</") {
+                    let name = html_tag_name(tag);
+
+                    if let Some(i) = stack.iter().rposition(|open| open.name == name) {
+                        stack.truncate(i);
+                    }
+                } else if token.len() > 1 && token.starts_with('<') {
+                    stack.push(HtmlTag {
+                        name: html_tag_name(&token[1..]),
+                        opening: token,
+                    });
+                } else if !token.trim().is_empty() {
+                    has_text = true;
+                    forced.get_or_insert_with(|| (at + token.len(), stack.clone()));
+                }
+
+                if len > max_len {
+                    fits = false;
+                    break;
+                }
+            }
+
+            let split = if fits && len + closing_len(&stack) <= max_len {
+                None
+            } else {
+                splits.into_iter().flatten().next().or(forced)
+            };
+
+            let Some((at, stack)) = split else {
+                parts.push(reopen + rest);
+                break;
+            };
+
+            let closing: String = stack
+                .iter()
+                .rev()
+                .map(|tag| ["</", tag.name, ">"].concat())
+                .collect();
+
+            parts.push(reopen + rest[..at].trim_end() + &closing);
+
+            open = stack;
+            rest = rest[at..].trim_start();
+        }
+
+        parts
+    }
+}
+
+#[derive(Clone)]
+struct HtmlTag<'a> {
+    name: &'a str,
+    opening: &'a str,
+}
+
+fn html_tag_name(tag: &str) -> &str {
+    let end = tag
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .unwrap_or(tag.len());
+
+    &tag[..end]
+}
+
+fn closing_len(stack: &[HtmlTag<'_>]) -> usize {
+    stack.iter().map(|tag| tag.name.len() + "</>".len()).sum()
+}
+
+/// Tags, entities and single chars of text with their byte offsets
+fn html_tokens(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut at = 0;
+
+    std::iter::from_fn(move || {
+        let rest = &text[at..];
+        let first = rest.chars().next()?;
+
+        let len = match first {
+            '<' => rest.find('>').map_or(1, |end| end + 1),
+            '&' => rest
+                .find(';')
+                .filter(|end| {
+                    *end <= 10
+                        && rest[1..*end]
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '#')
+                })
+                .map_or(1, |end| end + 1),
+            _ => first.len_utf8(),
+        };
+
+        let token = (at, &rest[..len]);
+        at += len;
+
+        Some(token)
+    })
 }
 
 pub trait DurationPrettyFormat {
@@ -196,6 +328,54 @@ mod tests {
         assert_eq!("😀😁😂".chars_len(), 3);
     }
 
+    #[test]
+    fn test_chars_split_fits() {
+        assert_eq!("hello".chars_split(10), vec!["hello"]);
+        assert!("".chars_split(10).is_empty());
+    }
+
+    #[test]
+    fn test_chars_split_boundaries() {
+        assert_eq!(
+            "first paragraph\n\nsecond one".chars_split(20),
+            vec!["first paragraph", "second one"]
+        );
+        assert_eq!(
+            "line one\nline two".chars_split(12),
+            vec!["line one", "line two"]
+        );
+        assert_eq!(
+            "some words here".chars_split(11),
+            vec!["some words", "here"]
+        );
+    }
+
+    #[test]
+    fn test_chars_split_long_word() {
+        assert_eq!("😀😁😂🤣".chars_split(3), vec!["😀😁😂", "🤣"]);
+        assert_eq!("abc".chars_split(0), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_chars_split_html() {
+        assert_eq!(
+            "<b>one two</b> three".chars_split(12),
+            vec!["<b>one</b>", "<b>two</b>", "three"]
+        );
+        assert_eq!(
+            "<tg-spoiler>a <i>b c</i></tg-spoiler>".chars_split(36),
+            vec![
+                "<tg-spoiler>a <i>b</i></tg-spoiler>",
+                "<tg-spoiler><i>c</i></tg-spoiler>"
+            ]
+        );
+        assert_eq!(
+            r#"<a href="a b">ab</a> cd"#.chars_split(20),
+            vec![r#"<a href="a b">ab</a>"#, "cd"]
+        );
+        assert_eq!("a &amp; b".chars_split(3), vec!["a", "&amp;", "b"]);
+    }
+
     #[test]
     fn test_chars_len_unicode_chinese() {
         assert_eq!("你好世界".chars_len(), 4);
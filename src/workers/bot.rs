@@ -1,4 +1,6 @@
 use sea_orm::Iterable as _;
+use teloxide::adaptors::DefaultParseMode;
+use teloxide::dispatching::UpdateHandler;
 use teloxide::prelude::*;
 use teloxide::sugar::request::RequestLinkPreviewExt as _;
 use teloxide::types::User;
@@ -52,31 +54,9 @@ async fn sync_name(
     Ok(())
 }
 
-#[tracing::instrument(skip_all)]
-pub async fn work() {
-    // profanity::check_cases();
-
-    rustify::infrastructure::logger::init().expect("Logger should be built");
-
-    tracing::info!(
-        git_commit_timestamp = env!("GIT_COMMIT_TIMESTAMP"),
-        git_sha = env!("GIT_SHA"),
-        "Starting Rustify bot..."
-    );
-
-    let app = App::init().await.expect("State to be built");
-
-    for locale in UserLocale::iter() {
-        app.bot()
-            .set_my_commands(UserCommand::localized_bot_commands(locale.as_ref()))
-            .language_code(locale.as_ref())
-            .await
-            .expect("update commands should be working");
-    }
-
-    tokio::spawn(rustify::utils::listen_for_ctrl_c());
-
-    let handler = dptree::entry()
+/// Routes messages and inline button presses to their handlers
+fn handler(app: &'static App) -> UpdateHandler<anyhow::Error> {
+    dptree::entry()
         .branch(
             Update::filter_message().endpoint(move |m: Message| async move {
                 let state = app.user_state(&m.chat.id.to_string()).await?;
@@ -103,7 +83,7 @@ pub async fn work() {
             }),
         )
         .branch(Update::filter_callback_query().endpoint(
-            move |q: CallbackQuery| async {
+            move |q: CallbackQuery| async move {
                 // Message can be None for: inline mode results, old messages (48+ hours),
                 // deleted messages, or inaccessible channels
                 let Some(m) = q.get_message() else {
@@ -136,11 +116,40 @@ pub async fn work() {
 
                 Ok(())
             },
-        ));
+        ))
+}
 
-    let mut dispatcher = Dispatcher::builder(app.bot().clone(), handler)
+fn dispatcher(app: &'static App) -> Dispatcher<DefaultParseMode<Bot>, anyhow::Error, ()> {
+    Dispatcher::builder(app.bot().clone(), handler(app))
         .distribution_function(|_| None::<()>)
-        .build();
+        .build()
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn work() {
+    // profanity::check_cases();
+
+    rustify::infrastructure::logger::init().expect("Logger should be built");
+
+    tracing::info!(
+        git_commit_timestamp = env!("GIT_COMMIT_TIMESTAMP"),
+        git_sha = env!("GIT_SHA"),
+        "Starting Rustify bot..."
+    );
+
+    let app = App::init().await.expect("State to be built");
+
+    for locale in UserLocale::iter() {
+        app.bot()
+            .set_my_commands(UserCommand::localized_bot_commands(locale.as_ref()))
+            .language_code(locale.as_ref())
+            .await
+            .expect("update commands should be working");
+    }
+
+    tokio::spawn(rustify::utils::listen_for_ctrl_c());
+
+    let mut dispatcher = dispatcher(app);
 
     let token = dispatcher.shutdown_token();
 
@@ -152,3 +161,236 @@ pub async fn work() {
 
     dispatcher.dispatch().await;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::entity::prelude::{TrackStatus, UserProfile};
+    use crate::services::{TrackStatusService, UserService, UserWordWhitelistService};
+    use crate::spotify::mock::track;
+    use crate::telegram::inline_buttons::InlineButtons;
+    use crate::telegram::mock::MockState;
+    use crate::testing::TestApp;
+
+    const TRACK_ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+
+    /// Dispatches injected updates until `done` holds for recorded calls
+    async fn dispatch_until(test: &TestApp, done: impl Fn(&MockState) -> bool) {
+        let mut dispatcher = dispatcher(test.app);
+        let token = dispatcher.shutdown_token();
+        let dispatching = tokio::spawn(async move { dispatcher.dispatch().await });
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !done(&test.telegram.state()) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("updates should be handled in time");
+
+        token
+            .shutdown()
+            .expect("dispatcher should be running")
+            .await;
+        dispatching.await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_command() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        let state = test.login(&user_id).await.unwrap();
+        let check_profanity = state.user().cfg_check_profanity;
+
+        test.telegram
+            .state()
+            .inject_message(user_id.parse().unwrap(), "/toggle_profanity_check");
+
+        dispatch_until(&test, |state| !state.calls("sendMessage").is_empty()).await;
+
+        let state = test.app.user_state(&user_id).await.unwrap();
+
+        assert_eq!(state.user().cfg_check_profanity, !check_profanity);
+        assert_eq!(
+            test.telegram.state().calls("sendMessage")[0]["chat_id"],
+            user_id.parse::<i64>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_unknown_command() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        test.login(&user_id).await.unwrap();
+
+        test.telegram
+            .state()
+            .inject_message(user_id.parse().unwrap(), "/definitely_unknown");
+
+        dispatch_until(&test, |state| !state.calls("sendMessage").is_empty()).await;
+
+        let reply = test.telegram.state().calls("sendMessage")[0]["text"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+
+        assert!(reply.contains("definitely_unknown"), "{reply}");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_dislike_button() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        test.login(&user_id).await.unwrap();
+        test.spotify.state().add_track(track(TRACK_ID, 200));
+
+        test.telegram.state().inject_button_press(
+            user_id.parse().unwrap(),
+            &InlineButtons::Dislike(TRACK_ID.to_owned()).to_string(),
+        );
+
+        dispatch_until(&test, |state| !state.calls("editMessageText").is_empty()).await;
+
+        assert_eq!(
+            TrackStatusService::get_status(test.app.db(), &user_id, TRACK_ID).await,
+            TrackStatus::Disliked
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_ignore_button() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        test.login(&user_id).await.unwrap();
+        test.spotify.state().add_track(track(TRACK_ID, 200));
+
+        test.telegram.state().inject_button_press(
+            user_id.parse().unwrap(),
+            &InlineButtons::Ignore(TRACK_ID.to_owned()).to_string(),
+        );
+
+        dispatch_until(&test, |state| !state.calls("editMessageText").is_empty()).await;
+
+        assert_eq!(
+            TrackStatusService::get_status(test.app.db(), &user_id, TRACK_ID).await,
+            TrackStatus::Ignore
+        );
+    }
+
+    /// Logged in user with kid-safe profile
+    async fn login_kid_safe(test: &TestApp) -> String {
+        let user_id = TestApp::user_id();
+        test.login(&user_id).await.unwrap();
+
+        UserService::set_profile(test.app.db(), &user_id, UserProfile::KidSafe, Some("1234"))
+            .await
+            .unwrap();
+
+        user_id
+    }
+
+    fn replied_locked(state: &MockState) -> bool {
+        state.calls("sendMessage").iter().any(|call| {
+            call["text"]
+                .as_str()
+                .is_some_and(|text| text.contains("/kid_safe"))
+        })
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_kid_safe_locks_whitelist_command() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = login_kid_safe(&test).await;
+
+        test.telegram
+            .state()
+            .inject_message(user_id.parse().unwrap(), "/add_word_to_whitelist damn");
+
+        dispatch_until(&test, replied_locked).await;
+
+        assert!(
+            UserWordWhitelistService::get_ok_words_for_user(test.app.db(), &user_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_kid_safe_locks_track_skip_command() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = login_kid_safe(&test).await;
+        let skip_tracks = test
+            .app
+            .user_state(&user_id)
+            .await
+            .unwrap()
+            .user()
+            .cfg_skip_tracks;
+
+        test.telegram
+            .state()
+            .inject_message(user_id.parse().unwrap(), "/toggle_track_skip");
+
+        dispatch_until(&test, replied_locked).await;
+
+        let state = test.app.user_state(&user_id).await.unwrap();
+
+        assert_eq!(state.user().cfg_skip_tracks, skip_tracks);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_kid_safe_locks_ignore_button() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = login_kid_safe(&test).await;
+        test.spotify.state().add_track(track(TRACK_ID, 200));
+
+        test.telegram.state().inject_button_press(
+            user_id.parse().unwrap(),
+            &InlineButtons::Ignore(TRACK_ID.to_owned()).to_string(),
+        );
+
+        dispatch_until(&test, replied_locked).await;
+
+        assert_eq!(
+            TrackStatusService::get_status(test.app.db(), &user_id, TRACK_ID).await,
+            TrackStatus::None
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_button_without_message() {
+        let test = TestApp::start().await.unwrap();
+        let user_id = TestApp::user_id();
+        test.login(&user_id).await.unwrap();
+
+        test.telegram.state().inject_callback_query(
+            user_id.parse().unwrap(),
+            &InlineButtons::Dislike(TRACK_ID.to_owned()).to_string(),
+        );
+
+        dispatch_until(&test, |state| {
+            !state.calls("answerCallbackQuery").is_empty()
+        })
+        .await;
+
+        assert_eq!(
+            test.telegram.state().calls("answerCallbackQuery")[0]["show_alert"],
+            true
+        );
+        assert_eq!(
+            TrackStatusService::get_status(test.app.db(), &user_id, TRACK_ID).await,
+            TrackStatus::None
+        );
+    }
+}
@@ -6,7 +6,7 @@ use apalis::prelude::{Monitor, WorkerBuilder};
 
 use crate as rustify;
 use crate::app::App;
-use crate::queue::track_check;
+use crate::queue::{track_check, webhook};
 
 pub async fn work() {
     rustify::infrastructure::logger::init().expect("Logger should be built");
@@ -26,12 +26,21 @@ pub async fn work() {
             WorkerBuilder::new("rustify:track_check")
                 .backend(app.queue_manager().track_check_queue())
                 .concurrency(2)
-                // Ordering of timeout and retry matters!
-                .timeout(Duration::from_secs(90))
-                .retry(RetryPolicy::retries(2))
+                // Timeout is applied by the task itself, so timed out tasks reach dead letters
+                .retry(RetryPolicy::retries(track_check::RETRIES))
                 .data(app)
                 .build(track_check::consume)
         })
+        .register(move |_| {
+            WorkerBuilder::new("rustify:webhook")
+                .backend(app.queue_manager().webhook_queue())
+                .concurrency(4)
+                // Ordering of timeout and retry matters!
+                .timeout(Duration::from_secs(30))
+                .retry(RetryPolicy::retries(5))
+                .data(app)
+                .build(webhook::consume)
+        })
         .run()
         .await
         .expect("Should Work");
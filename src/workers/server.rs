@@ -1,3 +1,9 @@
+mod admin;
+mod api;
+mod dashboard;
+mod web;
+mod web_app;
+
 use anyhow::Context as _;
 use axum::Router;
 use axum::extract::{Query, State};
@@ -12,7 +18,9 @@ use teloxide::prelude::Requester as _;
 use crate as rustify;
 use crate::app::App;
 use crate::entity::prelude::*;
-use crate::services::{NotificationService, UserService};
+use crate::queue::webhook;
+use crate::services::{NotificationService, UserService, WebhookEvent};
+use crate::spotify::ClientResultExt as _;
 use crate::spotify::auth::SpotifyAuthService;
 use crate::telegram::commands::UserCommandDisplay;
 use crate::telegram::keyboards::StartKeyboard;
@@ -58,6 +66,8 @@ async fn process_callback(app: &'static App, params: CallbackParams) -> anyhow::
     instance
         .request_token(&params.code)
         .await
+        .typed()
+        .await
         .context("Failed to exchange code for token")?;
 
     let token = {
@@ -80,6 +90,16 @@ async fn process_callback(app: &'static App, params: CallbackParams) -> anyhow::
 
     tracing::info!(user_id = %user.id, "User successfuly logged in");
 
+    if user.status != UserStatus::Active {
+        webhook::emit(
+            app,
+            &user.id,
+            WebhookEvent::UserStatusChanged,
+            webhook::user_status_data(UserStatus::Active),
+        )
+        .await;
+    }
+
     app.bot()
         .send_message(
             state.chat_id()?,
@@ -119,6 +139,10 @@ pub async fn work() {
 
     let router = Router::new()
         .route("/spotify-callback", get(callback_handler))
+        .nest("/api/v1", api::router())
+        .merge(dashboard::router())
+        .merge(admin::router())
+        .merge(web_app::router())
         .with_state(app);
 
     let listener = tokio::net::TcpListener::bind(app.server_http_address())
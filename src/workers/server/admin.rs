@@ -0,0 +1,680 @@
+use axum::Router;
+use axum::extract::{Form, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse as _, Redirect, Response};
+use axum::routing::get;
+use indoc::formatdoc;
+use itertools::Itertools as _;
+use sea_orm::{
+    ActiveEnum as _,
+    Iterable as _,
+    Order,
+    PaginatorTrait as _,
+    QueryOrder as _,
+    QuerySelect as _,
+};
+use teloxide::prelude::*;
+use teloxide::utils::html;
+use url::form_urlencoded;
+
+use super::web::{escape_attr, internal_error, link_expired, login, page, session_user_id};
+use crate::app::App;
+use crate::entity::prelude::*;
+use crate::services::{UserService, WordDefinitionService, WordStatsService};
+use crate::telegram::actions::admin_users::details::format_user_details;
+use crate::telegram::actions::broadcast::broadcast;
+use crate::telegram::inline_buttons_admin::{AdminUsersSortBy, AdminUsersSortOrder};
+
+const USERS_PER_PAGE: u64 = 25;
+const WORDS_PER_PAGE: usize = 25;
+
+const NAV: &str = r#"<nav><a href="/admin/users">Users</a><a href="/admin/word-definitions">Word definitions</a><a href="/admin/broadcast">Broadcast</a></nav>"#;
+
+fn admin_page(title: &str, body: &str) -> Response {
+    Html(page(
+        &format!("Rustify Admin: {title}"),
+        &format!("{NAV}<h1>{title}</h1>{body}", title = html::escape(title)),
+    ))
+    .into_response()
+}
+
+/// Returns user of the session, only if it's admin
+async fn session_admin(app: &App, headers: &HeaderMap) -> anyhow::Result<Option<UserModel>> {
+    let Some(user_id) = session_user_id(app, headers).await? else {
+        return Ok(None);
+    };
+
+    let user = UserService::get_by_id(app.db(), &user_id)
+        .await?
+        .filter(UserModel::is_admin);
+
+    Ok(user)
+}
+
+#[derive(Deserialize)]
+struct LoginParams {
+    token: String,
+}
+
+#[tracing::instrument(skip_all)]
+async fn login_handler(
+    State(app): State<&'static App>,
+    Query(params): Query<LoginParams>,
+) -> Response {
+    login(app, &params.token, "/admin/users")
+        .await
+        .unwrap_or_else(|err| internal_error(&err))
+}
+
+#[derive(Deserialize, Clone, Default)]
+struct UsersParams {
+    #[serde(default)]
+    q: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    sort_by: AdminUsersSortBy,
+    #[serde(default)]
+    sort_order: AdminUsersSortOrder,
+    #[serde(default)]
+    page: u64,
+}
+
+impl UsersParams {
+    fn url(&self) -> String {
+        let sort_by = match self.sort_by {
+            AdminUsersSortBy::CreatedAt => "c",
+            AdminUsersSortBy::LyricsChecked => "l",
+        };
+
+        let sort_order = match self.sort_order {
+            AdminUsersSortOrder::Asc => "a",
+            AdminUsersSortOrder::Desc => "d",
+        };
+
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("q", &self.q)
+            .append_pair("status", &self.status)
+            .append_pair("sort_by", sort_by)
+            .append_pair("sort_order", sort_order)
+            .append_pair("page", &self.page.to_string())
+            .finish();
+
+        format!("/admin/users?{query}")
+    }
+
+    fn sort_header(&self, title: &str, sort_by: AdminUsersSortBy) -> String {
+        let (sort_order, arrow) = match (self.sort_by == sort_by, self.sort_order) {
+            (true, AdminUsersSortOrder::Desc) => (AdminUsersSortOrder::Asc, " ↓"),
+            (true, AdminUsersSortOrder::Asc) => (AdminUsersSortOrder::Desc, " ↑"),
+            (false, _) => (AdminUsersSortOrder::Desc, ""),
+        };
+
+        let url = Self {
+            sort_by,
+            sort_order,
+            page: 0,
+            ..self.clone()
+        }
+        .url();
+
+        format!(
+            r#"<a href="{url}">{title}{arrow}</a>"#,
+            url = escape_attr(&url)
+        )
+    }
+}
+
+#[tracing::instrument(skip_all)]
+async fn users_handler(
+    State(app): State<&'static App>,
+    headers: HeaderMap,
+    Query(params): Query<UsersParams>,
+) -> Response {
+    let res = async {
+        if session_admin(app, &headers).await?.is_none() {
+            return Ok(link_expired());
+        }
+
+        let status = params.status.parse::<UserStatus>().ok();
+
+        let query = UserService::search_query(Some(&params.q), status);
+
+        let total = query.clone().count(app.db()).await?;
+        let total_pages = total.div_ceil(USERS_PER_PAGE).max(1);
+
+        let users = query
+            .order_by(
+                UserColumn::from(params.sort_by),
+                Order::from(params.sort_order),
+            )
+            .offset(params.page * USERS_PER_PAGE)
+            .limit(USERS_PER_PAGE)
+            .all(app.db())
+            .await?;
+
+        let status_options = UserStatus::iter()
+            .map(|option| {
+                format!(
+                    r#"<option value="{value}"{selected}>{option:?}</option>"#,
+                    value = option.to_value(),
+                    selected = if Some(option) == status {
+                        " selected"
+                    } else {
+                        ""
+                    },
+                )
+            })
+            .join("");
+
+        let rows = users
+            .iter()
+            .map(|user| {
+                formatdoc!(
+                    r#"
+                        <tr>
+                        <td><a href="/admin/users/{id}">{name}</a></td>
+                        <td><code>{id}</code></td>
+                        <td>{status:?}</td>
+                        <td>{locale}</td>
+                        <td>{lyrics_checked}</td>
+                        <td>{created_at}</td>
+                        </tr>
+                    "#,
+                    id = html::escape(&user.id),
+                    name = html::escape(&user.name),
+                    status = user.status,
+                    locale = user.locale,
+                    lyrics_checked = user.lyrics_checked,
+                    created_at = user.created_at.format("%Y-%m-%d %H:%M:%S"),
+                )
+            })
+            .join("");
+
+        let mut pages = vec![];
+        if params.page > 0 {
+            let url = UsersParams {
+                page: params.page - 1,
+                ..params.clone()
+            }
+            .url();
+            pages.push(format!(r#"<a href="{}">← Previous</a>"#, escape_attr(&url)));
+        }
+        pages.push(format!(
+            "Page {page}/{total_pages} (Total: {total} users)",
+            page = params.page + 1
+        ));
+        if params.page + 1 < total_pages {
+            let url = UsersParams {
+                page: params.page + 1,
+                ..params.clone()
+            }
+            .url();
+            pages.push(format!(r#"<a href="{}">Next →</a>"#, escape_attr(&url)));
+        }
+
+        let body = formatdoc!(
+            r#"
+                <section>
+                <form class="inline" method="get" action="/admin/users">
+                <input type="search" name="q" value="{q}" placeholder="ID or name">
+                <select name="status"><option value="">All statuses</option>{status_options}</select>
+                <button type="submit">Search</button>
+                </form>
+                <table>
+                <tr><th>Name</th><th>ID</th><th>Status</th><th>Locale</th><th>{lyrics_checked}</th><th>{created_at}</th></tr>
+                {rows}
+                </table>
+                <div class="pages">{pages}</div>
+                </section>
+            "#,
+            q = escape_attr(&params.q),
+            lyrics_checked = params.sort_header("Lyrics Checked", AdminUsersSortBy::LyricsChecked),
+            created_at = params.sort_header("Created", AdminUsersSortBy::CreatedAt),
+            pages = pages.join(""),
+        );
+
+        Ok::<_, anyhow::Error>(admin_page("Users", &body))
+    };
+
+    res.await.unwrap_or_else(|err| internal_error(&err))
+}
+
+#[tracing::instrument(skip_all, fields(target_user_id = %user_id))]
+async fn user_details_handler(
+    State(app): State<&'static App>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Response {
+    let res = async {
+        if session_admin(app, &headers).await?.is_none() {
+            return Ok(link_expired());
+        }
+
+        // User IDs are Telegram ones, anything else is not a user
+        if user_id.is_empty() || !user_id.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Ok((
+                StatusCode::NOT_FOUND,
+                admin_page("User", "<p>User is not found</p>"),
+            )
+                .into_response());
+        }
+
+        let details = format_user_details(app, &user_id).await?;
+
+        Ok::<_, anyhow::Error>(admin_page(
+            "User",
+            &format!(r#"<section class="pre">{details}</section>"#),
+        ))
+    };
+
+    res.await.unwrap_or_else(|err| internal_error(&err))
+}
+
+fn word_definition_url(locale: &str, word: &str) -> String {
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("locale", locale)
+        .append_pair("word", word)
+        .finish();
+
+    format!("/admin/word-definitions/edit?{query}")
+}
+
+fn locale_options(selected: &str) -> String {
+    UserLocale::locale_codes()
+        .into_iter()
+        .map(|locale| {
+            format!(
+                r#"<option value="{locale}"{selected}>{locale}</option>"#,
+                selected = if locale == selected { " selected" } else { "" },
+            )
+        })
+        .join("")
+}
+
+fn default_locale() -> String {
+    UserLocale::default().to_string()
+}
+
+#[derive(Deserialize)]
+struct WordDefinitionsParams {
+    #[serde(default = "default_locale")]
+    locale: String,
+    #[serde(default)]
+    page: usize,
+}
+
+#[tracing::instrument(skip_all)]
+async fn word_definitions_handler(
+    State(app): State<&'static App>,
+    headers: HeaderMap,
+    Query(params): Query<WordDefinitionsParams>,
+) -> Response {
+    let res = async {
+        if session_admin(app, &headers).await?.is_none() {
+            return Ok(link_expired());
+        }
+
+        let total = WordStatsService::count_stats(app.db()).await?;
+        let total_pages = total.div_ceil(WORDS_PER_PAGE).max(1);
+
+        let stats = WordStatsService::list_stats_with_definitions(
+            app.db(),
+            &params.locale,
+            params.page,
+            WORDS_PER_PAGE,
+        )
+        .await?;
+
+        let rows = stats
+            .iter()
+            .map(|stat| {
+                formatdoc!(
+                    r#"
+                        <tr>
+                        <td><a href="{url}">{word}</a></td>
+                        <td>{check}</td>
+                        <td>{details}</td>
+                        <td>{analyze}</td>
+                        <td class="pre">{definition}</td>
+                        </tr>
+                    "#,
+                    url = escape_attr(&word_definition_url(&params.locale, &stat.word)),
+                    word = html::escape(&stat.word),
+                    check = stat.check_occurrences,
+                    details = stat.details_occurrences,
+                    analyze = stat.analyze_occurrences,
+                    definition = stat.definition.as_deref().map_or_else(
+                        || r#"<span class="muted">Not generated</span>"#.into(),
+                        html::escape
+                    ),
+                )
+            })
+            .join("");
+
+        let page_url = |page: usize| {
+            let query = form_urlencoded::Serializer::new(String::new())
+                .append_pair("locale", &params.locale)
+                .append_pair("page", &page.to_string())
+                .finish();
+
+            escape_attr(&format!("/admin/word-definitions?{query}"))
+        };
+
+        let mut pages = vec![];
+        if params.page > 0 {
+            pages.push(format!(
+                r#"<a href="{}">← Previous</a>"#,
+                page_url(params.page - 1)
+            ));
+        }
+        pages.push(format!(
+            "Page {page}/{total_pages} (Total: {total} words)",
+            page = params.page + 1
+        ));
+        if params.page + 1 < total_pages {
+            pages.push(format!(
+                r#"<a href="{}">Next →</a>"#,
+                page_url(params.page + 1)
+            ));
+        }
+
+        let body = formatdoc!(
+            r#"
+                <section>
+                <form class="inline" method="get" action="/admin/word-definitions">
+                <select name="locale">{locales}</select>
+                <button type="submit">Show</button>
+                </form>
+                <table>
+                <tr><th>Word</th><th>Checks</th><th>Details</th><th>Analyzes</th><th>Definition</th></tr>
+                {rows}
+                </table>
+                <div class="pages">{pages}</div>
+                </section>
+            "#,
+            locales = locale_options(&params.locale),
+            pages = pages.join(""),
+        );
+
+        Ok::<_, anyhow::Error>(admin_page("Word definitions", &body))
+    };
+
+    res.await.unwrap_or_else(|err| internal_error(&err))
+}
+
+#[derive(Deserialize)]
+struct WordDefinitionParams {
+    locale: String,
+    word: String,
+}
+
+#[tracing::instrument(skip_all, fields(locale = %params.locale, word = %params.word))]
+async fn word_definition_handler(
+    State(app): State<&'static App>,
+    headers: HeaderMap,
+    Query(params): Query<WordDefinitionParams>,
+) -> Response {
+    let res = async {
+        if session_admin(app, &headers).await?.is_none() {
+            return Ok(link_expired());
+        }
+
+        let definition =
+            WordDefinitionService::find_definition(app.db(), &params.locale, &params.word).await?;
+
+        let body = formatdoc!(
+            r#"
+                <section>
+                <p>Word <code>{word}</code>, locale <code>{locale}</code></p>
+                <form method="post" action="/admin/word-definitions/edit">
+                <input type="hidden" name="locale" value="{locale_attr}">
+                <input type="hidden" name="word" value="{word_attr}">
+                <textarea name="definition">{definition}</textarea>
+                <div class="pages">
+                <button type="submit" name="action" value="save">Save</button>
+                <button type="submit" name="action" value="regenerate">Regenerate with AI</button>
+                <button type="submit" name="action" value="clear">Clear</button>
+                </div>
+                </form>
+                </section>
+            "#,
+            word = html::escape(&params.word),
+            locale = html::escape(&params.locale),
+            word_attr = escape_attr(&params.word),
+            locale_attr = escape_attr(&params.locale),
+            definition = html::escape(definition.as_deref().unwrap_or_default()),
+        );
+
+        Ok::<_, anyhow::Error>(admin_page("Word definition", &body))
+    };
+
+    res.await.unwrap_or_else(|err| internal_error(&err))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WordDefinitionAction {
+    Save,
+    Regenerate,
+    Clear,
+}
+
+#[derive(Deserialize)]
+struct WordDefinitionForm {
+    locale: String,
+    word: String,
+    #[serde(default)]
+    definition: String,
+    action: WordDefinitionAction,
+}
+
+#[tracing::instrument(skip_all, fields(locale = %form.locale, word = %form.word))]
+async fn word_definition_update_handler(
+    State(app): State<&'static App>,
+    headers: HeaderMap,
+    Form(form): Form<WordDefinitionForm>,
+) -> Response {
+    let res = async {
+        let Some(admin) = session_admin(app, &headers).await? else {
+            return Ok(link_expired());
+        };
+
+        match form.action {
+            WordDefinitionAction::Save => {
+                WordDefinitionService::set_definition(
+                    app.db(),
+                    &form.locale,
+                    &form.word,
+                    form.definition.trim(),
+                )
+                .await?;
+            },
+            WordDefinitionAction::Regenerate => {
+                let Some(ai_config) = app.ai() else {
+                    return Ok(admin_page(
+                        "Word definition",
+                        "<p>AI configuration is not available. Word definitions cannot be generated.</p>",
+                    ));
+                };
+
+                WordDefinitionService::clear_definition(app.db(), &form.locale, &form.word).await?;
+
+                let definition = WordDefinitionService::get_definition(
+                    app,
+                    &admin.id,
+                    &form.locale,
+                    ai_config,
+                    &form.word,
+                )
+                .await?;
+
+                if definition.is_none() {
+                    return Ok(admin_page(
+                        "Word definition",
+                        "<p>Your AI quota is used up. Word definition is not generated.</p>",
+                    ));
+                }
+            },
+            WordDefinitionAction::Clear => {
+                WordDefinitionService::clear_definition(app.db(), &form.locale, &form.word).await?;
+            },
+        }
+
+        Ok::<_, anyhow::Error>(
+            Redirect::to(&word_definition_url(&form.locale, &form.word)).into_response(),
+        )
+    };
+
+    res.await.unwrap_or_else(|err| internal_error(&err))
+}
+
+fn broadcast_form(locale: &str, text: &str) -> String {
+    formatdoc!(
+        r#"
+            <section>
+            <form method="post" action="/admin/broadcast">
+            <p>Locale: <select name="locale">{locales}</select></p>
+            <textarea name="text" placeholder="Message text, Telegram HTML is supported">{text}</textarea>
+            <div class="pages">
+            <button type="submit" name="action" value="preview">Preview</button>
+            <button type="submit" name="action" value="send">Send to all users</button>
+            </div>
+            </form>
+            </section>
+        "#,
+        locales = locale_options(locale),
+        text = html::escape(text),
+    )
+}
+
+#[tracing::instrument(skip_all)]
+async fn broadcast_handler(State(app): State<&'static App>, headers: HeaderMap) -> Response {
+    let res = async {
+        if session_admin(app, &headers).await?.is_none() {
+            return Ok(link_expired());
+        }
+
+        Ok::<_, anyhow::Error>(admin_page(
+            "Broadcast",
+            &broadcast_form(&default_locale(), ""),
+        ))
+    };
+
+    res.await.unwrap_or_else(|err| internal_error(&err))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BroadcastAction {
+    Preview,
+    Send,
+}
+
+#[derive(Deserialize)]
+struct BroadcastForm {
+    locale: String,
+    text: String,
+    action: BroadcastAction,
+}
+
+#[tracing::instrument(skip_all, fields(locale = %form.locale))]
+async fn broadcast_send_handler(
+    State(app): State<&'static App>,
+    headers: HeaderMap,
+    Form(form): Form<BroadcastForm>,
+) -> Response {
+    let res = async {
+        let Some(admin) = session_admin(app, &headers).await? else {
+            return Ok(link_expired());
+        };
+
+        let admin_chat_id = ChatId(admin.id.parse()?);
+        let form_html = broadcast_form(&form.locale, &form.text);
+
+        let Ok(locale) = form.locale.parse::<UserLocale>() else {
+            return Ok(admin_page(
+                "Broadcast",
+                &format!("<p>Pass right locale</p>{form_html}"),
+            ));
+        };
+
+        if form.text.trim().is_empty() {
+            return Ok(admin_page(
+                "Broadcast",
+                &format!("<p>Message is empty</p>{form_html}"),
+            ));
+        }
+
+        match form.action {
+            BroadcastAction::Preview => {
+                let status = match app.bot().send_message(admin_chat_id, &form.text).await {
+                    Ok(_) => "Preview is sent to your Telegram chat".to_owned(),
+                    Err(err) => format!(
+                        "Telegram rejected message: {}",
+                        html::escape(&err.to_string())
+                    ),
+                };
+
+                Ok(admin_page(
+                    "Broadcast",
+                    &format!(
+                        r#"<p>{status}</p><section class="pre">{text}</section>{form_html}"#,
+                        text = html::escape(&form.text)
+                    ),
+                ))
+            },
+            BroadcastAction::Send => {
+                let text = form.text;
+
+                tokio::spawn(async move {
+                    let res = broadcast(app, locale, |chat_id| {
+                        let text = text.clone();
+
+                        async move {
+                            app.bot().send_message(chat_id, text).await?;
+
+                            Ok(())
+                        }
+                    })
+                    .await;
+
+                    let message = match res {
+                        Ok((sent, errors)) => format!("Sent to {sent} users. Errors {errors}"),
+                        Err(err) => {
+                            tracing::error!(err = ?err, "Failed to broadcast message");
+                            "Failed to broadcast message".to_owned()
+                        },
+                    };
+
+                    if let Err(err) = app.bot().send_message(admin_chat_id, message).await {
+                        tracing::error!(err = ?err, "Failed to send broadcast report");
+                    }
+                });
+
+                Ok(admin_page(
+                    "Broadcast",
+                    "<p>Broadcast is started. You will receive report in Telegram when it's done.</p>",
+                ))
+            },
+        }
+    };
+
+    res.await.unwrap_or_else(|err| internal_error(&err))
+}
+
+pub fn router() -> Router<&'static App> {
+    Router::new()
+        .route("/admin/login", get(login_handler))
+        .route("/admin/users", get(users_handler))
+        .route("/admin/users/{user_id}", get(user_details_handler))
+        .route("/admin/word-definitions", get(word_definitions_handler))
+        .route(
+            "/admin/word-definitions/edit",
+            get(word_definition_handler).post(word_definition_update_handler),
+        )
+        .route(
+            "/admin/broadcast",
+            get(broadcast_handler).post(broadcast_send_handler),
+        )
+}
@@ -0,0 +1,734 @@
+use axum::extract::{FromRequestParts, Path, Query, State};
+use axum::http::request::Parts;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use rspotify::model::{Id as _, TrackId};
+use sea_orm::ActiveEnum as _;
+use serde_json::json;
+
+use crate::app::App;
+use crate::entity::prelude::*;
+use crate::queue::webhook;
+use crate::services::{
+    ApiTokenService,
+    RateLimitAction,
+    RateLimitOutput,
+    RateLimitService,
+    RecommendasionFeedbackService,
+    SkippageService,
+    TelegramWebAppService,
+    TrackStatusService,
+    UserService,
+    UserStats,
+    UserWordWhitelistService,
+    WebhookEvent,
+};
+use crate::spotify::CurrentlyPlaying;
+use crate::spotify::scopes::Feature;
+use crate::telegram::actions;
+use crate::utils::{Clock, StringUtils as _};
+
+const MAX_PAGE_SIZE: u64 = 100;
+
+pub enum ApiError {
+    Unauthorized,
+    TooManyRequests(chrono::Duration),
+    BadRequest(String),
+    Forbidden(String),
+    NotFound,
+    Internal(anyhow::Error),
+}
+
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(err: E) -> Self {
+        Self::Internal(err.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Self::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                "Invalid or missing credentials".into(),
+            ),
+            Self::TooManyRequests(duration) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Rate limit exceeded, retry in {}s", duration.num_seconds()),
+            ),
+            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            Self::Forbidden(message) => (StatusCode::FORBIDDEN, message),
+            Self::NotFound => (StatusCode::NOT_FOUND, "Not found".into()),
+            Self::Internal(err) => {
+                tracing::error!(err = ?err, "API request failed");
+
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".into(),
+                )
+            },
+        };
+
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+type ApiResult<T = Json<serde_json::Value>> = Result<T, ApiError>;
+
+/// User authenticated by `Authorization: Bearer <token>` header
+/// or by Telegram Mini App `Authorization: tma <initData>` header
+pub struct ApiUser(UserModel);
+
+impl ApiUser {
+    async fn authenticate(app: &App, authorization: &str) -> anyhow::Result<Option<UserModel>> {
+        if let Some(token) = authorization.strip_prefix("Bearer ") {
+            return ApiTokenService::authenticate(app.db(), token.trim()).await;
+        }
+
+        let Some(init_data) = authorization
+            .strip_prefix(TelegramWebAppService::AUTH_SCHEME)
+            .and_then(|value| value.strip_prefix(' '))
+        else {
+            return Ok(None);
+        };
+
+        let Some(web_app_user) = TelegramWebAppService::validate_init_data(
+            app.bot().inner().token(),
+            init_data.trim(),
+            Clock::now().and_utc().timestamp(),
+        )?
+        else {
+            return Ok(None);
+        };
+
+        UserService::get_by_id(app.db(), &web_app_user.id.to_string()).await
+    }
+}
+
+impl FromRequestParts<&'static App> for ApiUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        app: &&'static App,
+    ) -> Result<Self, Self::Rejection> {
+        let authorization = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(ApiError::Unauthorized)?;
+
+        let user = Self::authenticate(app, authorization)
+            .await?
+            .ok_or(ApiError::Unauthorized)?;
+
+        if let RateLimitOutput::NeedToWait(duration) = RateLimitService::enforce_limit(
+            &mut app.redis_conn().await?,
+            &user.id,
+            RateLimitAction::Api,
+        )
+        .await?
+        {
+            return Err(ApiError::TooManyRequests(duration));
+        }
+
+        Ok(Self(user))
+    }
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    page: Option<u64>,
+    limit: Option<u64>,
+    /// Resolve track names via Spotify, slower
+    #[serde(default)]
+    with_names: bool,
+}
+
+fn settings_json(user: &UserModel) -> serde_json::Value {
+    json!({
+        "locale": user.locale.to_string(),
+        "check_profanity": user.cfg_check_profanity,
+        "skip_tracks": user.cfg_skip_tracks,
+        "skip_explicit": user.cfg_skip_explicit,
+        "kid_mode": user.cfg_kid_mode,
+        "skippage_enabled": user.cfg_skippage_enabled,
+        "skippage_days": chrono::Duration::seconds(user.cfg_skippage_secs).num_days(),
+        "ai_slop_detection": user.cfg_ai_slop_detection.to_value(),
+        "profile": user.profile.to_value(),
+    })
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn me(ApiUser(user): ApiUser) -> ApiResult {
+    Ok(Json(json!({
+        "id": user.id,
+        "name": user.name,
+        "status": user.status.to_value(),
+        "settings": settings_json(&user),
+    })))
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn current(State(app): State<&'static App>, ApiUser(user): ApiUser) -> ApiResult {
+    let state = app.user_state(&user.id).await?;
+
+    if !state.is_spotify_authed().await {
+        return Err(ApiError::BadRequest(
+            "Spotify account is not connected".into(),
+        ));
+    }
+
+    let track = match state.spotify().await.current_playing_wrapped().await {
+        CurrentlyPlaying::Err(err) => return Err(err.into()),
+        CurrentlyPlaying::None(reason) => {
+            return Ok(Json(json!({
+                "playing": false,
+                "reason": reason.localize(state.locale()),
+            })));
+        },
+        CurrentlyPlaying::Ok(track, ..) => track,
+    };
+
+    let status = TrackStatusService::get_status(app.db(), &user.id, track.id()).await;
+
+    Ok(Json(json!({
+        "playing": true,
+        "track": webhook::track_data(&track),
+        "status": status.to_value(),
+    })))
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn stats(State(app): State<&'static App>, ApiUser(user): ApiUser) -> ApiResult {
+    let dislikes =
+        TrackStatusService::count_status(app.db(), TrackStatus::Disliked, Some(&user.id), None)
+            .await?;
+
+    let ignored =
+        TrackStatusService::count_status(app.db(), TrackStatus::Ignore, Some(&user.id), None)
+            .await?;
+
+    let skips = TrackStatusService::sum_skips(app.db(), Some(&user.id)).await?;
+
+    let UserStats {
+        removed_playlists,
+        removed_collection,
+        lyrics_checked,
+        lyrics_found,
+        lyrics_profane,
+        lyrics_genius,
+        lyrics_musixmatch,
+        lyrics_lrclib,
+        lyrics_analyzed,
+        skipped_skippage,
+        skipped_ai_slop,
+        ai_slop_detected,
+    } = UserService::get_stats(app.db(), Some(&user.id)).await?;
+
+    Ok(Json(json!({
+        "dislikes": dislikes,
+        "ignored": ignored,
+        "skips": {
+            "disliked": skips,
+            "skippage": skipped_skippage,
+            "ai_slop": skipped_ai_slop,
+        },
+        "ai_slop_detected": ai_slop_detected,
+        "removed_playlists": removed_playlists,
+        "removed_collection": removed_collection,
+        "lyrics": {
+            "checked": lyrics_checked,
+            "found": lyrics_found,
+            "profane": lyrics_profane,
+            "analyzed": lyrics_analyzed,
+            "genius": lyrics_genius,
+            "musixmatch": lyrics_musixmatch,
+            "lrclib": lyrics_lrclib,
+        },
+    })))
+}
+
+/// Kid-safe profile locks settings which can relax the checks
+fn ensure_settings_unlocked(user: &UserModel) -> Result<(), ApiError> {
+    if user.profile.is_kid_safe() {
+        return Err(ApiError::Forbidden(
+            "Settings are locked by kid-safe profile".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn get_settings(ApiUser(user): ApiUser) -> ApiResult {
+    Ok(Json(settings_json(&user)))
+}
+
+#[derive(Deserialize)]
+struct SettingsPatch {
+    locale: Option<String>,
+    check_profanity: Option<bool>,
+    skip_tracks: Option<bool>,
+    skip_explicit: Option<bool>,
+    kid_mode: Option<bool>,
+    skippage_enabled: Option<bool>,
+    skippage_days: Option<i64>,
+    ai_slop_detection: Option<String>,
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn patch_settings(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Json(patch): Json<SettingsPatch>,
+) -> ApiResult {
+    let locale = patch
+        .locale
+        .map(|locale| {
+            locale.parse::<UserLocale>().map_err(|_| {
+                ApiError::BadRequest(format!(
+                    "Unknown locale, available: {}",
+                    UserLocale::locale_codes().join(", ")
+                ))
+            })
+        })
+        .transpose()?;
+
+    let ai_slop_detection = patch
+        .ai_slop_detection
+        .map(|value| {
+            value
+                .parse::<UserAISlopDetection>()
+                .map_err(|_| ApiError::BadRequest("Unknown ai_slop_detection value".into()))
+        })
+        .transpose()?;
+
+    if patch.check_profanity.is_some()
+        || patch.skip_tracks.is_some()
+        || patch.skip_explicit.is_some()
+        || patch.kid_mode.is_some()
+        || ai_slop_detection.is_some()
+    {
+        ensure_settings_unlocked(&user)?;
+    }
+
+    // Kid mode relies on AI age ratings
+    if patch.kid_mode == Some(true) && app.ai().is_none() {
+        return Err(ApiError::BadRequest(
+            "Kid mode is not available without AI".into(),
+        ));
+    }
+
+    if let Some(days) = patch.skippage_days
+        && !(1..=365).contains(&days)
+    {
+        return Err(ApiError::BadRequest(
+            "skippage_days must be between 1 and 365".into(),
+        ));
+    }
+
+    if let Some(locale) = locale {
+        UserService::set_locale(app.db(), &user.id, locale).await?;
+    }
+
+    if let Some(ai_slop_detection) = ai_slop_detection {
+        UserService::set_cfg_ai_slop_detection(app.db(), &user.id, ai_slop_detection).await?;
+    }
+
+    if let Some(check_profanity) = patch.check_profanity {
+        UserService::set_cfg_check_profanity(app.db(), &user.id, check_profanity).await?;
+    }
+
+    if let Some(skip_tracks) = patch.skip_tracks {
+        UserService::set_cfg_skip_tracks(app.db(), &user.id, skip_tracks).await?;
+    }
+
+    if let Some(skip_explicit) = patch.skip_explicit {
+        UserService::set_cfg_skip_explicit(app.db(), &user.id, skip_explicit).await?;
+    }
+
+    if let Some(kid_mode) = patch.kid_mode {
+        UserService::set_cfg_kid_mode(app.db(), &user.id, kid_mode).await?;
+    }
+
+    if let Some(skippage_enabled) = patch.skippage_enabled {
+        UserService::set_cfg_skippage_enabled(app.db(), &user.id, skippage_enabled).await?;
+    }
+
+    if let Some(days) = patch.skippage_days {
+        let duration = chrono::Duration::days(days);
+
+        UserService::set_cfg_skippage_secs(app.db(), &user.id, duration).await?;
+
+        if duration.num_seconds() != user.cfg_skippage_secs {
+            SkippageService::update_skippage_entries_ttl(
+                &mut app.redis_conn().await?,
+                &user.id,
+                user.cfg_skippage_secs,
+                duration.num_seconds(),
+            )
+            .await?;
+        }
+    }
+
+    let user = UserService::get_by_id(app.db(), &user.id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    // Newly enabled settings may need scopes, consent is asked in the chat
+    let state = app.user_state(&user.id).await?;
+    let features = Feature::enabled_for(&user);
+
+    if state.is_spotify_authed().await
+        && let Err(err) = actions::login::ensure_features(app, &state, &features).await
+    {
+        tracing::error!(err = ?err, "Failed to ask for Spotify consent");
+    }
+
+    Ok(Json(settings_json(&user)))
+}
+
+async fn list_tracks(
+    app: &'static App,
+    user: &UserModel,
+    status: TrackStatus,
+    params: PageParams,
+) -> ApiResult {
+    let limit = params.limit.unwrap_or(50).clamp(1, MAX_PAGE_SIZE);
+    let page = params.page.unwrap_or_default();
+
+    let statuses =
+        TrackStatusService::list_by_status(app.db(), &user.id, status, page, limit).await?;
+
+    let mut state = None;
+    if params.with_names {
+        let user_state = app.user_state(&user.id).await?;
+
+        if user_state.is_spotify_authed().await {
+            state = Some(user_state);
+        }
+    }
+
+    let mut tracks = vec![];
+    for track_status in statuses {
+        let mut track = json!({
+            "track_id": track_status.track_id,
+            "skips": track_status.skips,
+            "updated_at": track_status.updated_at.and_utc().to_rfc3339(),
+        });
+
+        if let Some(state) = &state
+            && let Ok(track_id) = TrackId::from_id(&track_status.track_id)
+        {
+            match state
+                .spotify()
+                .await
+                .short_track_cached(&mut app.redis_conn().await?, track_id)
+                .await
+            {
+                Ok(short_track) => {
+                    track["name"] = json!(short_track.name_with_artists());
+                    track["url"] = json!(short_track.url());
+                },
+                Err(err) => {
+                    tracing::warn!(err = ?err, "Failed to fetch track name");
+                },
+            }
+        }
+
+        tracks.push(track);
+    }
+
+    Ok(Json(json!({
+        "page": page,
+        "limit": limit,
+        "tracks": tracks,
+    })))
+}
+
+async fn set_track_status(
+    app: &'static App,
+    user: &UserModel,
+    track_id: &str,
+    status: TrackStatus,
+) -> ApiResult {
+    let track_id = TrackId::from_id(track_id)
+        .map_err(|_| ApiError::BadRequest("Invalid Spotify track id".into()))?;
+
+    TrackStatusService::set_status(app.db(), &user.id, track_id.id(), status).await?;
+
+    if status == TrackStatus::Disliked {
+        let state = app.user_state(&user.id).await?;
+
+        match state
+            .spotify()
+            .await
+            .short_track_cached(&mut app.redis_conn().await?, track_id.clone())
+            .await
+        {
+            Ok(track) => {
+                webhook::emit(
+                    app,
+                    &user.id,
+                    WebhookEvent::TrackDisliked,
+                    webhook::track_data(&track),
+                )
+                .await;
+
+                if let Err(err) = RecommendasionFeedbackService::record_reaction(
+                    app.db(),
+                    &mut app.redis_conn().await?,
+                    &user.id,
+                    &track,
+                    RecommendasionOutcome::Disliked,
+                )
+                .await
+                {
+                    tracing::error!(err = ?err, "Failed to record Recommendasion feedback");
+                }
+            },
+            Err(err) => {
+                tracing::warn!(err = ?err, "Failed to fetch disliked track for webhook");
+            },
+        }
+    }
+
+    Ok(Json(json!({
+        "track_id": track_id.id(),
+        "status": status.to_value(),
+    })))
+}
+
+async fn remove_track_status(
+    app: &'static App,
+    user: &UserModel,
+    track_id: &str,
+    status: TrackStatus,
+) -> ApiResult {
+    if TrackStatusService::get_status(app.db(), &user.id, track_id).await != status {
+        return Err(ApiError::NotFound);
+    }
+
+    set_track_status(app, user, track_id, TrackStatus::None).await
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn list_dislikes(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Query(params): Query<PageParams>,
+) -> ApiResult {
+    list_tracks(app, &user, TrackStatus::Disliked, params).await
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id, %track_id))]
+async fn add_dislike(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Path(track_id): Path<String>,
+) -> ApiResult {
+    set_track_status(app, &user, &track_id, TrackStatus::Disliked).await
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id, %track_id))]
+async fn remove_dislike(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Path(track_id): Path<String>,
+) -> ApiResult {
+    remove_track_status(app, &user, &track_id, TrackStatus::Disliked).await
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn list_ignored(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Query(params): Query<PageParams>,
+) -> ApiResult {
+    list_tracks(app, &user, TrackStatus::Ignore, params).await
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id, %track_id))]
+async fn add_ignored(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Path(track_id): Path<String>,
+) -> ApiResult {
+    ensure_settings_unlocked(&user)?;
+
+    set_track_status(app, &user, &track_id, TrackStatus::Ignore).await
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id, %track_id))]
+async fn remove_ignored(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Path(track_id): Path<String>,
+) -> ApiResult {
+    remove_track_status(app, &user, &track_id, TrackStatus::Ignore).await
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn list_whitelist(State(app): State<&'static App>, ApiUser(user): ApiUser) -> ApiResult {
+    let mut words: Vec<_> = UserWordWhitelistService::get_ok_words_for_user(app.db(), &user.id)
+        .await?
+        .into_iter()
+        .collect();
+
+    words.sort();
+
+    Ok(Json(json!({ "words": words })))
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn add_whitelist_word(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Path(word): Path<String>,
+) -> ApiResult {
+    ensure_settings_unlocked(&user)?;
+
+    let word = word.trim().to_lowercase();
+
+    if word.is_empty() || word.chars_len() > UserWordWhitelistService::MAX_WORD_LENGTH {
+        return Err(ApiError::BadRequest(format!(
+            "Word must be between 1 and {} characters",
+            UserWordWhitelistService::MAX_WORD_LENGTH
+        )));
+    }
+
+    let count = UserWordWhitelistService::count_ok_words_for_user(app.db(), &user.id).await?;
+
+    if count >= UserWordWhitelistService::MAX_WORDS {
+        return Err(ApiError::BadRequest(format!(
+            "Whitelist is limited to {} words",
+            UserWordWhitelistService::MAX_WORDS
+        )));
+    }
+
+    let added =
+        UserWordWhitelistService::add_ok_word_for_user(app.db(), user.id, word.clone()).await?;
+
+    Ok(Json(json!({ "word": word, "added": added })))
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn remove_whitelist_word(
+    State(app): State<&'static App>,
+    ApiUser(user): ApiUser,
+    Path(word): Path<String>,
+) -> ApiResult {
+    if !UserWordWhitelistService::remove_ok_word_for_user(app.db(), &user.id, &word).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Json(
+        json!({ "word": word.to_lowercase(), "removed": true }),
+    ))
+}
+
+pub fn router() -> Router<&'static App> {
+    Router::new()
+        .route("/me", get(me))
+        .route("/current", get(current))
+        .route("/stats", get(stats))
+        .route("/settings", get(get_settings).patch(patch_settings))
+        .route("/dislikes", get(list_dislikes))
+        .route(
+            "/dislikes/{track_id}",
+            put(add_dislike).delete(remove_dislike),
+        )
+        .route("/ignored", get(list_ignored))
+        .route(
+            "/ignored/{track_id}",
+            put(add_ignored).delete(remove_ignored),
+        )
+        .route("/whitelist", get(list_whitelist))
+        .route(
+            "/whitelist/{word}",
+            put(add_whitelist_word).delete(remove_whitelist_word),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    const TRACK_ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+
+    async fn kid_safe_user(test: &TestApp) -> UserModel {
+        let user_id = TestApp::user_id();
+        test.login(&user_id).await.unwrap();
+
+        UserService::set_profile(test.app.db(), &user_id, UserProfile::KidSafe, Some("1234"))
+            .await
+            .unwrap();
+
+        UserService::get_by_id(test.app.db(), &user_id)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_kid_safe_locks_whitelist() {
+        let test = TestApp::start().await.unwrap();
+        let user = kid_safe_user(&test).await;
+
+        let res = add_whitelist_word(
+            State(test.app),
+            ApiUser(user.clone()),
+            Path("damn".to_owned()),
+        )
+        .await;
+
+        assert!(matches!(res, Err(ApiError::Forbidden(_))));
+        assert!(
+            UserWordWhitelistService::get_ok_words_for_user(test.app.db(), &user.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_kid_safe_locks_ignored() {
+        let test = TestApp::start().await.unwrap();
+        let user = kid_safe_user(&test).await;
+
+        let res = add_ignored(
+            State(test.app),
+            ApiUser(user.clone()),
+            Path(TRACK_ID.to_owned()),
+        )
+        .await;
+
+        assert!(matches!(res, Err(ApiError::Forbidden(_))));
+        assert_eq!(
+            TrackStatusService::get_status(test.app.db(), &user.id, TRACK_ID).await,
+            TrackStatus::None
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL and TEST_REDIS_URL"]
+    async fn test_kid_safe_locks_kid_mode() {
+        let test = TestApp::start().await.unwrap();
+        let user = kid_safe_user(&test).await;
+
+        let res = patch_settings(
+            State(test.app),
+            ApiUser(user.clone()),
+            Json(serde_json::from_value(json!({ "kid_mode": false })).unwrap()),
+        )
+        .await;
+
+        assert!(matches!(res, Err(ApiError::Forbidden(_))));
+    }
+}
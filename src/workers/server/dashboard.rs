@@ -0,0 +1,244 @@
+use axum::Router;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::{Html, IntoResponse as _, Response};
+use axum::routing::get;
+use chrono::{Duration, NaiveDate};
+use indoc::formatdoc;
+use itertools::Itertools as _;
+use teloxide::utils::html;
+
+use super::web::{internal_error, link_expired, login, page, session_user_id};
+use crate::app::App;
+use crate::entity::prelude::*;
+use crate::services::{
+    TrackLanguageStatsService,
+    TrackStatusService,
+    UserProfaneWordStatsService,
+    UserService,
+    UserStats,
+};
+use crate::utils::Clock;
+
+const DAYS: i64 = 30;
+
+#[derive(Deserialize)]
+struct LoginParams {
+    token: String,
+}
+
+#[tracing::instrument(skip_all)]
+async fn login_handler(
+    State(app): State<&'static App>,
+    Query(params): Query<LoginParams>,
+) -> Response {
+    login(app, &params.token, "/dashboard")
+        .await
+        .unwrap_or_else(|err| internal_error(&err))
+}
+
+#[tracing::instrument(skip_all)]
+async fn dashboard_handler(State(app): State<&'static App>, headers: HeaderMap) -> Response {
+    let res = async {
+        let Some(user_id) = session_user_id(app, &headers).await? else {
+            return Ok(link_expired());
+        };
+
+        let Some(user) = UserService::get_by_id(app.db(), &user_id).await? else {
+            return Ok(link_expired());
+        };
+
+        Ok::<_, anyhow::Error>(Html(render(app, &user).await?).into_response())
+    };
+
+    res.await.unwrap_or_else(|err| internal_error(&err))
+}
+
+/// Fills days without data with zeros
+fn fill_days(data: &[(NaiveDate, i64)], from: NaiveDate, to: NaiveDate) -> Vec<(NaiveDate, i64)> {
+    from.iter_days()
+        .take_while(|day| *day <= to)
+        .map(|day| {
+            let count = data
+                .iter()
+                .find(|(date, _)| *date == day)
+                .map_or(0, |(_, count)| *count);
+
+            (day, count)
+        })
+        .collect()
+}
+
+fn columns_chart(data: &[(NaiveDate, i64)]) -> String {
+    let max = data
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let columns = data
+        .iter()
+        .map(|(day, count)| {
+            format!(
+                r#"<div class="column" title="{day}: {count}"><div style="height: {height}%"></div><span>{label}</span></div>"#,
+                height = count * 100 / max,
+                label = day.format("%d"),
+            )
+        })
+        .join("");
+
+    format!(r#"<div class="columns">{columns}</div>"#)
+}
+
+fn bars_chart(data: &[(String, i64)], no_data: &str) -> String {
+    if data.iter().all(|(_, value)| *value == 0) {
+        return format!(r#"<p class="muted">{no_data}</p>"#);
+    }
+
+    let max = data
+        .iter()
+        .map(|(_, value)| *value)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let rows = data
+        .iter()
+        .map(|(label, value)| {
+            format!(
+                r#"<div class="bar"><span class="label">{label}</span><div class="track"><div style="width: {width}%"></div></div><span class="value">{value}</span></div>"#,
+                label = html::escape(label),
+                width = value * 100 / max,
+            )
+        })
+        .join("");
+
+    format!(r#"<div class="bars">{rows}</div>"#)
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id))]
+async fn render(app: &App, user: &UserModel) -> anyhow::Result<String> {
+    let locale = user.locale.as_ref();
+    let no_data = t!("dashboard.no-data", locale = locale);
+
+    let today = Clock::now().date();
+    let from = today - Duration::days(DAYS - 1);
+
+    let dislikes_by_day = TrackStatusService::count_status_by_day(
+        app.db(),
+        &user.id,
+        TrackStatus::Disliked,
+        from.and_time(chrono::NaiveTime::MIN),
+    )
+    .await?;
+
+    let dislikes =
+        TrackStatusService::count_status(app.db(), TrackStatus::Disliked, Some(&user.id), None)
+            .await?;
+
+    let ignored =
+        TrackStatusService::count_status(app.db(), TrackStatus::Ignore, Some(&user.id), None)
+            .await?;
+
+    let skipped_disliked = TrackStatusService::sum_skips(app.db(), Some(&user.id)).await?;
+
+    let UserStats {
+        lyrics_checked,
+        lyrics_profane,
+        skipped_skippage,
+        skipped_ai_slop,
+        ai_slop_detected,
+        ..
+    } = UserService::get_stats(app.db(), Some(&user.id)).await?;
+
+    let profane_words = UserProfaneWordStatsService::top_for_user(app.db(), &user.id, 15)
+        .await?
+        .into_iter()
+        .map(|(word, occurrences)| (word, i64::from(occurrences)))
+        .collect_vec();
+
+    let languages = TrackLanguageStatsService::stats_for_user(app.db(), &user.id, Some(10))
+        .await?
+        .into_iter()
+        .map(|(lang, count)| {
+            (
+                lang.map_or("Unknown", |lang| lang.to_name()).to_owned(),
+                i64::from(count),
+            )
+        })
+        .collect_vec();
+
+    let skips = vec![
+        (
+            t!("dashboard.skip-reason-disliked", locale = locale).to_string(),
+            skipped_disliked,
+        ),
+        (
+            t!("dashboard.skip-reason-skippage", locale = locale).to_string(),
+            skipped_skippage,
+        ),
+        (
+            t!("dashboard.skip-reason-ai-slop", locale = locale).to_string(),
+            skipped_ai_slop,
+        ),
+    ];
+
+    let body = formatdoc!(
+        r#"
+            <h1>{title}</h1>
+            <section class="summary">
+            <div><b>{dislikes}</b>{dislikes_label}</div>
+            <div><b>{ignored}</b>{ignored_label}</div>
+            <div><b>{lyrics_checked}</b>{lyrics_checked_label}</div>
+            <div><b>{lyrics_profane}</b>{lyrics_profane_label}</div>
+            <div><b>{ai_slop_detected}</b>{ai_slop_label}</div>
+            </section>
+            <section><h2>{dislikes_over_time}</h2>{dislikes_chart}</section>
+            <section><h2>{skips_by_reason}</h2>{skips_chart}</section>
+            <section><h2>{top_profane_words}</h2>{profane_words_chart}</section>
+            <section><h2>{languages_title}</h2>{languages_chart}</section>
+        "#,
+        title = t!(
+            "dashboard.title",
+            locale = locale,
+            name = html::escape(&user.name)
+        ),
+        dislikes_label = t!("dashboard.dislikes", locale = locale),
+        ignored_label = t!("dashboard.ignored", locale = locale),
+        lyrics_checked_label = t!("dashboard.lyrics-checked", locale = locale),
+        lyrics_profane_label = t!("dashboard.lyrics-profane", locale = locale),
+        ai_slop_label = t!("dashboard.ai-slop-detected", locale = locale),
+        dislikes_over_time = t!("dashboard.dislikes-over-time", locale = locale, days = DAYS),
+        dislikes_chart = columns_chart(&fill_days(&dislikes_by_day, from, today)),
+        skips_by_reason = t!("dashboard.skips-by-reason", locale = locale),
+        skips_chart = bars_chart(&skips, &no_data),
+        top_profane_words = t!("dashboard.top-profane-words", locale = locale),
+        profane_words_chart = bars_chart(&profane_words, &no_data),
+        languages_title = t!("dashboard.languages", locale = locale),
+        languages_chart = bars_chart(&languages, &no_data),
+    );
+
+    Ok(page("Rustify", &body))
+}
+
+pub fn router() -> Router<&'static App> {
+    Router::new()
+        .route("/dashboard", get(dashboard_handler))
+        .route("/dashboard/login", get(login_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_days() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+
+        assert_eq!(
+            fill_days(&[(day(2), 3), (day(4), 1)], day(1), day(4)),
+            vec![(day(1), 0), (day(2), 3), (day(3), 0), (day(4), 1)]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{Html, IntoResponse as _, Redirect, Response};
+use indoc::formatdoc;
+use teloxide::utils::html;
+
+use crate::app::App;
+use crate::services::WebSessionService;
+
+/// Finds value of the cookie in `Cookie` headers
+pub fn get_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Returns user id of the session from cookie
+pub async fn session_user_id(app: &App, headers: &HeaderMap) -> anyhow::Result<Option<String>> {
+    let Some(session) = get_cookie(headers, WebSessionService::COOKIE_NAME) else {
+        return Ok(None);
+    };
+
+    WebSessionService::get_session_user(&mut app.redis_conn().await?, session).await
+}
+
+/// Exchanges one-time login token for session cookie and redirects to `redirect_to`
+pub async fn login(app: &App, token: &str, redirect_to: &str) -> anyhow::Result<Response> {
+    let mut redis_conn = app.redis_conn().await?;
+
+    let Some(user_id) = WebSessionService::consume_login_token(&mut redis_conn, token).await?
+    else {
+        return Ok(link_expired());
+    };
+
+    let session = WebSessionService::create_session(&mut redis_conn, &user_id).await?;
+
+    let secure = if app
+        .server_public_url()
+        .is_some_and(|url| url.starts_with("https://"))
+    {
+        "; Secure"
+    } else {
+        ""
+    };
+
+    let cookie = format!(
+        "{name}={session}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Lax{secure}",
+        name = WebSessionService::COOKIE_NAME,
+        max_age = WebSessionService::session_ttl().num_seconds(),
+    );
+
+    Ok(([(header::SET_COOKIE, cookie)], Redirect::to(redirect_to)).into_response())
+}
+
+/// Escapes text to be used inside of double-quoted attribute value
+pub fn escape_attr(text: &str) -> String {
+    html::escape(text).replace('"', "&quot;")
+}
+
+pub fn link_expired() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Html(page(
+            "Rustify",
+            "<p>Link is expired or already used. Request a new one from the bot.</p>",
+        )),
+    )
+        .into_response()
+}
+
+pub fn internal_error(err: &anyhow::Error) -> Response {
+    tracing::error!(err = ?err, "Failed to render page");
+
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Html(page(
+            "Rustify",
+            "<p>Something went wrong. Try again later.</p>",
+        )),
+    )
+        .into_response()
+}
+
+pub fn page(title: &str, body: &str) -> String {
+    formatdoc!(
+        r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+            <meta charset="utf-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1">
+            <title>{title}</title>
+            <style>
+            body {{ font-family: system-ui, sans-serif; max-width: 860px; margin: 0 auto; padding: 16px; background: #121212; color: #eee; }}
+            h1 {{ color: #1db954; }}
+            section {{ background: #1e1e1e; border-radius: 8px; padding: 12px 16px; margin-bottom: 16px; }}
+            .muted {{ color: #888; }}
+            .summary {{ display: flex; flex-wrap: wrap; gap: 12px; }}
+            .summary div {{ flex: 1 1 120px; }}
+            .summary b {{ display: block; font-size: 1.6em; color: #1db954; }}
+            .columns {{ display: flex; align-items: flex-end; height: 160px; gap: 2px; }}
+            .column {{ flex: 1; height: 100%; display: flex; flex-direction: column; justify-content: flex-end; }}
+            .column div {{ background: #e22134; min-height: 1px; }}
+            .column span {{ font-size: 0.6em; color: #888; text-align: center; }}
+            .bar {{ display: flex; align-items: center; gap: 8px; margin: 4px 0; }}
+            .bar .label {{ width: 140px; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }}
+            .bar .track {{ flex: 1; background: #2a2a2a; border-radius: 4px; }}
+            .bar .track div {{ background: #1db954; height: 14px; border-radius: 4px; }}
+            .bar .value {{ width: 60px; text-align: right; }}
+            a {{ color: #1db954; }}
+            nav {{ display: flex; gap: 16px; margin-bottom: 16px; }}
+            table {{ width: 100%; border-collapse: collapse; }}
+            th, td {{ text-align: left; padding: 6px 8px; border-bottom: 1px solid #2a2a2a; vertical-align: top; }}
+            form.inline {{ display: flex; flex-wrap: wrap; gap: 8px; margin-bottom: 12px; }}
+            input, select, textarea, button {{ background: #2a2a2a; color: #eee; border: 1px solid #444; border-radius: 4px; padding: 6px 8px; font: inherit; }}
+            textarea {{ width: 100%; box-sizing: border-box; min-height: 200px; }}
+            button {{ cursor: pointer; }}
+            .pre {{ white-space: pre-line; }}
+            .pages {{ display: flex; gap: 16px; margin-top: 12px; }}
+            </style>
+            </head>
+            <body>
+            {body}
+            </body>
+            </html>
+        "#,
+        title = html::escape(title),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, "a=1; rustify_session=abc".parse().unwrap());
+
+        assert_eq!(get_cookie(&headers, "rustify_session"), Some("abc"));
+        assert_eq!(get_cookie(&headers, "b"), None);
+    }
+
+    #[test]
+    fn test_escape_attr() {
+        assert_eq!(escape_attr(r#"a"<b>&"#), "a&quot;&lt;b&gt;&amp;");
+    }
+}
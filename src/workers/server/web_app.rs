@@ -0,0 +1,266 @@
+use axum::Router;
+use axum::response::Html;
+use axum::routing::get;
+use indoc::formatdoc;
+use serde_json::json;
+
+use crate::app::App;
+use crate::entity::prelude::UserLocale;
+
+const TRANSLATIONS: &[&str] = &[
+    "settings",
+    "language",
+    "check-profanity",
+    "skip-tracks",
+    "skip-explicit",
+    "kid-mode",
+    "kid-safe",
+    "settings-locked",
+    "skippage",
+    "skippage-days",
+    "ai-slop-detection",
+    "dislikes",
+    "undo",
+    "load-more",
+    "whitelist",
+    "whitelist-placeholder",
+    "add",
+    "remove",
+    "empty",
+    "error",
+    "open-in-telegram",
+];
+
+/// Strings for all locales, the app picks one after loading user settings
+fn translations() -> serde_json::Value {
+    let locales = UserLocale::locale_codes()
+        .into_iter()
+        .map(|locale| {
+            let mut strings: serde_json::Map<_, _> = TRANSLATIONS
+                .iter()
+                .map(|key| {
+                    (
+                        (*key).to_owned(),
+                        json!(t!(format!("web-app.{key}"), locale = locale)),
+                    )
+                })
+                .collect();
+
+            for value in ["notify", "ignore", "skip"] {
+                strings.insert(
+                    format!("ai-slop-{value}"),
+                    json!(t!(
+                        format!("ai-slop.button-{value}"),
+                        locale = locale,
+                        mark = ""
+                    )),
+                );
+            }
+
+            (locale, serde_json::Value::Object(strings))
+        })
+        .collect();
+
+    serde_json::Value::Object(locales)
+}
+
+#[tracing::instrument(skip_all)]
+async fn web_app_handler() -> Html<String> {
+    Html(formatdoc!(
+        r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+            <meta charset="utf-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1">
+            <title>Rustify</title>
+            <script src="https://telegram.org/js/telegram-web-app.js"></script>
+            <style>
+            body {{ font-family: system-ui, sans-serif; margin: 0; padding: 12px; background: var(--tg-theme-bg-color, #fff); color: var(--tg-theme-text-color, #000); }}
+            h2 {{ font-size: 1.1em; margin: 20px 0 8px; color: var(--tg-theme-section-header-text-color, #1db954); }}
+            section {{ background: var(--tg-theme-secondary-bg-color, #f2f2f2); border-radius: 10px; padding: 4px 12px; }}
+            .row {{ display: flex; align-items: center; justify-content: space-between; gap: 8px; padding: 8px 0; border-bottom: 1px solid var(--tg-theme-hint-color, #ccc); }}
+            .row:last-child {{ border-bottom: none; }}
+            .row a {{ color: var(--tg-theme-link-color, #1db954); overflow: hidden; text-overflow: ellipsis; }}
+            .muted {{ color: var(--tg-theme-hint-color, #888); padding: 8px 0; }}
+            button {{ background: var(--tg-theme-button-color, #1db954); color: var(--tg-theme-button-text-color, #fff); border: none; border-radius: 6px; padding: 6px 10px; font: inherit; }}
+            input, select {{ font: inherit; padding: 4px 6px; border-radius: 6px; border: 1px solid var(--tg-theme-hint-color, #ccc); background: var(--tg-theme-bg-color, #fff); color: inherit; }}
+            input[type=number] {{ width: 70px; }}
+            button:disabled, input:disabled, select:disabled {{ opacity: 0.5; }}
+            </style>
+            </head>
+            <body>
+            <div id="app"></div>
+            <script>
+            const I18N = {translations};
+            const tg = window.Telegram.WebApp;
+            const root = document.getElementById("app");
+            let t = I18N[tg.initDataUnsafe?.user?.language_code] ?? I18N.en;
+            let settings = null;
+            let dislikesPage = 0;
+
+            function el(tag, attrs = {{}}, ...children) {{
+                const node = document.createElement(tag);
+                Object.assign(node, attrs);
+                node.append(...children);
+                return node;
+            }}
+
+            async function api(method, path, body) {{
+                const res = await fetch("/api/v1" + path, {{
+                    method,
+                    headers: {{ "Authorization": "tma " + tg.initData, "Content-Type": "application/json" }},
+                    body: body === undefined ? undefined : JSON.stringify(body),
+                }});
+                const data = await res.json();
+                if (!res.ok) {{
+                    tg.showAlert(data.error ?? t.error);
+                    throw new Error(data.error);
+                }}
+                return data;
+            }}
+
+            async function patch(changes) {{
+                settings = await api("PATCH", "/settings", changes);
+                if (changes.locale) {{
+                    t = I18N[settings.locale] ?? t;
+                    await render();
+                }}
+            }}
+
+            // Kid-safe profile turns these settings on and locks them
+            function locked() {{
+                return settings.profile === "kid_safe";
+            }}
+
+            function toggle(title, key, lockable = true) {{
+                const isLocked = lockable && locked();
+                const input = el("input", {{ type: "checkbox", checked: settings[key] || isLocked, disabled: isLocked }});
+                input.onchange = () => patch({{ [key]: input.checked }});
+                return el("label", {{ className: "row" }}, title, input);
+            }}
+
+            function settingsSection() {{
+                const language = el("select", {{}}, ...Object.keys(I18N).map((code) =>
+                    el("option", {{ value: code, selected: code === settings.locale }}, code)));
+                language.onchange = () => patch({{ locale: language.value }});
+
+                const aiSlopValue = locked() ? "skip" : settings.ai_slop_detection;
+                const aiSlop = el("select", {{ disabled: locked() }}, ...["notify", "ignore", "skip"].map((value) =>
+                    el("option", {{ value, selected: value === aiSlopValue }}, t["ai-slop-" + value])));
+                aiSlop.onchange = () => patch({{ ai_slop_detection: aiSlop.value }});
+
+                const days = el("input", {{ type: "number", min: 1, max: 365, value: settings.skippage_days }});
+                days.onchange = () => patch({{ skippage_days: Number(days.value) }});
+
+                const section = el("section", {{}},
+                    el("label", {{ className: "row" }}, t.language, language),
+                    toggle(t["check-profanity"], "check_profanity"),
+                    toggle(t["skip-tracks"], "skip_tracks"),
+                    toggle(t["skip-explicit"], "skip_explicit"),
+                    toggle(t["kid-mode"], "kid_mode"),
+                    toggle(t.skippage, "skippage_enabled", false),
+                    el("label", {{ className: "row" }}, t["skippage-days"], days),
+                    el("label", {{ className: "row" }}, t["ai-slop-detection"], aiSlop),
+                );
+                if (locked()) {{
+                    section.prepend(el("div", {{ className: "row" }}, t["kid-safe"]),
+                        el("div", {{ className: "muted" }}, t["settings-locked"]));
+                }}
+                return section;
+            }}
+
+            async function loadDislikes(section, more) {{
+                const data = await api("GET", `/dislikes?page=${{dislikesPage}}&limit=20&with_names=true`);
+                more.remove();
+                if (dislikesPage === 0 && data.tracks.length === 0) {{
+                    section.append(el("div", {{ className: "muted" }}, t.empty));
+                }}
+                for (const track of data.tracks) {{
+                    const undo = el("button", {{}}, t.undo);
+                    const row = el("div", {{ className: "row" }},
+                        el("a", {{ href: track.url ?? `https://open.spotify.com/track/${{track.track_id}}`, target: "_blank" }}, track.name ?? track.track_id),
+                        undo);
+                    undo.onclick = async () => {{
+                        await api("DELETE", "/dislikes/" + track.track_id);
+                        row.remove();
+                    }};
+                    section.append(row);
+                }}
+                if (data.tracks.length === data.limit) {{
+                    dislikesPage += 1;
+                    section.append(more);
+                }}
+            }}
+
+            function dislikesSection() {{
+                const section = el("section");
+                const more = el("button", {{}}, t["load-more"]);
+                more.onclick = () => loadDislikes(section, more);
+                dislikesPage = 0;
+                loadDislikes(section, more);
+                return section;
+            }}
+
+            async function whitelistSection() {{
+                const section = el("section");
+                const {{ words }} = await api("GET", "/whitelist");
+                const input = el("input", {{ placeholder: t["whitelist-placeholder"] }});
+                const add = el("button", {{ disabled: locked() }}, t.add);
+                add.onclick = async () => {{
+                    if (!input.value.trim()) return;
+                    await api("PUT", "/whitelist/" + encodeURIComponent(input.value.trim()));
+                    section.replaceWith(await whitelistSection());
+                }};
+                section.append(el("div", {{ className: "row" }}, input, add));
+                if (words.length === 0) {{
+                    section.append(el("div", {{ className: "muted" }}, t.empty));
+                }}
+                for (const word of words) {{
+                    const remove = el("button", {{ disabled: locked() }}, t.remove);
+                    const row = el("div", {{ className: "row" }}, word, remove);
+                    remove.onclick = async () => {{
+                        await api("DELETE", "/whitelist/" + encodeURIComponent(word));
+                        row.remove();
+                    }};
+                    section.append(row);
+                }}
+                return section;
+            }}
+
+            async function render() {{
+                root.replaceChildren(
+                    el("h2", {{}}, t.settings), settingsSection(),
+                    el("h2", {{}}, t.dislikes), dislikesSection(),
+                    el("h2", {{}}, t.whitelist), await whitelistSection(),
+                );
+            }}
+
+            async function main() {{
+                tg.ready();
+                tg.expand();
+                if (!tg.initData) {{
+                    root.append(el("p", {{ className: "muted" }}, t["open-in-telegram"]));
+                    return;
+                }}
+                try {{
+                    settings = await api("GET", "/settings");
+                    t = I18N[settings.locale] ?? t;
+                    await render();
+                }} catch (err) {{
+                    root.replaceChildren(el("p", {{ className: "muted" }}, t.error));
+                }}
+            }}
+
+            main();
+            </script>
+            </body>
+            </html>
+        "#,
+        translations = translations(),
+    ))
+}
+
+pub fn router() -> Router<&'static App> {
+    Router::new().route("/app", get(web_app_handler))
+}